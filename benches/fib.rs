@@ -0,0 +1,69 @@
+#![feature(test)]
+
+extern crate test;
+
+use lemon_lisp::{
+    evaluator::Evaluator,
+    internal::{math, Function, InternalFunction},
+    interpreter::{Backend, Interpreter},
+    lexer::TokenStream,
+    model::{Closure, Environment, Name, Params, Value},
+    parser::Parser,
+};
+use test::Bencher;
+
+const FIB: &str = "(define (fib n)
+                     (cond ((= n 0) 0)
+                           ((= n 1) 1)
+                           (else (+ (fib (- n 1)) (fib (- n 2))))))";
+
+const FIB_BODY: &str = "(cond ((= n 0) 0)
+                              ((= n 1) 1)
+                              (else (+ (fib (- n 1)) (fib (- n 2)))))";
+
+fn parse(input: &str) -> Vec<Value> {
+    Parser::new(TokenStream::new(input)).parse().unwrap()
+}
+
+fn bench_fib(bencher: &mut Bencher, backend: Backend) {
+    let interpreter = Interpreter::new().with_backend(backend);
+    interpreter.eval(FIB).unwrap();
+    bencher.iter(|| interpreter.eval("(fib 15)").unwrap());
+}
+
+#[bench]
+fn fib_tree_walking(bencher: &mut Bencher) {
+    bench_fib(bencher, Backend::TreeWalking);
+}
+
+#[bench]
+fn fib_bytecode(bencher: &mut Bencher) {
+    bench_fib(bencher, Backend::Bytecode);
+}
+
+// 作为对照：`Closure::new` 创建的闭包没有预先分析，每次调用都重新分析过程体，
+// 与逐次检查语法、按名称查找变量的直接求值一样，每次求值都要重复这些工作
+#[bench]
+fn fib_direct(bencher: &mut Bencher) {
+    let env = Environment::new();
+    for (name, function) in [
+        ("+", math::add as Function),
+        ("-", math::sub),
+        ("=", math::numeric_equal),
+    ] {
+        env.set(
+            name,
+            Value::InternalFunction(InternalFunction { name, function }),
+        );
+    }
+    let fib = Closure::new(
+        Some("fib".to_string()),
+        Params::from(vec![Name::from("n")]),
+        parse(FIB_BODY),
+        &env,
+    );
+    env.set("fib", Value::Closure(fib));
+
+    let call = parse("(fib 15)").remove(0);
+    bencher.iter(|| Evaluator.eval_value(&call, &env).unwrap());
+}
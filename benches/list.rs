@@ -0,0 +1,66 @@
+#![feature(test)]
+
+extern crate test;
+
+use lemon_lisp::{
+    interpreter::{Backend, Interpreter},
+    model::{List, Value},
+};
+use rug::Integer;
+use test::{black_box, Bencher};
+
+const SUM: &str = "(define (sum lst)
+                     (if (null? lst)
+                         0
+                         (+ (car lst) (sum (cdr lst)))))";
+
+// 递归地对字面量列表求和，每次调用都会查找 `sum` 并取列表的尾部
+fn bench_sum(bencher: &mut Bencher, backend: Backend) {
+    let interpreter = Interpreter::new().with_backend(backend);
+    interpreter.eval(SUM).unwrap();
+    let items: Vec<String> = (0..500).map(|i| i.to_string()).collect();
+    interpreter
+        .eval(&format!("(define data '({}))", items.join(" ")))
+        .unwrap();
+    bencher.iter(|| interpreter.eval("(sum data)").unwrap());
+}
+
+#[bench]
+fn list_sum_tree_walking(bencher: &mut Bencher) {
+    bench_sum(bencher, Backend::TreeWalking);
+}
+
+#[bench]
+fn list_sum_bytecode(bencher: &mut Bencher) {
+    bench_sum(bencher, Backend::Bytecode);
+}
+
+fn items() -> Vec<Value> {
+    (0..500).map(|i| Value::from(Integer::from(i))).collect()
+}
+
+// 作为对照：每次取尾部都复制剩余的元素，遍历整个列表的时间与长度的平方成正比
+#[bench]
+fn list_cdr_copy(bencher: &mut Bencher) {
+    let items = items();
+    bencher.iter(|| {
+        let mut rest = items.clone();
+        while let Some((first, tail)) = rest.split_first() {
+            black_box(first);
+            rest = tail.to_vec();
+        }
+    });
+}
+
+// 尾部与原列表共享元素，每次取尾部只需移动起始位置
+#[bench]
+fn list_cdr_shared(bencher: &mut Bencher) {
+    let list = List::from(items());
+    bencher.iter(|| {
+        let mut rest = list.clone();
+        while let Some(first) = rest.first() {
+            black_box(first);
+            rest = rest.tail();
+        }
+    });
+}
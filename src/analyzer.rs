@@ -0,0 +1,687 @@
+//! 将表达式分析为树遍历求值器使用的语法树
+//!
+//! 与字节码编译器一样，分析时确定每个变量引用所在的作用域并展开宏调用，
+//! 求值时局部变量按环境中的层数与位置访问，特殊形式也不必再逐次匹配。
+
+use std::rc::Rc;
+
+use crate::{
+    evaluator::Evaluator,
+    expander,
+    model::{
+        Body, Call, Clause, Closure, Consequent, Environment, Frame, Guard, Keyword, Lambda, Let,
+        Letrec, List, NamedLet, Node, NodeKind, Params, ParseErrorKind, RuntimeError,
+        RuntimeErrorKind, Span, Symbol, Test, Token, Value, Variable,
+    },
+    scope::{
+        environment, is_auxiliary, is_same_binding, keyword, resolve, scan_defines, Binding, Scope,
+    },
+};
+
+type AnalyzeResult = Result<Rc<Node>, RuntimeError>;
+
+pub struct Analyzer<'a> {
+    // 求值时的顶层环境，分析时在这里查找全局的宏
+    global: &'a Rc<Environment>,
+}
+
+impl<'a> Analyzer<'a> {
+    pub fn new(global: &'a Rc<Environment>) -> Self {
+        Self { global }
+    }
+
+    /// 分析顶层表达式
+    ///
+    /// 语法错误与宏展开时的错误不会立即返回，而是分析为引发该错误的节点，
+    /// 在求值到出错的表达式时才报告。
+    #[must_use]
+    pub fn analyze(&self, expr: &Value) -> Rc<Node> {
+        self.analyze_expr(expr, None)
+    }
+
+    /// 分析没有经过分析就创建的闭包，例如宏的转换器
+    #[must_use]
+    pub fn analyze_closure(&self, closure: &Closure) -> Rc<Lambda> {
+        self.analyze_lambda(
+            closure.name.clone(),
+            Rc::clone(&closure.params),
+            &closure.body,
+            closure.span,
+            None,
+        )
+    }
+
+    /// 展开一次顶层的宏调用，`form` 不是宏调用时返回 `None`
+    pub fn macroexpand_1(&self, form: &List) -> Result<Option<Value>, RuntimeError> {
+        let Some(Value::Symbol(name)) = form.first() else {
+            return Ok(None);
+        };
+        match resolve(self.global, name, None) {
+            Binding::Macro(transformer) => self.expand(&transformer, form, None).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn analyze_expr(&self, expr: &Value, scope: Option<&Rc<Scope>>) -> Rc<Node> {
+        self.analyze_form(expr, scope)
+            .unwrap_or_else(|err| Node::new(NodeKind::Fail(err), expr.span()))
+    }
+
+    fn analyze_form(&self, expr: &Value, scope: Option<&Rc<Scope>>) -> AnalyzeResult {
+        match expr {
+            Value::List(list) => self.analyze_list(list, scope),
+            Value::Symbol(symbol) => Ok(self.analyze_symbol(symbol, scope)),
+            // 带点号的序对不是合法的表达式
+            Value::Pair(pair) => Err(RuntimeError::new(
+                RuntimeErrorKind::SyntaxError(ParseErrorKind::InvalidSyntax(Token::Dot)),
+                pair.span(),
+            )),
+            Value::Void | Value::Closure(_) => Ok(Self::constant(Value::Void)),
+            value => Ok(Self::constant(value.clone())),
+        }
+    }
+
+    fn constant(value: Value) -> Rc<Node> {
+        Node::new(NodeKind::Constant(value), None)
+    }
+
+    fn analyze_symbol(&self, symbol: &Symbol, scope: Option<&Rc<Scope>>) -> Rc<Node> {
+        // 关键字参数的名称求值为自身
+        if symbol.original().starts_with("#:") {
+            return Self::constant(Value::Symbol(symbol.original().clone()));
+        }
+        let kind = match resolve(self.global, symbol, scope) {
+            Binding::Local { depth, index } => NodeKind::Local {
+                depth,
+                index,
+                symbol: symbol.clone(),
+            },
+            Binding::Global(global) => NodeKind::Global(global),
+            Binding::Macro(transformer) => return Self::constant(transformer),
+            Binding::Keyword(keyword) => return Self::constant(Value::Keyword(keyword)),
+        };
+        Node::new(kind, symbol.span())
+    }
+
+    fn analyze_list(&self, list: &List, scope: Option<&Rc<Scope>>) -> AnalyzeResult {
+        let (first, rest) = list.split_first().ok_or(RuntimeErrorKind::EmptyList)?;
+        let operator = match first {
+            Value::Keyword(keyword) => return self.analyze_keyword(keyword, list, scope),
+            Value::Symbol(symbol) => match resolve(self.global, symbol, scope) {
+                Binding::Keyword(keyword) => return self.analyze_keyword(&keyword, list, scope),
+                Binding::Macro(transformer) => {
+                    let expansion = self.expand(&transformer, list, scope)?;
+                    return Ok(self.analyze_expr(&expansion, scope));
+                }
+                _ => self.analyze_symbol(symbol, scope),
+            },
+            Value::List(_) => self.analyze_expr(first, scope),
+            // 准引用与 `guard` 生成的代码直接以内部过程作为运算符
+            Value::Closure(_) | Value::InternalFunction(_) | Value::Control(_) => {
+                Self::constant(first.clone())
+            }
+            _ => return Err(RuntimeErrorKind::NonCallableValue(first.clone()).into()),
+        };
+        let kind = NodeKind::Call(Rc::new(Call {
+            operator,
+            args: self.analyze_body(rest, scope),
+            form: list.clone(),
+        }));
+        Ok(Node::new(kind, list.span()))
+    }
+
+    // 以未求值的参数展开宏调用，`syntax-rules` 的字面量在使用处的作用域中比较
+    fn expand(
+        &self,
+        transformer: &Value,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+    ) -> Result<Value, RuntimeError> {
+        let (name, expansion) = match transformer {
+            Value::Macro(transformer) => (
+                transformer.name.clone(),
+                Evaluator.expand_macro(transformer, &form[1..]),
+            ),
+            Value::Syntax(syntax) => (
+                syntax.name.clone(),
+                expander::expand(syntax, form, &|literal, input| {
+                    is_same_binding(self.global, &syntax.environment, literal, input, scope)
+                })
+                .map(|expansion| expansion.to_form()),
+            ),
+            _ => unreachable!(),
+        };
+        expansion
+            .map_err(|err| err.with_frame(Frame::new(name.as_deref().map(str::to_string), form)))
+    }
+
+    fn analyze_keyword(
+        &self,
+        keyword: &Keyword,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+    ) -> AnalyzeResult {
+        let kind = match keyword {
+            Keyword::Define => return self.analyze_define(form, scope),
+            Keyword::DefineMacro => return self.analyze_define_macro(form, scope),
+            Keyword::DefineSyntax => return self.analyze_define_syntax(form, scope),
+            Keyword::LetSyntax => return self.analyze_let_syntax(form, scope, false),
+            Keyword::LetrecSyntax => return self.analyze_let_syntax(form, scope, true),
+            Keyword::SyntaxRules => {
+                let syntax =
+                    Evaluator::eval_keyword_syntax_rules(form, &environment(self.global, scope))?;
+                return Ok(Self::constant(syntax));
+            }
+            Keyword::Lambda => {
+                let [_, params, body @ ..] = form.as_slice() else {
+                    return Err(RuntimeErrorKind::EmptyList.into());
+                };
+                let params = Rc::new(Evaluator::parse_params(params)?);
+                NodeKind::Lambda(self.analyze_lambda(None, params, body, form.span(), scope))
+            }
+            Keyword::CaseLambda => {
+                let clauses = form[1..]
+                    .iter()
+                    .map(|clause| match clause.try_as_list()?.split_first() {
+                        Some((params, body)) => {
+                            let params = Rc::new(Evaluator::parse_params(params)?);
+                            Ok(self.analyze_lambda(None, params, body, form.span(), scope))
+                        }
+                        None => Err(Evaluator::invalid_syntax(form)),
+                    })
+                    .try_collect()?;
+                NodeKind::CaseLambda(clauses)
+            }
+            Keyword::Let => return self.analyze_let(form, scope),
+            Keyword::LetStar => return self.analyze_let_star(form, scope),
+            Keyword::Letrec => return self.analyze_letrec(form, scope, false),
+            Keyword::LetrecStar => return self.analyze_letrec(form, scope, true),
+            Keyword::Set => return self.analyze_set(form, scope),
+            Keyword::If => {
+                let ([_, test, consequent] | [_, test, consequent, _]) = form.as_slice() else {
+                    return Err(Evaluator::invalid_syntax(form));
+                };
+                NodeKind::If {
+                    test: self.analyze_expr(test, scope),
+                    consequent: self.analyze_expr(consequent, scope),
+                    alternative: form.get(3).map(|expr| self.analyze_expr(expr, scope)),
+                }
+            }
+            Keyword::Cond => return self.analyze_cond(form, scope),
+            Keyword::Case => return self.analyze_case(form, scope),
+            Keyword::And => NodeKind::And(self.analyze_body(&form[1..], scope)),
+            Keyword::Or => NodeKind::Or(self.analyze_body(&form[1..], scope)),
+            Keyword::When | Keyword::Unless => {
+                let [_, test, body @ ..] = form.as_slice() else {
+                    return Err(Evaluator::invalid_syntax(form));
+                };
+                NodeKind::When {
+                    test: self.analyze_expr(test, scope),
+                    body: self.analyze_body(body, scope),
+                    expected: matches!(keyword, Keyword::When),
+                }
+            }
+            Keyword::Begin => NodeKind::Sequence(self.analyze_body(&form[1..], scope)),
+            Keyword::Guard => return self.analyze_guard(form, scope),
+            Keyword::Quote => match &form[1..] {
+                [value] => return Ok(Self::constant(value.to_datum())),
+                rest => {
+                    return Err(RuntimeErrorKind::InvalidArity {
+                        expected: 1,
+                        founded: rest.len(),
+                    }
+                    .into())
+                }
+            },
+            Keyword::Quasiquote => {
+                let expr = Evaluator::expand_quasiquote(form)?;
+                return Ok(self.analyze_expr(&expr, scope));
+            }
+        };
+        Ok(Node::new(kind, form.span()))
+    }
+
+    fn analyze_body(&self, body: &[Value], scope: Option<&Rc<Scope>>) -> Body {
+        body.iter()
+            .map(|expr| self.analyze_expr(expr, scope))
+            .collect()
+    }
+
+    // 分析在新的作用域中求值的过程体，其中的内部定义先分配位置
+    fn analyze_scope_body(&self, body: &[Value], scope: &Rc<Scope>) -> Body {
+        scan_defines(self.global, body, scope);
+        self.analyze_body(body, Some(scope))
+    }
+
+    // 在当前作用域中定义变量，顶层的定义创建全局变量
+    fn define(
+        name: &Symbol,
+        value: Rc<Node>,
+        scope: Option<&Rc<Scope>>,
+        span: Option<Span>,
+    ) -> Rc<Node> {
+        let variable = match scope {
+            Some(scope) => Variable::Local {
+                depth: 0,
+                index: scope.define(name),
+            },
+            None => Variable::Global(name.clone()),
+        };
+        Node::new(NodeKind::Define { variable, value }, span)
+    }
+
+    fn analyze_define(&self, form: &List, scope: Option<&Rc<Scope>>) -> AnalyzeResult {
+        match &form[1..] {
+            [Value::Symbol(name), value] => {
+                if let Some(scope) = scope {
+                    scope.define(name);
+                }
+                let value = self.analyze_expr(value, scope);
+                Ok(Self::define(name, value, scope, form.span()))
+            }
+            [signature @ (Value::List(_) | Value::Pair(_)), body @ ..] => {
+                let (name, params) = Evaluator::parse_signature(signature)?;
+                if let Some(scope) = scope {
+                    scope.define(&name);
+                }
+                let lambda = self.analyze_lambda(
+                    Some(name.as_str().into()),
+                    Rc::new(params),
+                    body,
+                    form.span(),
+                    scope,
+                );
+                let value = Node::new(NodeKind::Lambda(lambda), None);
+                Ok(Self::define(&name, value, scope, form.span()))
+            }
+            [value, ..] => Err(RuntimeErrorKind::TypeError {
+                expected: "symbol or list",
+                founded: value.clone(),
+            }
+            .into()),
+            [] => Err(RuntimeErrorKind::EmptyList.into()),
+        }
+    }
+
+    // 顶层的宏在运行时定义；过程体中的宏只在分析时可见，转换器在全局环境中求值
+    fn analyze_define_macro(&self, form: &List, scope: Option<&Rc<Scope>>) -> AnalyzeResult {
+        let (name, transformer) = match &form[1..] {
+            [signature @ (Value::List(_) | Value::Pair(_)), body @ ..] => {
+                let (name, params) = Evaluator::parse_signature(signature)?;
+                let transformer =
+                    Closure::new(Some(name.to_string()), params, body.to_vec(), self.global)
+                        .with_span(form.span());
+                (name, Value::Macro(transformer))
+            }
+            [Value::Symbol(name), transformer] => {
+                if scope.is_none() {
+                    let kind = NodeKind::DefineMacro {
+                        name: name.clone(),
+                        value: self.analyze_expr(transformer, None),
+                    };
+                    return Ok(Node::new(kind, form.span()));
+                }
+                let value = Evaluator.eval_value(transformer, self.global)?;
+                (
+                    name.clone(),
+                    Evaluator::macro_transformer(name, value, form.span())?,
+                )
+            }
+            [value, ..] => {
+                return Err(RuntimeErrorKind::TypeError {
+                    expected: "symbol or list",
+                    founded: value.clone(),
+                }
+                .into())
+            }
+            [] => return Err(RuntimeErrorKind::EmptyList.into()),
+        };
+
+        match scope {
+            Some(scope) => {
+                scope.define_macro(&name, transformer);
+                Ok(Self::constant(Value::Void))
+            }
+            None => Ok(Self::define(
+                &name,
+                Self::constant(transformer),
+                None,
+                form.span(),
+            )),
+        }
+    }
+
+    fn analyze_define_syntax(&self, form: &List, scope: Option<&Rc<Scope>>) -> AnalyzeResult {
+        let [_, Value::Symbol(name), spec] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        if let Some(scope) = scope {
+            let transformer = self.transformer(name, spec, Some(scope))?;
+            scope.define_macro(name, transformer);
+            return Ok(Self::constant(Value::Void));
+        }
+        let kind = NodeKind::DefineSyntax {
+            name: name.clone(),
+            value: self.analyze_expr(spec, None),
+        };
+        Ok(Node::new(kind, form.span()))
+    }
+
+    // 在分析时求值局部宏的转换器
+    fn transformer(
+        &self,
+        name: &Symbol,
+        spec: &Value,
+        scope: Option<&Rc<Scope>>,
+    ) -> Result<Value, RuntimeError> {
+        let value = match spec {
+            Value::List(list)
+                if list
+                    .first()
+                    .and_then(|head| keyword(self.global, head, scope))
+                    == Some(Keyword::SyntaxRules) =>
+            {
+                Evaluator::eval_keyword_syntax_rules(list, &environment(self.global, scope))?
+            }
+            Value::Symbol(symbol) => match resolve(self.global, symbol, scope) {
+                Binding::Macro(transformer) => transformer,
+                _ => Evaluator.eval_value(spec, self.global)?,
+            },
+            spec => Evaluator.eval_value(spec, self.global)?,
+        };
+        Evaluator::name_transformer(name, value)
+    }
+
+    // `let-syntax` 的转换器在外层作用域中定义，`letrec-syntax` 的转换器在新作用域中定义
+    fn analyze_let_syntax(
+        &self,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        recursive: bool,
+    ) -> AnalyzeResult {
+        let [_, Value::List(bindings), body @ ..] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+
+        let new_scope = Scope::new(scope);
+        let transformer_scope = if recursive { Some(&new_scope) } else { scope };
+        for binding in bindings.iter() {
+            let [Value::Symbol(name), spec] = binding.try_as_list()?.as_slice() else {
+                return Err(Evaluator::invalid_syntax(form));
+            };
+            let transformer = self.transformer(name, spec, transformer_scope)?;
+            new_scope.define_macro(name, transformer);
+        }
+
+        let body = self.analyze_scope_body(body, &new_scope);
+        let kind = NodeKind::Letrec(Rc::new(Letrec {
+            inits: Rc::new([]),
+            size: new_scope.size(),
+            body,
+            sequential: true,
+        }));
+        Ok(Node::new(kind, form.span()))
+    }
+
+    // 分析过程，形参依次占用过程环境中最前面的位置
+    fn analyze_lambda(
+        &self,
+        name: Option<Rc<str>>,
+        params: Rc<Params>,
+        body: &[Value],
+        span: Option<Span>,
+        scope: Option<&Rc<Scope>>,
+    ) -> Rc<Lambda> {
+        let new_scope = Scope::new(scope);
+        let names = params
+            .required
+            .iter()
+            .chain(params.optional.iter().map(|(name, _)| name))
+            .chain(params.keys.iter().map(|(name, _)| name))
+            .chain(&params.rest);
+        for name in names {
+            new_scope.bind(name);
+        }
+
+        // 默认值在过程环境中求值，可以引用之前的参数
+        let defaults = params
+            .optional
+            .iter()
+            .chain(&params.keys)
+            .map(|(_, default)| self.analyze_expr(default, Some(&new_scope)))
+            .collect();
+        let analyzed = self.analyze_scope_body(body, &new_scope);
+
+        Rc::new(Lambda {
+            name,
+            params,
+            forms: body.into(),
+            span,
+            size: new_scope.size(),
+            defaults,
+            body: analyzed,
+        })
+    }
+
+    // (let ((name init) ...) body ...) 或命名 let：(let loop ((name init) ...) body ...)
+    fn analyze_let(&self, form: &List, scope: Option<&Rc<Scope>>) -> AnalyzeResult {
+        let (name, bindings, body) = match form.as_slice() {
+            [_, Value::Symbol(name), bindings, body @ ..] => (Some(name), bindings, body),
+            [_, bindings, body @ ..] => (None, bindings, body),
+            _ => return Err(Evaluator::invalid_syntax(form)),
+        };
+        let bindings = Evaluator::parse_bindings(form, bindings)?;
+        let inits = bindings
+            .iter()
+            .map(|(_, init)| self.analyze_expr(init, scope))
+            .collect();
+
+        let Some(name) = name else {
+            let new_scope = Scope::new(scope);
+            for (name, _) in &bindings {
+                new_scope.bind(name);
+            }
+            let body = self.analyze_scope_body(body, &new_scope);
+            let kind = NodeKind::Let(Rc::new(Let {
+                inits,
+                size: new_scope.size(),
+                body,
+            }));
+            return Ok(Node::new(kind, form.span()));
+        };
+
+        // 循环过程绑定在只对过程体可见的环境中，对它的调用都是尾调用
+        let loop_scope = Scope::new(scope);
+        loop_scope.bind(name);
+        let params = Params::from(
+            bindings
+                .iter()
+                .map(|(param, _)| param.name().clone())
+                .collect::<Vec<_>>(),
+        );
+        let lambda = self.analyze_lambda(
+            Some(name.as_str().into()),
+            Rc::new(params),
+            body,
+            form.span(),
+            Some(&loop_scope),
+        );
+        let kind = NodeKind::NamedLet(Rc::new(NamedLet {
+            lambda,
+            inits,
+            form: form.clone(),
+        }));
+        Ok(Node::new(kind, form.span()))
+    }
+
+    // 所有绑定位于同一个环境中，每个初始值只能看到之前的绑定
+    fn analyze_let_star(&self, form: &List, scope: Option<&Rc<Scope>>) -> AnalyzeResult {
+        let [_, bindings, body @ ..] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        let bindings = Evaluator::parse_bindings(form, bindings)?;
+
+        let new_scope = Scope::new(scope);
+        let inits = bindings
+            .iter()
+            .map(|(name, init)| {
+                let init = self.analyze_expr(init, Some(&new_scope));
+                new_scope.bind(name);
+                init
+            })
+            .collect();
+        let body = self.analyze_scope_body(body, &new_scope);
+        let kind = NodeKind::Letrec(Rc::new(Letrec {
+            inits,
+            size: new_scope.size(),
+            body,
+            sequential: true,
+        }));
+        Ok(Node::new(kind, form.span()))
+    }
+
+    // 初始值在新的作用域中求值，因此可以定义相互递归的过程
+    fn analyze_letrec(
+        &self,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        sequential: bool,
+    ) -> AnalyzeResult {
+        let [_, bindings, body @ ..] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        let bindings = Evaluator::parse_bindings(form, bindings)?;
+
+        let new_scope = Scope::new(scope);
+        for (name, _) in &bindings {
+            new_scope.bind(name);
+        }
+        let inits = bindings
+            .iter()
+            .map(|(_, init)| self.analyze_expr(init, Some(&new_scope)))
+            .collect();
+        let body = self.analyze_scope_body(body, &new_scope);
+        let kind = NodeKind::Letrec(Rc::new(Letrec {
+            inits,
+            size: new_scope.size(),
+            body,
+            sequential,
+        }));
+        Ok(Node::new(kind, form.span()))
+    }
+
+    fn analyze_set(&self, form: &List, scope: Option<&Rc<Scope>>) -> AnalyzeResult {
+        match &form[1..] {
+            [Value::Symbol(name), value] => {
+                let variable = match resolve(self.global, name, scope) {
+                    Binding::Local { depth, index } => Variable::Local { depth, index },
+                    Binding::Global(global) => Variable::Global(global),
+                    Binding::Macro(_) | Binding::Keyword(_) => Variable::Global(name.clone()),
+                };
+                let kind = NodeKind::Set {
+                    variable,
+                    value: self.analyze_expr(value, scope),
+                };
+                Ok(Node::new(kind, form.span()))
+            }
+            [value, _] => Err(RuntimeErrorKind::TypeError {
+                expected: "symbol",
+                founded: value.clone(),
+            }
+            .into()),
+            list => Err(RuntimeErrorKind::InvalidArity {
+                expected: 2,
+                founded: list.len(),
+            }
+            .into()),
+        }
+    }
+
+    // 子句中测试之后的部分
+    fn analyze_consequent(&self, body: &[Value], scope: Option<&Rc<Scope>>) -> Consequent {
+        match body {
+            [] => Consequent::Value,
+            [arrow, receiver] if is_auxiliary(self.global, arrow, "=>", scope) => {
+                Consequent::Receive(self.analyze_expr(receiver, scope))
+            }
+            body => Consequent::Body(self.analyze_body(body, scope)),
+        }
+    }
+
+    // (cond (test expr ...) ... (test => receiver) ... (else expr ...))
+    fn analyze_cond(&self, form: &List, scope: Option<&Rc<Scope>>) -> AnalyzeResult {
+        let clauses = form[1..]
+            .iter()
+            .map(|clause| {
+                let clause = clause.try_as_list()?;
+                let Some((test, body)) = clause.split_first() else {
+                    return Err(Evaluator::invalid_syntax(form));
+                };
+                let test = if is_auxiliary(self.global, test, "else", scope) {
+                    Test::Else
+                } else {
+                    Test::Expr(self.analyze_expr(test, scope))
+                };
+                Ok(Clause {
+                    test,
+                    consequent: self.analyze_consequent(body, scope),
+                    form: clause.clone(),
+                })
+            })
+            .try_collect()?;
+        Ok(Node::new(NodeKind::Cond(clauses), form.span()))
+    }
+
+    // (case key ((datum ...) expr ...) ... (else expr ...))
+    fn analyze_case(&self, form: &List, scope: Option<&Rc<Scope>>) -> AnalyzeResult {
+        let [_, key, clauses @ ..] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        let clauses = clauses
+            .iter()
+            .map(|clause| {
+                let clause = clause.try_as_list()?;
+                let test = match clause.first() {
+                    Some(test) if is_auxiliary(self.global, test, "else", scope) => Test::Else,
+                    Some(Value::List(data)) => {
+                        Test::Data(data.iter().map(Value::to_datum).collect())
+                    }
+                    _ => return Err(Evaluator::invalid_syntax(form)),
+                };
+                Ok(Clause {
+                    test,
+                    consequent: self.analyze_consequent(&clause[1..], scope),
+                    form: clause.clone(),
+                })
+            })
+            .try_collect()?;
+        let kind = NodeKind::Case {
+            key: self.analyze_expr(key, scope),
+            clauses,
+        };
+        Ok(Node::new(kind, form.span()))
+    }
+
+    // (guard (var clause ...) body ...)，捕获的值绑定在新的作用域中，然后以 `cond` 的方式测试子句
+    fn analyze_guard(&self, form: &List, scope: Option<&Rc<Scope>>) -> AnalyzeResult {
+        let [_, Value::List(spec), body @ ..] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        let [Value::Symbol(_), ..] = spec.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        let (name, clauses) = Evaluator::guard_clauses(form)?;
+
+        let body = self.analyze_body(body, scope);
+        let new_scope = Scope::new(scope);
+        new_scope.bind(&name);
+        let clauses = self.analyze_cond(&clauses, Some(&new_scope))?;
+        let kind = NodeKind::Guard(Rc::new(Guard {
+            body,
+            size: new_scope.size(),
+            clauses,
+            form: form.clone(),
+        }));
+        Ok(Node::new(kind, form.span()))
+    }
+}
@@ -1,5 +1,8 @@
+use std::io::{stdout, IsTerminal};
+
+use lemon_lisp::diagnostic::{Diagnostic, Style};
 use lemon_lisp::interpreter::Interpreter;
-use lemon_lisp::model::Value;
+use lemon_lisp::model::{SourceId, Value};
 use rustyline::error::ReadlineError;
 use rustyline::highlight::MatchingBracketHighlighter;
 use rustyline::validate::MatchingBracketValidator;
@@ -23,15 +26,24 @@ fn main() -> Result<()> {
     rl.set_helper(Some(h));
 
     let interpreter = Interpreter::new();
+    let style = if stdout().is_terminal() {
+        Style::Ansi
+    } else {
+        Style::Plain
+    };
 
+    // 之前的输入中定义的过程出错时，诊断信息需要引用当时的输入，以输入的序号作为源码的编号
+    let mut inputs: Vec<String> = Vec::new();
     loop {
         match rl.readline("🍋> ") {
             Ok(line) => {
                 rl.add_history_entry(&line)?;
-                match interpreter.eval(&line) {
+                let source = SourceId(inputs.len());
+                inputs.push(line);
+                match interpreter.eval_source(&inputs[source.0], source) {
                     Ok(Value::Void) => continue,
                     Ok(value) => println!("{value}"),
-                    Err(err) => println!("{err}"),
+                    Err(err) => print!("{}", Diagnostic::from(&err).render_sources(&inputs, style)),
                 }
             }
             Err(ReadlineError::Interrupted) => {
@@ -0,0 +1,1051 @@
+//! 将表达式编译为字节码
+//!
+//! 编译时确定每个变量引用所在的作用域：局部变量编译为环境中的层数与位置，
+//! 其余的变量在运行时按名称在全局环境中查找。宏调用在编译时展开。
+
+use std::rc::Rc;
+
+use crate::{
+    evaluator::Evaluator,
+    expander,
+    model::{
+        Closure, Code, Environment, Frame, Keyword, List, Op, Params, ParseErrorKind, RuntimeError,
+        RuntimeErrorKind, Span, Symbol, Token, Value,
+    },
+    scope::{
+        environment, is_auxiliary, is_same_binding, keyword, resolve, scan_defines, Binding, Scope,
+    },
+    vm::Vm,
+};
+
+type CompileResult = Result<(), RuntimeError>;
+
+// 正在编译的代码
+#[derive(Default)]
+struct Builder {
+    code: Code,
+}
+
+impl Builder {
+    fn emit(&mut self, op: Op, span: Option<Span>) -> usize {
+        self.code.ops.push(op);
+        self.code.spans.push(span);
+        self.code.ops.len() - 1
+    }
+
+    // 结果处于尾部位置时返回
+    fn ret(&mut self, tail: bool) {
+        if tail {
+            self.emit(Op::Return, None);
+        }
+    }
+
+    fn constant(&mut self, value: Value) -> usize {
+        self.code.constants.push(value);
+        self.code.constants.len() - 1
+    }
+
+    fn form(&mut self, form: &List) -> usize {
+        self.code.forms.push(form.clone());
+        self.code.forms.len() - 1
+    }
+
+    fn lambda(&mut self, code: Rc<Code>) -> usize {
+        self.code.lambdas.push(code);
+        self.code.lambdas.len() - 1
+    }
+
+    // 将第 `at` 条指令的跳转目标设为下一条指令
+    fn patch(&mut self, at: usize) {
+        let here = self.code.ops.len();
+        match &mut self.code.ops[at] {
+            Op::Jump(target)
+            | Op::JumpIfFalse(target)
+            | Op::JumpIfTrue(target)
+            | Op::And(target)
+            | Op::Or(target)
+            | Op::Default { target, .. }
+            | Op::PushGuard {
+                handler: target, ..
+            } => *target = here,
+            op => unreachable!("{op:?} is not a jump"),
+        }
+    }
+}
+
+pub struct Compiler<'a> {
+    // 运行代码时的顶层环境，编译时在这里查找全局的宏
+    global: &'a Rc<Environment>,
+}
+
+impl<'a> Compiler<'a> {
+    pub fn new(global: &'a Rc<Environment>) -> Self {
+        Self { global }
+    }
+
+    /// 编译顶层表达式
+    ///
+    /// 语法错误与宏展开时的错误不会立即返回，而是编译为引发该错误的指令，
+    /// 与树遍历求值器一样在执行到出错的表达式时才报告。
+    #[must_use]
+    pub fn compile(&self, expr: &Value) -> Rc<Code> {
+        let mut builder = Builder::default();
+        self.compile_expr(&mut builder, expr, None, true);
+        Rc::new(builder.code)
+    }
+
+    /// 编译宏的转换器
+    #[must_use]
+    pub fn compile_closure(&self, closure: &Closure) -> Rc<Code> {
+        self.compile_lambda(
+            closure.name.clone(),
+            (*closure.params).clone(),
+            &closure.body,
+            closure.span,
+            None,
+        )
+    }
+
+    /// 展开一次顶层的宏调用，`form` 不是宏调用时返回 `None`
+    pub fn macroexpand_1(&self, form: &List) -> Result<Option<Value>, RuntimeError> {
+        let Some(Value::Symbol(name)) = form.first() else {
+            return Ok(None);
+        };
+        match resolve(self.global, name, None) {
+            Binding::Macro(transformer) => self.expand(&transformer, form, None).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    // 编译表达式，结果压入栈顶；处于尾部位置时直接返回结果
+    fn compile_expr(
+        &self,
+        builder: &mut Builder,
+        expr: &Value,
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+    ) {
+        let start = builder.code.ops.len();
+        if let Err(err) = self.compile_form(builder, expr, scope, tail) {
+            builder.code.ops.truncate(start);
+            builder.code.spans.truncate(start);
+            builder.code.errors.push(err);
+            let index = builder.code.errors.len() - 1;
+            builder.emit(Op::Fail(index), expr.span());
+        }
+    }
+
+    fn compile_form(
+        &self,
+        builder: &mut Builder,
+        expr: &Value,
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+    ) -> CompileResult {
+        match expr {
+            Value::List(list) => return self.compile_list(builder, list, scope, tail),
+            Value::Symbol(symbol) => self.compile_symbol(builder, symbol, scope),
+            // 带点号的序对不是合法的表达式
+            Value::Pair(pair) => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::SyntaxError(ParseErrorKind::InvalidSyntax(Token::Dot)),
+                    pair.span(),
+                ))
+            }
+            Value::Void | Value::Closure(_) => {
+                builder.emit(Op::Void, None);
+            }
+            value => {
+                let index = builder.constant(value.clone());
+                builder.emit(Op::Constant(index), None);
+            }
+        }
+        builder.ret(tail);
+        Ok(())
+    }
+
+    fn compile_symbol(&self, builder: &mut Builder, symbol: &Symbol, scope: Option<&Rc<Scope>>) {
+        // 关键字参数的名称求值为自身
+        if symbol.original().starts_with("#:") {
+            let index = builder.constant(Value::Symbol(symbol.original().clone()));
+            builder.emit(Op::Constant(index), None);
+            return;
+        }
+        match resolve(self.global, symbol, scope) {
+            Binding::Local { depth, index } => {
+                let name = builder.constant(Value::Symbol(symbol.clone()));
+                builder.emit(Op::Local { depth, index, name }, symbol.span());
+            }
+            Binding::Global(global) => {
+                let index = builder.constant(Value::Symbol(global));
+                builder.emit(Op::Global(index), symbol.span());
+            }
+            Binding::Macro(transformer) => {
+                let index = builder.constant(transformer);
+                builder.emit(Op::Constant(index), None);
+            }
+            Binding::Keyword(keyword) => {
+                let index = builder.constant(Value::Keyword(keyword));
+                builder.emit(Op::Constant(index), None);
+            }
+        }
+    }
+
+    fn compile_list(
+        &self,
+        builder: &mut Builder,
+        list: &List,
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+    ) -> CompileResult {
+        let (first, _) = list.split_first().ok_or(RuntimeErrorKind::EmptyList)?;
+        match first {
+            Value::Keyword(keyword) => self.compile_keyword(builder, keyword, list, scope, tail),
+            Value::Symbol(symbol) => match resolve(self.global, symbol, scope) {
+                Binding::Keyword(keyword) => {
+                    self.compile_keyword(builder, &keyword, list, scope, tail)
+                }
+                Binding::Macro(transformer) => {
+                    let expansion = self.expand(&transformer, list, scope)?;
+                    self.compile_expr(builder, &expansion, scope, tail);
+                    Ok(())
+                }
+                _ => {
+                    self.compile_symbol(builder, symbol, scope);
+                    self.compile_call(builder, list, scope, tail, true);
+                    Ok(())
+                }
+            },
+            Value::List(_) => {
+                self.compile_expr(builder, first, scope, false);
+                self.compile_call(builder, list, scope, tail, true);
+                Ok(())
+            }
+            // 准引用与 `guard` 生成的代码直接以内部过程作为运算符
+            Value::Closure(_) | Value::InternalFunction(_) | Value::Control(_) => {
+                let index = builder.constant(first.clone());
+                builder.emit(Op::Constant(index), None);
+                self.compile_call(builder, list, scope, tail, false);
+                Ok(())
+            }
+            _ => Err(RuntimeErrorKind::NonCallableValue(first.clone()).into()),
+        }
+    }
+
+    // 运算符已在栈顶，依次求值参数后调用，`check` 表示需要在求值参数之前检查运算符
+    fn compile_call(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+        check: bool,
+    ) {
+        let args = &form[1..];
+        if check && !args.is_empty() {
+            builder.emit(Op::Callable, form.span());
+        }
+        for arg in args {
+            self.compile_expr(builder, arg, scope, false);
+        }
+        Self::emit_call(builder, args.len(), form, tail);
+    }
+
+    fn emit_call(builder: &mut Builder, argc: usize, form: &List, tail: bool) {
+        let site = builder.form(form);
+        let op = if tail {
+            Op::TailCall { argc, site }
+        } else {
+            Op::Call { argc, site }
+        };
+        builder.emit(op, form.span());
+    }
+
+    // 以未求值的参数展开宏调用，`syntax-rules` 的字面量在使用处的作用域中比较
+    fn expand(
+        &self,
+        transformer: &Value,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+    ) -> Result<Value, RuntimeError> {
+        let (name, expansion) = match transformer {
+            Value::Macro(transformer) => (
+                transformer.name.clone(),
+                Vm::expand_macro(transformer, &form[1..]),
+            ),
+            Value::Syntax(syntax) => (
+                syntax.name.clone(),
+                expander::expand(syntax, form, &|literal, input| {
+                    is_same_binding(self.global, &syntax.environment, literal, input, scope)
+                })
+                .map(|expansion| expansion.to_form()),
+            ),
+            _ => unreachable!(),
+        };
+        expansion
+            .map_err(|err| err.with_frame(Frame::new(name.as_deref().map(str::to_string), form)))
+    }
+
+    fn compile_keyword(
+        &self,
+        builder: &mut Builder,
+        keyword: &Keyword,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+    ) -> CompileResult {
+        match keyword {
+            Keyword::Define => self.compile_define(builder, form, scope)?,
+            Keyword::DefineMacro => self.compile_define_macro(builder, form, scope)?,
+            Keyword::DefineSyntax => self.compile_define_syntax(builder, form, scope)?,
+            Keyword::LetSyntax => {
+                return self.compile_let_syntax(builder, form, scope, false, tail)
+            }
+            Keyword::LetrecSyntax => {
+                return self.compile_let_syntax(builder, form, scope, true, tail)
+            }
+            Keyword::SyntaxRules => {
+                let syntax =
+                    Evaluator::eval_keyword_syntax_rules(form, &environment(self.global, scope))?;
+                let index = builder.constant(syntax);
+                builder.emit(Op::Constant(index), None);
+            }
+            Keyword::Lambda => {
+                let [_, params, body @ ..] = form.as_slice() else {
+                    return Err(RuntimeErrorKind::EmptyList.into());
+                };
+                let params = Evaluator::parse_params(params)?;
+                let code = self.compile_lambda(None, params, body, form.span(), scope);
+                let index = builder.lambda(code);
+                builder.emit(Op::Lambda(index), None);
+            }
+            Keyword::CaseLambda => self.compile_case_lambda(builder, form, scope)?,
+            Keyword::Let => return self.compile_let(builder, form, scope, tail),
+            Keyword::LetStar => return self.compile_let_star(builder, form, scope, tail),
+            Keyword::Letrec => return self.compile_letrec(builder, form, scope, false, tail),
+            Keyword::LetrecStar => return self.compile_letrec(builder, form, scope, true, tail),
+            Keyword::Set => self.compile_set(builder, form, scope)?,
+            Keyword::If => return self.compile_if(builder, form, scope, tail),
+            Keyword::Cond => return self.compile_cond(builder, form, scope, tail),
+            Keyword::Case => return self.compile_case(builder, form, scope, tail),
+            Keyword::And => {
+                self.compile_and_or(builder, form, scope, true, tail);
+                return Ok(());
+            }
+            Keyword::Or => {
+                self.compile_and_or(builder, form, scope, false, tail);
+                return Ok(());
+            }
+            Keyword::When => return self.compile_when(builder, form, scope, true, tail),
+            Keyword::Unless => return self.compile_when(builder, form, scope, false, tail),
+            Keyword::Begin => {
+                self.compile_body(builder, &form[1..], scope, tail);
+                return Ok(());
+            }
+            Keyword::Guard => return self.compile_guard(builder, form, scope, tail),
+            Keyword::Quote => match &form[1..] {
+                [value] => {
+                    let index = builder.constant(value.to_datum());
+                    builder.emit(Op::Constant(index), None);
+                }
+                rest => {
+                    return Err(RuntimeErrorKind::InvalidArity {
+                        expected: 1,
+                        founded: rest.len(),
+                    }
+                    .into())
+                }
+            },
+            Keyword::Quasiquote => {
+                let expr = Evaluator::expand_quasiquote(form)?;
+                self.compile_expr(builder, &expr, scope, tail);
+                return Ok(());
+            }
+        }
+        builder.ret(tail);
+        Ok(())
+    }
+
+    // 编译过程体，最后一个表达式处于尾部位置
+    fn compile_body(
+        &self,
+        builder: &mut Builder,
+        body: &[Value],
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+    ) {
+        let Some((last, init)) = body.split_last() else {
+            builder.emit(Op::Void, None);
+            builder.ret(tail);
+            return;
+        };
+        for expr in init {
+            self.compile_expr(builder, expr, scope, false);
+            builder.emit(Op::Pop, None);
+        }
+        self.compile_expr(builder, last, scope, tail);
+    }
+
+    // 编译在新的作用域中求值的过程体，`push` 为进入该作用域的指令
+    fn compile_scope_body(
+        &self,
+        builder: &mut Builder,
+        push: usize,
+        body: &[Value],
+        scope: &Rc<Scope>,
+        tail: bool,
+    ) {
+        scan_defines(self.global, body, scope);
+        self.compile_body(builder, body, Some(scope), tail);
+        builder.code.ops[push] = Op::PushEnv(scope.size());
+        if !tail {
+            builder.emit(Op::PopEnv, None);
+        }
+    }
+
+    // 弹出栈顶的值，在当前作用域中定义变量；顶层的定义创建全局变量
+    fn emit_define(builder: &mut Builder, name: &Symbol, scope: Option<&Rc<Scope>>) {
+        if let Some(scope) = scope {
+            let index = scope.define(name);
+            builder.emit(Op::DefineLocal(index), None);
+        } else {
+            let index = builder.constant(Value::Symbol(name.clone()));
+            builder.emit(Op::DefineGlobal(index), None);
+        }
+    }
+
+    fn compile_define(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+    ) -> CompileResult {
+        match &form[1..] {
+            [Value::Symbol(name), value] => {
+                if let Some(scope) = scope {
+                    scope.define(name);
+                }
+                self.compile_expr(builder, value, scope, false);
+                Self::emit_define(builder, name, scope);
+            }
+            [signature @ (Value::List(_) | Value::Pair(_)), body @ ..] => {
+                let (name, params) = Evaluator::parse_signature(signature)?;
+                if let Some(scope) = scope {
+                    scope.define(&name);
+                }
+                let code = self.compile_lambda(
+                    Some(name.as_str().into()),
+                    params,
+                    body,
+                    form.span(),
+                    scope,
+                );
+                let index = builder.lambda(code);
+                builder.emit(Op::Lambda(index), None);
+                Self::emit_define(builder, &name, scope);
+            }
+            [value, ..] => {
+                return Err(RuntimeErrorKind::TypeError {
+                    expected: "symbol or list",
+                    founded: value.clone(),
+                }
+                .into())
+            }
+            [] => return Err(RuntimeErrorKind::EmptyList.into()),
+        }
+        builder.emit(Op::Void, None);
+        Ok(())
+    }
+
+    // 顶层的宏在运行时定义；过程体中的宏只在编译时可见，转换器在全局环境中求值
+    fn compile_define_macro(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+    ) -> CompileResult {
+        let (name, transformer) = match &form[1..] {
+            [signature @ (Value::List(_) | Value::Pair(_)), body @ ..] => {
+                let (name, params) = Evaluator::parse_signature(signature)?;
+                let transformer =
+                    Closure::new(Some(name.to_string()), params, body.to_vec(), self.global)
+                        .with_span(form.span());
+                (name, Value::Macro(transformer))
+            }
+            [Value::Symbol(name), transformer] => {
+                if scope.is_none() {
+                    self.compile_expr(builder, transformer, None, false);
+                    let index = builder.constant(Value::Symbol(name.clone()));
+                    builder.emit(Op::DefineMacro(index), form.span());
+                    builder.emit(Op::Void, None);
+                    return Ok(());
+                }
+                let value = Vm.eval_value(transformer, self.global)?;
+                (
+                    name.clone(),
+                    Evaluator::macro_transformer(name, value, form.span())?,
+                )
+            }
+            [value, ..] => {
+                return Err(RuntimeErrorKind::TypeError {
+                    expected: "symbol or list",
+                    founded: value.clone(),
+                }
+                .into())
+            }
+            [] => return Err(RuntimeErrorKind::EmptyList.into()),
+        };
+
+        if let Some(scope) = scope {
+            scope.define_macro(&name, transformer);
+        } else {
+            let index = builder.constant(transformer);
+            builder.emit(Op::Constant(index), None);
+            Self::emit_define(builder, &name, None);
+        }
+        builder.emit(Op::Void, None);
+        Ok(())
+    }
+
+    fn compile_define_syntax(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+    ) -> CompileResult {
+        let [_, Value::Symbol(name), spec] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        if let Some(scope) = scope {
+            let transformer = self.transformer(name, spec, Some(scope))?;
+            scope.define_macro(name, transformer);
+        } else {
+            self.compile_expr(builder, spec, None, false);
+            let index = builder.constant(Value::Symbol(name.clone()));
+            builder.emit(Op::DefineSyntax(index), form.span());
+        }
+        builder.emit(Op::Void, None);
+        Ok(())
+    }
+
+    // 在编译时求值局部宏的转换器
+    fn transformer(
+        &self,
+        name: &Symbol,
+        spec: &Value,
+        scope: Option<&Rc<Scope>>,
+    ) -> Result<Value, RuntimeError> {
+        let value = match spec {
+            Value::List(list)
+                if list
+                    .first()
+                    .and_then(|head| keyword(self.global, head, scope))
+                    == Some(Keyword::SyntaxRules) =>
+            {
+                Evaluator::eval_keyword_syntax_rules(list, &environment(self.global, scope))?
+            }
+            Value::Symbol(symbol) => match resolve(self.global, symbol, scope) {
+                Binding::Macro(transformer) => transformer,
+                _ => Vm.eval_value(spec, self.global)?,
+            },
+            spec => Vm.eval_value(spec, self.global)?,
+        };
+        Evaluator::name_transformer(name, value)
+    }
+
+    // `let-syntax` 的转换器在外层作用域中定义，`letrec-syntax` 的转换器在新作用域中定义
+    fn compile_let_syntax(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        recursive: bool,
+        tail: bool,
+    ) -> CompileResult {
+        let [_, Value::List(bindings), body @ ..] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+
+        let new_scope = Scope::new(scope);
+        let transformer_scope = if recursive { Some(&new_scope) } else { scope };
+        for binding in bindings.iter() {
+            let [Value::Symbol(name), spec] = binding.try_as_list()?.as_slice() else {
+                return Err(Evaluator::invalid_syntax(form));
+            };
+            let transformer = self.transformer(name, spec, transformer_scope)?;
+            new_scope.define_macro(name, transformer);
+        }
+
+        let push = builder.emit(Op::PushEnv(0), None);
+        self.compile_scope_body(builder, push, body, &new_scope, tail);
+        Ok(())
+    }
+
+    // 编译过程，形参依次占用过程环境中最前面的位置
+    fn compile_lambda(
+        &self,
+        name: Option<Rc<str>>,
+        params: Params,
+        body: &[Value],
+        span: Option<Span>,
+        scope: Option<&Rc<Scope>>,
+    ) -> Rc<Code> {
+        let new_scope = Scope::new(scope);
+        let names = params
+            .required
+            .iter()
+            .chain(params.optional.iter().map(|(name, _)| name))
+            .chain(params.keys.iter().map(|(name, _)| name))
+            .chain(&params.rest);
+        for name in names {
+            new_scope.bind(name);
+        }
+
+        // 调用时没有提供的可选参数与关键字参数依次求值默认值，可以引用之前的参数
+        let mut builder = Builder::default();
+        let defaults = params.optional.iter().chain(&params.keys);
+        for (i, (_, default)) in defaults.enumerate() {
+            let index = params.required.len() + i;
+            let skip = builder.emit(Op::Default { index, target: 0 }, None);
+            self.compile_expr(&mut builder, default, Some(&new_scope), false);
+            builder.emit(Op::DefineLocal(index), None);
+            builder.patch(skip);
+        }
+
+        scan_defines(self.global, body, &new_scope);
+        self.compile_body(&mut builder, body, Some(&new_scope), true);
+
+        Rc::new(Code {
+            name,
+            params: Rc::new(params),
+            body: body.into(),
+            span,
+            size: new_scope.size(),
+            ..builder.code
+        })
+    }
+
+    // (case-lambda (params body ...) ...)
+    fn compile_case_lambda(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+    ) -> CompileResult {
+        let clauses = form[1..]
+            .iter()
+            .map(|clause| match clause.try_as_list()?.split_first() {
+                Some((params, body)) => {
+                    let params = Evaluator::parse_params(params)?;
+                    Ok(self.compile_lambda(None, params, body, form.span(), scope))
+                }
+                None => Err(Evaluator::invalid_syntax(form)),
+            })
+            .try_collect()?;
+        let code = Code {
+            span: form.span(),
+            clauses: Some(clauses),
+            ..Code::default()
+        };
+        let index = builder.lambda(Rc::new(code));
+        builder.emit(Op::Lambda(index), None);
+        Ok(())
+    }
+
+    // (let ((name init) ...) body ...) 或命名 let：(let loop ((name init) ...) body ...)
+    fn compile_let(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+    ) -> CompileResult {
+        let (name, bindings, body) = match form.as_slice() {
+            [_, Value::Symbol(name), bindings, body @ ..] => (Some(name), bindings, body),
+            [_, bindings, body @ ..] => (None, bindings, body),
+            _ => return Err(Evaluator::invalid_syntax(form)),
+        };
+        let bindings = Evaluator::parse_bindings(form, bindings)?;
+
+        let Some(name) = name else {
+            // 初始值在外层作用域中求值，全部求值之后才创建绑定
+            for (_, init) in &bindings {
+                self.compile_expr(builder, init, scope, false);
+            }
+            let new_scope = Scope::new(scope);
+            let push = builder.emit(Op::PushEnv(0), None);
+            let slots: Vec<usize> = bindings
+                .iter()
+                .map(|(name, _)| new_scope.bind(name))
+                .collect();
+            for index in slots.into_iter().rev() {
+                builder.emit(Op::DefineLocal(index), None);
+            }
+            self.compile_scope_body(builder, push, body, &new_scope, tail);
+            return Ok(());
+        };
+
+        // 循环过程绑定在只对过程体可见的环境中，对它的调用都是尾调用
+        let loop_scope = Scope::new(scope);
+        loop_scope.bind(name);
+        let params = Params::from(
+            bindings
+                .iter()
+                .map(|(param, _)| param.name().clone())
+                .collect::<Vec<_>>(),
+        );
+        let code = self.compile_lambda(
+            Some(name.as_str().into()),
+            params,
+            body,
+            form.span(),
+            Some(&loop_scope),
+        );
+        let index = builder.lambda(code);
+        builder.emit(Op::Loop(index), None);
+        for (_, init) in &bindings {
+            self.compile_expr(builder, init, scope, false);
+        }
+        Self::emit_call(builder, bindings.len(), form, tail);
+        Ok(())
+    }
+
+    // 所有绑定位于同一个环境中，每个初始值只能看到之前的绑定
+    fn compile_let_star(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+    ) -> CompileResult {
+        let [_, bindings, body @ ..] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        let bindings = Evaluator::parse_bindings(form, bindings)?;
+
+        let new_scope = Scope::new(scope);
+        let push = builder.emit(Op::PushEnv(0), None);
+        for (name, init) in bindings {
+            self.compile_expr(builder, init, Some(&new_scope), false);
+            let index = new_scope.bind(name);
+            builder.emit(Op::DefineLocal(index), None);
+        }
+        self.compile_scope_body(builder, push, body, &new_scope, tail);
+        Ok(())
+    }
+
+    // 初始值在新的作用域中求值，`letrec` 在所有初始值求值完毕后才绑定，`letrec*` 则依次求值并绑定
+    fn compile_letrec(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        sequential: bool,
+        tail: bool,
+    ) -> CompileResult {
+        let [_, bindings, body @ ..] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        let bindings = Evaluator::parse_bindings(form, bindings)?;
+
+        let new_scope = Scope::new(scope);
+        let push = builder.emit(Op::PushEnv(0), None);
+        let slots: Vec<usize> = bindings
+            .iter()
+            .map(|(name, _)| new_scope.bind(name))
+            .collect();
+        for ((_, init), index) in bindings.iter().zip(&slots) {
+            self.compile_expr(builder, init, Some(&new_scope), false);
+            if sequential {
+                builder.emit(Op::DefineLocal(*index), None);
+            }
+        }
+        if !sequential {
+            for index in slots.into_iter().rev() {
+                builder.emit(Op::DefineLocal(index), None);
+            }
+        }
+        self.compile_scope_body(builder, push, body, &new_scope, tail);
+        Ok(())
+    }
+
+    fn compile_set(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+    ) -> CompileResult {
+        match &form[1..] {
+            [Value::Symbol(name), value] => {
+                self.compile_expr(builder, value, scope, false);
+                let span = name.span().or(form.span());
+                match resolve(self.global, name, scope) {
+                    Binding::Local { depth, index } => {
+                        builder.emit(Op::SetLocal { depth, index }, span);
+                    }
+                    Binding::Global(global) => {
+                        let index = builder.constant(Value::Symbol(global));
+                        builder.emit(Op::SetGlobal(index), span);
+                    }
+                    Binding::Macro(_) | Binding::Keyword(_) => {
+                        let index = builder.constant(Value::Symbol(name.clone()));
+                        builder.emit(Op::SetGlobal(index), span);
+                    }
+                }
+                builder.emit(Op::Void, None);
+                Ok(())
+            }
+            [value, _] => Err(RuntimeErrorKind::TypeError {
+                expected: "symbol",
+                founded: value.clone(),
+            }
+            .into()),
+            list => Err(RuntimeErrorKind::InvalidArity {
+                expected: 2,
+                founded: list.len(),
+            }
+            .into()),
+        }
+    }
+
+    // (if test consequent [alternative])
+    fn compile_if(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+    ) -> CompileResult {
+        let ([_, condition, consequent] | [_, condition, consequent, _]) = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        self.compile_expr(builder, condition, scope, false);
+        let alternative = builder.emit(Op::JumpIfFalse(0), None);
+        self.compile_expr(builder, consequent, scope, tail);
+        let end = (!tail).then(|| builder.emit(Op::Jump(0), None));
+        builder.patch(alternative);
+        if let Some(expr) = form.get(3) {
+            self.compile_expr(builder, expr, scope, tail);
+        } else {
+            builder.emit(Op::Void, None);
+            builder.ret(tail);
+        }
+        if let Some(end) = end {
+            builder.patch(end);
+        }
+        Ok(())
+    }
+
+    // 被选中的子句求值完毕后返回或跳转到整个表达式之后
+    fn exit_clause(builder: &mut Builder, tail: bool, ends: &mut Vec<usize>) {
+        if !tail {
+            ends.push(builder.emit(Op::Jump(0), None));
+        }
+    }
+
+    // 编译被选中的子句，测试的结果在栈顶
+    fn compile_clause(
+        &self,
+        builder: &mut Builder,
+        body: &[Value],
+        clause: &List,
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+    ) {
+        match body {
+            // 没有表达式时结果即为测试的结果
+            [] => builder.ret(tail),
+            // 以测试的结果调用 `receiver`
+            [arrow, receiver] if is_auxiliary(self.global, arrow, "=>", scope) => {
+                self.compile_expr(builder, receiver, scope, false);
+                builder.emit(Op::Swap, None);
+                Self::emit_call(builder, 1, clause, tail);
+            }
+            body => {
+                builder.emit(Op::Pop, None);
+                self.compile_body(builder, body, scope, tail);
+            }
+        }
+    }
+
+    // (cond (test expr ...) ... (test => receiver) ... (else expr ...))
+    fn compile_cond(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+    ) -> CompileResult {
+        let mut ends = Vec::new();
+        for clause in &form[1..] {
+            let clause = clause.try_as_list()?;
+            let Some((test, body)) = clause.split_first() else {
+                return Err(Evaluator::invalid_syntax(form));
+            };
+            if is_auxiliary(self.global, test, "else", scope) {
+                let index = builder.constant(Value::Bool(true));
+                builder.emit(Op::Constant(index), None);
+                self.compile_clause(builder, body, clause, scope, tail);
+                for end in ends {
+                    builder.patch(end);
+                }
+                return Ok(());
+            }
+            self.compile_expr(builder, test, scope, false);
+            builder.emit(Op::Dup, None);
+            let next = builder.emit(Op::JumpIfFalse(0), None);
+            self.compile_clause(builder, body, clause, scope, tail);
+            Self::exit_clause(builder, tail, &mut ends);
+            builder.patch(next);
+            builder.emit(Op::Pop, None);
+        }
+        builder.emit(Op::Void, None);
+        builder.ret(tail);
+        for end in ends {
+            builder.patch(end);
+        }
+        Ok(())
+    }
+
+    // (case key ((datum ...) expr ...) ... (else expr ...))，比较时键一直保留在栈顶
+    fn compile_case(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+    ) -> CompileResult {
+        let [_, key, clauses @ ..] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        self.compile_expr(builder, key, scope, false);
+
+        let mut ends = Vec::new();
+        for clause in clauses {
+            let clause = clause.try_as_list()?;
+            match clause.first() {
+                Some(test) if is_auxiliary(self.global, test, "else", scope) => {
+                    self.compile_clause(builder, &clause[1..], clause, scope, tail);
+                    for end in ends {
+                        builder.patch(end);
+                    }
+                    return Ok(());
+                }
+                Some(Value::List(data)) => {
+                    let data: Vec<Value> = data.iter().map(Value::to_datum).collect();
+                    let index = builder.constant(Value::List(data.into()));
+                    builder.emit(Op::Memv(index), None);
+                    let next = builder.emit(Op::JumpIfFalse(0), None);
+                    self.compile_clause(builder, &clause[1..], clause, scope, tail);
+                    Self::exit_clause(builder, tail, &mut ends);
+                    builder.patch(next);
+                }
+                _ => return Err(Evaluator::invalid_syntax(form)),
+            }
+        }
+        builder.emit(Op::Pop, None);
+        builder.emit(Op::Void, None);
+        builder.ret(tail);
+        for end in ends {
+            builder.patch(end);
+        }
+        Ok(())
+    }
+
+    // `and` 遇到假值、`or` 遇到真值时以该值作为结果，最后一个表达式处于尾部位置
+    fn compile_and_or(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        and: bool,
+        tail: bool,
+    ) {
+        let Some((last, init)) = form[1..].split_last() else {
+            let index = builder.constant(Value::Bool(and));
+            builder.emit(Op::Constant(index), None);
+            builder.ret(tail);
+            return;
+        };
+        let mut ends = Vec::new();
+        for expr in init {
+            self.compile_expr(builder, expr, scope, false);
+            ends.push(builder.emit(if and { Op::And(0) } else { Op::Or(0) }, None));
+        }
+        self.compile_expr(builder, last, scope, tail);
+        for end in &ends {
+            builder.patch(*end);
+        }
+        if !ends.is_empty() {
+            builder.ret(tail);
+        }
+    }
+
+    // `when` 在条件成立时求值过程体，`unless` 在条件不成立时求值
+    fn compile_when(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        expected: bool,
+        tail: bool,
+    ) -> CompileResult {
+        let [_, condition, body @ ..] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        self.compile_expr(builder, condition, scope, false);
+        let skip = builder.emit(
+            if expected {
+                Op::JumpIfFalse(0)
+            } else {
+                Op::JumpIfTrue(0)
+            },
+            None,
+        );
+        self.compile_body(builder, body, scope, tail);
+        let end = (!tail).then(|| builder.emit(Op::Jump(0), None));
+        builder.patch(skip);
+        builder.emit(Op::Void, None);
+        builder.ret(tail);
+        if let Some(end) = end {
+            builder.patch(end);
+        }
+        Ok(())
+    }
+
+    // (guard (var clause ...) body ...)，捕获的值绑定在新的作用域中，然后以 `cond` 的方式测试子句
+    fn compile_guard(
+        &self,
+        builder: &mut Builder,
+        form: &List,
+        scope: Option<&Rc<Scope>>,
+        tail: bool,
+    ) -> CompileResult {
+        let [_, Value::List(spec), body @ ..] = form.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        let [Value::Symbol(_), ..] = spec.as_slice() else {
+            return Err(Evaluator::invalid_syntax(form));
+        };
+        let (name, clauses) = Evaluator::guard_clauses(form)?;
+
+        let site = builder.form(form);
+        let guard = builder.emit(Op::PushGuard { handler: 0, site }, form.span());
+        self.compile_body(builder, body, scope, false);
+        builder.emit(Op::PopGuard, None);
+        let end = builder.emit(Op::Jump(0), None);
+
+        builder.patch(guard);
+        let new_scope = Scope::new(scope);
+        let push = builder.emit(Op::PushEnv(0), None);
+        let index = new_scope.bind(&name);
+        builder.emit(Op::DefineLocal(index), None);
+        self.compile_cond(builder, &clauses, Some(&new_scope), false)?;
+        builder.code.ops[push] = Op::PushEnv(new_scope.size());
+        builder.emit(Op::PopEnv, None);
+
+        builder.patch(end);
+        builder.ret(tail);
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+use crate::model::{Frame, Note, ParseError, RuntimeError, Span, TokenizeError};
+
+/// 诊断信息的输出样式
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Style {
+    Plain,
+    Ansi,
+}
+
+/// 可渲染为带源码片段的错误诊断
+///
+/// ```rust
+/// # use lemon_lisp::{
+/// #     diagnostic::{Diagnostic, Style},
+/// #     interpreter::Interpreter,
+/// # };
+/// #
+/// let source = "(+ 1 y)";
+/// let error = Interpreter::new().eval(source).unwrap_err();
+///
+/// assert_eq!(
+///     Diagnostic::from(&error).render(source, Style::Plain),
+///     concat!(
+///         "error[UndefinedVariable]: Undefined variable: y\n",
+///         " --> 1:6\n",
+///         "  |\n",
+///         "1 | (+ 1 y)\n",
+///         "  |      ^\n",
+///     )
+/// );
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub kind: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+    pub notes: Vec<Note>,
+    pub backtrace: Vec<Frame>,
+}
+
+// ANSI 颜色
+const RED: &str = "\x1b[1;31m";
+const GREEN: &str = "\x1b[1;32m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+// 调用栈最多显示的帧数，深层递归时省略外层的调用
+const MAX_FRAMES: usize = 16;
+
+impl Diagnostic {
+    /// 渲染诊断信息，`source` 应为产生错误时求值的完整输入
+    ///
+    /// 只有默认编号的源码中的位置会截取片段，分多次读取的源码使用 [`Diagnostic::render_sources`]。
+    pub fn render(&self, source: &str, style: Style) -> String {
+        self.render_sources(&[source], style)
+    }
+
+    /// 渲染诊断信息，每个位置在它所属的源码中截取片段
+    ///
+    /// `sources` 按 [`SourceId`](crate::model::SourceId) 的编号排列，不在其中的源码只输出位置而不输出片段。
+    pub fn render_sources<S: AsRef<str>>(&self, sources: &[S], style: Style) -> String {
+        let paint = |color: &str, text: &str| match style {
+            Style::Plain => text.to_string(),
+            Style::Ansi => format!("{color}{text}{RESET}"),
+        };
+        let source = |span: Span| sources.get(span.source.0).map(AsRef::as_ref);
+
+        // 行号栏的宽度由出现的最大行号决定
+        let width = self
+            .notes
+            .iter()
+            .filter_map(|note| note.span)
+            .chain(self.span)
+            .map(|span| span.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        let mut output = format!(
+            "{}{}\n",
+            paint(RED, &format!("error[{}]", self.kind)),
+            paint(BOLD, &format!(": {}", self.message)),
+        );
+        if let Some(span) = self.span {
+            output += &Self::render_snippet(source(span), span, width, '^', RED, &paint);
+        }
+
+        for note in &self.notes {
+            match note.span {
+                Some(span) => {
+                    output += &format!(
+                        "{}{}\n",
+                        paint(GREEN, "note"),
+                        paint(BOLD, &format!(": {}", note.message)),
+                    );
+                    output += &Self::render_snippet(source(span), span, width, '-', BLUE, &paint);
+                }
+                None => {
+                    output += &format!(
+                        "{} {} {}\n",
+                        " ".repeat(width + 1),
+                        paint(BLUE, "="),
+                        paint(BOLD, &format!("note: {}", note.message)),
+                    );
+                }
+            }
+        }
+
+        if !self.backtrace.is_empty() {
+            output += &format!("{}\n", paint(BOLD, "backtrace:"));
+            for (i, frame) in self.backtrace.iter().take(MAX_FRAMES).enumerate() {
+                output += &format!("{:>4}: {}\n", i, frame);
+            }
+            if self.backtrace.len() > MAX_FRAMES {
+                output += &format!(
+                    "      ... {} more frames\n",
+                    self.backtrace.len() - MAX_FRAMES
+                );
+            }
+        }
+
+        output
+    }
+
+    // 渲染位置、源码行以及标记出错范围的下划线，没有对应的源码时只渲染位置
+    // 跨越多行的位置只标记到第一行的末尾
+    fn render_snippet(
+        source: Option<&str>,
+        span: Span,
+        width: usize,
+        mark: char,
+        color: &str,
+        paint: &impl Fn(&str, &str) -> String,
+    ) -> String {
+        let padding = " ".repeat(width);
+        let mut output = format!("{}{} {}\n", padding, paint(BLUE, "-->"), span);
+
+        let Some((source, before)) =
+            source.and_then(|source| Some((source, source.get(..span.start)?)))
+        else {
+            return output;
+        };
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[span.start..]
+            .find('\n')
+            .map_or(source.len(), |i| span.start + i);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+
+        // 制表符原样保留以保证下划线对齐
+        let indent: String = source[line_start..span.start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let length = source
+            .get(span.start..span.end.min(line_end))
+            .map_or(0, |s| s.chars().count())
+            .max(1);
+        let gutter = paint(BLUE, &format!("{padding} |"));
+
+        output += &format!("{gutter}\n");
+        output += &format!(
+            "{} {}\n",
+            paint(BLUE, &format!("{:>width$} |", span.line)),
+            line
+        );
+        output += &format!(
+            "{gutter} {indent}{}\n",
+            paint(color, &mark.to_string().repeat(length))
+        );
+        output
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Self {
+        Self {
+            kind: error.kind.name(),
+            message: error.kind.to_string(),
+            span: error.span,
+            notes: error.notes.clone(),
+            backtrace: error.backtrace.clone(),
+        }
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        Self {
+            kind: error.kind.name(),
+            message: error.kind.to_string(),
+            span: Some(error.span),
+            notes: Vec::new(),
+            backtrace: Vec::new(),
+        }
+    }
+}
+
+impl From<&TokenizeError> for Diagnostic {
+    fn from(error: &TokenizeError) -> Self {
+        Self {
+            kind: error.kind.name(),
+            message: error.kind.to_string(),
+            span: Some(error.span),
+            notes: Vec::new(),
+            backtrace: Vec::new(),
+        }
+    }
+}
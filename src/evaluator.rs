@@ -1,9 +1,23 @@
-use std::rc::Rc;
+use std::{
+    collections::VecDeque,
+    mem,
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use crate::{
-    internal::InternalFunction,
-    model::{Closure, Environment, Keyword, RuntimeError, TailCall, Value},
-    optimizer::optimize_closure,
+    analyzer::Analyzer,
+    expander,
+    internal::{list, math, Function, InternalFunction},
+    model::{
+        Arity, Body, Call, Clause, Closure, Consequent, Cont, Continuation, Control, Environment,
+        Frame, Guard, Keyword, Lambda, Let, Letrec, List, Name, NamedLet, Node, NodeKind, Numeric,
+        Pair, Params, ParseErrorKind, Pending, RuntimeError, RuntimeErrorKind, Span, Symbol,
+        SyntaxRules, Test, Token, Value, Variable, Winder,
+    },
+    scope::{is_same_binding, keyword},
+    vm::Vm,
+    winding::{ControlStack, Role},
 };
 
 #[derive(Default)]
@@ -11,208 +25,1711 @@ pub struct Evaluator;
 
 type EvalResult = Result<Value, RuntimeError>;
 
+// 尾调用进入的过程不占用续延栈，错误回溯中每一层只保留最近的这几帧
+pub(crate) const MAX_TAIL_FRAMES: usize = 16;
+
+// 每次调用 `call/ec` 使用不同的编号标记返回处
+pub(crate) static NEXT_ESCAPE: AtomicUsize = AtomicUsize::new(0);
+
+// 单步求值的结果
+enum Step {
+    // 求值完毕，值交给续延栈顶的计算
+    Done(Value),
+    // 需要在尾部位置继续求值的表达式
+    Tail {
+        node: Rc<Node>,
+        env: Rc<Environment>,
+    },
+}
+// 求值循环的状态
+//
+// 非尾部位置的子表达式求值之前，之后要继续的计算被压入续延栈，而不是占用 Rust 栈，
+// 因此捕获续延只需复制续延栈，恢复续延只需替换续延栈。
+#[derive(Default)]
+struct Machine {
+    stack: Vec<Pending>,
+    // 当前这一层通过尾调用进入的过程
+    backtrace: VecDeque<Frame>,
+    // 当前所在的动态范围
+    winders: Option<Rc<Winder>>,
+}
+
+impl Machine {
+    // 保存子表达式求值完毕后要继续的计算，`span` 为继续计算出错时报告的位置
+    fn push(&mut self, cont: Cont, span: Option<Span>) {
+        self.stack.push(Pending {
+            cont,
+            span,
+            backtrace: mem::take(&mut self.backtrace),
+        });
+    }
+
+    fn record(&mut self, frame: Frame) {
+        if self.backtrace.len() == MAX_TAIL_FRAMES {
+            self.backtrace.pop_front();
+        }
+        self.backtrace.push_back(frame);
+    }
+
+    // 错误向外传递时依次补上每一层的位置与调用帧
+    fn unwind(mut self, err: RuntimeError) -> RuntimeError {
+        let mut err = self
+            .backtrace
+            .into_iter()
+            .rev()
+            .fold(err, RuntimeError::with_frame);
+        while let Some(pending) = self.stack.pop() {
+            err = pending
+                .backtrace
+                .into_iter()
+                .rev()
+                .fold(err.or_span(pending.span), RuntimeError::with_frame);
+        }
+        err
+    }
+}
+
+impl ControlStack for Machine {
+    type Frame = Pending;
+    type Site<'a> = (&'a List, &'a Rc<Environment>);
+    type Step = Step;
+
+    fn frames(&self) -> &[Pending] {
+        &self.stack
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.stack.truncate(len);
+    }
+
+    fn role(pending: &Pending) -> Role {
+        match pending.cont {
+            Cont::Escape { id } => Role::Escape(id),
+            Cont::Handler { .. } | Cont::Guard { .. } => Role::Handler,
+            Cont::Raise { marker, .. } => Role::Raise(marker),
+            _ => Role::Other,
+        }
+    }
+
+    fn winders(&self) -> Option<&Rc<Winder>> {
+        self.winders.as_ref()
+    }
+
+    fn restore(&mut self, continuation: &Continuation) {
+        let Some(stack) = continuation.stack() else {
+            return;
+        };
+        self.stack = stack;
+    }
+
+    fn push_return(&mut self, value: Value, winders: Option<Rc<Winder>>) {
+        self.push(Cont::Return { value, winders }, None);
+    }
+
+    fn push_wind(
+        &mut self,
+        thunk: Value,
+        winders: Option<Rc<Winder>>,
+        (form, env): Self::Site<'_>,
+    ) {
+        let cont = Cont::Wind {
+            thunk,
+            winders,
+            form: form.clone(),
+            env: Rc::clone(env),
+        };
+        self.push(cont, form.span());
+    }
+
+    fn value(value: Value) -> Step {
+        Step::Done(value)
+    }
+
+    fn raise(
+        &mut self,
+        value: Value,
+        index: usize,
+        continuable: bool,
+    ) -> Result<Step, RuntimeError> {
+        match self.stack[index].cont.clone() {
+            // 处理器在 `raise` 所在的动态环境中调用
+            Cont::Handler { handler, form, env } => {
+                let cont = Cont::Raise {
+                    value: value.clone(),
+                    marker: index,
+                    continuable,
+                };
+                self.push(cont, form.span());
+                Evaluator.apply(handler, &[value], &form, &env, self)
+            }
+            // `guard` 丢弃过程体的续延，离开其中的动态范围后测试子句
+            Cont::Guard { node, env, winders } => {
+                let span = self.stack[index].span;
+                self.stack.truncate(index);
+                self.push(
+                    Cont::Catch {
+                        node: Rc::clone(&node),
+                        env: Rc::clone(&env),
+                    },
+                    span,
+                );
+                Ok(self.jump(value, winders, (&node.form, &env)))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
 impl Evaluator {
+    /// 对表达式求值
+    ///
+    /// 表达式先被分析为语法树：局部变量解析为环境中的位置，宏调用被展开，特殊形式被识别。
+    /// 顶层的 `begin` 中的表达式在前一个表达式求值之后才分析，
+    /// 之前的表达式定义的宏因此可以在之后的表达式中使用。
+    /// 整个 `begin` 在同一个求值循环中运行，其中捕获的续延也包含之后的表达式。
+    ///
+    /// 尾部位置的表达式（条件分支、过程体的最后一个表达式等）在同一个循环中继续求值，
+    /// 因此任意形式的尾调用，包括互相递归，都只占用常数大小的栈空间。
+    /// 其他位置的子表达式求值完毕后要继续的计算保存在显式的续延栈中，
+    /// 递归的深度不受 Rust 栈的限制，`call/cc` 也可以捕获续延并在之后多次重新进入。
+    ///
+    /// 宏的转换器在独立的续延栈中运行，其中捕获的续延只在本次展开中有效。
     pub fn eval_value(&self, value: &Value, env: &Rc<Environment>) -> EvalResult {
-        match value {
-            Value::Void | Value::Closure { .. } => Ok(Value::Void),
-            Value::Symbol(symbol) => Self::eval_symbol(symbol, env),
-            Value::List(list) => self.eval_list(list, env),
-            Value::Quoted(box_value) => Ok(*box_value.clone()),
-            _ => Ok(value.clone()),
+        let mut machine = Machine::default();
+        let next = Self::eval_toplevel(value.clone(), env, &mut machine);
+        self.run(machine, next, value.span())
+    }
+
+    // 展开顶层表达式开头的宏调用，顶层的 `begin` 之后的表达式留待前面的表达式求值之后再分析
+    fn eval_toplevel(
+        mut expr: Value,
+        env: &Rc<Environment>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        let analyzer = Analyzer::new(env);
+        while let Value::List(list) = &expr {
+            match list.first() {
+                Some(head) if keyword(env, head, None) == Some(Keyword::Begin) => {
+                    return Self::eval_toplevel_forms(&list.tail(), env, machine)
+                }
+                Some(Value::Symbol(_)) => match analyzer.macroexpand_1(list) {
+                    Ok(Some(expansion)) => expr = expansion,
+                    Ok(None) => break,
+                    Err(err) => return Err(err.or_span(list.span())),
+                },
+                _ => break,
+            }
+        }
+
+        Ok(Step::Tail {
+            node: analyzer.analyze(&expr),
+            env: Rc::clone(env),
+        })
+    }
+
+    // 求值顶层 `begin` 中的第一个表达式，其余的表达式保存在续延栈中
+    fn eval_toplevel_forms(
+        forms: &List,
+        env: &Rc<Environment>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        let Some(first) = forms.first().cloned() else {
+            return Ok(Step::Done(Value::Void));
+        };
+        if forms.len() > 1 {
+            let cont = Cont::Toplevel {
+                forms: forms.tail(),
+                env: Rc::clone(env),
+            };
+            machine.push(cont, None);
+        }
+        Self::eval_toplevel(first, env, machine)
+    }
+
+    // 运行求值循环直到续延栈为空，`span` 为 `next` 出错时报告的位置
+    fn run(
+        &self,
+        mut machine: Machine,
+        mut next: Result<Step, RuntimeError>,
+        mut span: Option<Span>,
+    ) -> EvalResult {
+        loop {
+            let step = match next {
+                Ok(step) => step,
+                // 错误先交给当前的处理器，没有处理器时向外传递，并记录最内层出错表达式的位置
+                Err(err) => match machine.handle(err.or_span(span)) {
+                    Ok(step) => step,
+                    Err(err) => return Err(machine.unwind(err)),
+                },
+            };
+            next = match step {
+                Step::Done(value) => {
+                    let Some(pending) = machine.stack.pop() else {
+                        return Ok(value);
+                    };
+                    span = pending.span;
+                    machine.backtrace = pending.backtrace;
+                    self.resume(pending.cont, value, span, &mut machine)
+                }
+                Step::Tail { node, env } => {
+                    span = node.span;
+                    self.step(&node, &env, &mut machine)
+                }
+            };
+        }
+    }
+
+    fn step(
+        &self,
+        node: &Rc<Node>,
+        env: &Rc<Environment>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        if let Some(value) = Self::eval_atom(node, env) {
+            return value.map(Step::Done);
+        }
+        let span = node.span;
+        let (expr, cont) = match &node.kind {
+            NodeKind::Define { variable, value } => (
+                value,
+                Cont::Define {
+                    variable: variable.clone(),
+                    env: Rc::clone(env),
+                },
+            ),
+            NodeKind::DefineMacro { name, value } => (
+                value,
+                Cont::DefineMacro {
+                    name: name.clone(),
+                    env: Rc::clone(env),
+                },
+            ),
+            NodeKind::DefineSyntax { name, value } => (
+                value,
+                Cont::DefineSyntax {
+                    name: name.clone(),
+                    env: Rc::clone(env),
+                },
+            ),
+            NodeKind::Set { variable, value } => (
+                value,
+                Cont::Set {
+                    variable: variable.clone(),
+                    env: Rc::clone(env),
+                },
+            ),
+            NodeKind::If {
+                test,
+                consequent,
+                alternative,
+            } => (
+                test,
+                Cont::If {
+                    consequent: Rc::clone(consequent),
+                    alternative: alternative.clone(),
+                    env: Rc::clone(env),
+                },
+            ),
+            NodeKind::Case { key, clauses } => (
+                key,
+                Cont::Case {
+                    clauses: Rc::clone(clauses),
+                    env: Rc::clone(env),
+                },
+            ),
+            NodeKind::When {
+                test,
+                body,
+                expected,
+            } => (
+                test,
+                Cont::When {
+                    body: Rc::clone(body),
+                    expected: *expected,
+                    env: Rc::clone(env),
+                },
+            ),
+            NodeKind::Call(call) => match Self::eval_atom(&call.operator, env) {
+                Some(procedure) => return self.eval_call(procedure?, call, env, span, machine),
+                None => (
+                    &call.operator,
+                    Cont::Operator {
+                        call: Rc::clone(call),
+                        env: Rc::clone(env),
+                    },
+                ),
+            },
+            NodeKind::Cond(clauses) => return Self::eval_cond(clauses, 0, env, span, machine),
+            NodeKind::And(body) => return Self::eval_and(body, 0, env, span, machine),
+            NodeKind::Or(body) => return Self::eval_or(body, 0, env, span, machine),
+            NodeKind::Sequence(body) => return Ok(Self::eval_body(body, Rc::clone(env), machine)),
+            NodeKind::Let(node) => return Self::eval_let(node, Vec::new(), env, span, machine),
+            NodeKind::NamedLet(node) => {
+                return self.eval_named_let(node, Vec::new(), env, span, machine)
+            }
+            NodeKind::Letrec(node) => {
+                let new_env = Environment::with_slots(env, node.size);
+                return Self::eval_letrec(node, 0, Vec::new(), new_env, span, machine);
+            }
+            NodeKind::Guard(node) => return Ok(Self::eval_guard(node, env, span, machine)),
+            NodeKind::Fail(err) => return Err(err.clone()),
+            NodeKind::Constant(_)
+            | NodeKind::Local { .. }
+            | NodeKind::Global(_)
+            | NodeKind::Lambda(_)
+            | NodeKind::CaseLambda(_) => unreachable!(),
+        };
+        Self::eval_then(expr, env, cont, span, machine)
+    }
+
+    // 求值没有子表达式的节点，其他节点返回 `None`
+    fn eval_atom(node: &Node, env: &Rc<Environment>) -> Option<EvalResult> {
+        let value = match &node.kind {
+            NodeKind::Constant(value) => Ok(value.clone()),
+            NodeKind::Local {
+                depth,
+                index,
+                symbol,
+            } => env
+                .slot(*depth, *index)
+                .ok_or_else(|| Self::undefined_variable(symbol)),
+            NodeKind::Global(symbol) => env
+                .lookup(symbol)
+                .ok_or_else(|| Self::undefined_variable(symbol)),
+            NodeKind::Lambda(lambda) => Ok(Value::Closure(Self::closure(lambda, env))),
+            NodeKind::CaseLambda(clauses) => Ok(Value::CaseLambda(
+                clauses
+                    .iter()
+                    .map(|lambda| Self::closure(lambda, env))
+                    .collect(),
+            )),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn undefined_variable(symbol: &Symbol) -> RuntimeError {
+        RuntimeError::new(
+            RuntimeErrorKind::UndefinedVariable(symbol.to_string()),
+            symbol.span(),
+        )
+    }
+
+    // 在环境 `env` 中创建分析得到的过程
+    fn closure(lambda: &Rc<Lambda>, env: &Rc<Environment>) -> Closure {
+        Closure {
+            name: lambda.name.clone(),
+            params: Rc::clone(&lambda.params),
+            body: lambda.forms.clone(),
+            environment: Rc::clone(env),
+            span: lambda.span,
+            lambda: Some(Rc::clone(lambda)),
         }
     }
 
-    fn eval_symbol(symbol: &str, env: &Rc<Environment>) -> EvalResult {
-        let value = env
-            .get(symbol)
-            .ok_or(RuntimeError::UndefinedVariable(symbol.into()))?;
-        Ok(value)
+    // 在非尾部位置求值 `node`，得到的值交给 `cont` 继续计算
+    fn eval_then(
+        node: &Rc<Node>,
+        env: &Rc<Environment>,
+        cont: Cont,
+        span: Option<Span>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        let step = match Self::eval_atom(node, env) {
+            Some(value) => Step::Done(value?),
+            None => Step::Tail {
+                node: Rc::clone(node),
+                env: Rc::clone(env),
+            },
+        };
+        machine.push(cont, span);
+        Ok(step)
     }
 
-    fn eval_list(&self, list: &[Value], env: &Rc<Environment>) -> EvalResult {
-        let (first, rest) = list.split_first().ok_or(RuntimeError::EmptyList)?;
-        match first {
+    // 以子表达式的值继续之前保存的计算，`span` 为保存这一计算时记录的位置
+    fn resume(
+        &self,
+        cont: Cont,
+        value: Value,
+        span: Option<Span>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        match cont {
+            Cont::Operator { call, env } => self.eval_call(value, &call, &env, span, machine),
+            Cont::Args {
+                procedure,
+                call,
+                mut args,
+                env,
+            } => {
+                args.push(value);
+                self.eval_args(procedure, &call, args, &env, span, machine)
+            }
+            Cont::Sequence { body, index, env } => {
+                Ok(Self::eval_sequence(body, index, env, machine))
+            }
+            Cont::Toplevel { forms, env } => Self::eval_toplevel_forms(&forms, &env, machine),
+            cont @ (Cont::Define { .. }
+            | Cont::DefineMacro { .. }
+            | Cont::DefineSyntax { .. }
+            | Cont::Set { .. }) => {
+                Self::resume_definition(cont, value, span)?;
+                Ok(Step::Done(Value::Void))
+            }
+            Cont::If {
+                consequent,
+                alternative,
+                env,
+            } => {
+                let branch = if Self::is_true(&value) {
+                    consequent
+                } else if let Some(alternative) = alternative {
+                    alternative
+                } else {
+                    return Ok(Step::Done(Value::Void));
+                };
+                Ok(Step::Tail { node: branch, env })
+            }
+            Cont::Cond {
+                clauses,
+                index,
+                env,
+            } => {
+                if Self::is_true(&value) {
+                    Self::eval_clause(value, &clauses[index], &env, machine)
+                } else {
+                    Self::eval_cond(&clauses, index + 1, &env, span, machine)
+                }
+            }
+            Cont::Case { clauses, env } => Self::eval_case_clauses(value, &clauses, &env, machine),
+            Cont::And { body, index, env } => {
+                if Self::is_true(&value) {
+                    Self::eval_and(&body, index, &env, span, machine)
+                } else {
+                    Ok(Step::Done(value))
+                }
+            }
+            Cont::Or { body, index, env } => {
+                if Self::is_true(&value) {
+                    Ok(Step::Done(value))
+                } else {
+                    Self::eval_or(&body, index, &env, span, machine)
+                }
+            }
+            Cont::When {
+                body,
+                expected,
+                env,
+            } => {
+                if Self::is_true(&value) == expected {
+                    Ok(Self::eval_body(&body, env, machine))
+                } else {
+                    Ok(Step::Done(Value::Void))
+                }
+            }
+            Cont::Receive {
+                clause,
+                value: tested,
+                env,
+            } => self.apply(value, &[tested], &clause, &env, machine),
+            cont @ (Cont::Let { .. }
+            | Cont::NamedLet { .. }
+            | Cont::Letrec { .. }
+            | Cont::Default { .. }) => self.resume_binding(cont, value, span, machine),
+            cont @ (Cont::WindBody { .. }
+            | Cont::Unwind { .. }
+            | Cont::Wind { .. }
+            | Cont::Return { .. }
+            | Cont::Escape { .. }
+            | Cont::Handler { .. }
+            | Cont::Guard { .. }
+            | Cont::Raise { .. }
+            | Cont::Catch { .. }) => self.resume_control(cont, value, machine),
+        }
+    }
+
+    // 以求值得到的值定义或修改变量
+    fn resume_definition(cont: Cont, value: Value, span: Option<Span>) -> Result<(), RuntimeError> {
+        match cont {
+            Cont::Define { variable, env } => Self::define(&variable, value, &env),
+            Cont::DefineMacro { name, env } => {
+                env.set(&name, Self::macro_transformer(&name, value, span)?);
+            }
+            Cont::DefineSyntax { name, env } => {
+                env.set(&name, Self::name_transformer(&name, value)?);
+            }
+            Cont::Set { variable, env } => Self::assign(&variable, value, &env)?,
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    // `define` 总是在当前环境中创建绑定：在顶层重新定义会替换全局变量，
+    // 通过名称引用它的闭包随后会看到新的值；在过程体中则绑定到分析时分配的位置
+    fn define(variable: &Variable, value: Value, env: &Rc<Environment>) {
+        match variable {
+            Variable::Local { index, .. } => env.set_slot(0, *index, value),
+            Variable::Global(name) => env.set(name, value),
+        }
+    }
+
+    // `set!` 修改最近一层已有的绑定，捕获了该绑定的所有闭包都会看到修改后的值
+    fn assign(
+        variable: &Variable,
+        value: Value,
+        env: &Rc<Environment>,
+    ) -> Result<(), RuntimeError> {
+        match variable {
+            Variable::Local { depth, index } => env.set_slot(*depth, *index, value),
+            Variable::Global(name) => env
+                .assign(name, value)
+                .map_err(|err| err.or_span(name.span()))?,
+        }
+        Ok(())
+    }
+
+    // 以初始值或默认值创建绑定，然后继续求值
+    fn resume_binding(
+        &self,
+        cont: Cont,
+        value: Value,
+        span: Option<Span>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        match cont {
+            Cont::Let {
+                node,
+                mut values,
+                env,
+            } => {
+                values.push(value);
+                Self::eval_let(&node, values, &env, span, machine)
+            }
+            Cont::NamedLet {
+                node,
+                mut values,
+                env,
+            } => {
+                values.push(value);
+                self.eval_named_let(&node, values, &env, span, machine)
+            }
+            Cont::Letrec {
+                node,
+                index,
+                mut values,
+                env,
+            } => {
+                if node.sequential {
+                    env.set_slot(0, index, value);
+                } else {
+                    values.push(value);
+                }
+                Self::eval_letrec(&node, index + 1, values, env, span, machine)
+            }
+            Cont::Default { lambda, index, env } => {
+                env.set_slot(0, lambda.params.required.len() + index, value);
+                Ok(Self::eval_default(lambda, index + 1, env, machine))
+            }
+            _ => unreachable!(),
+        }
+    }
+    // 继续 `dynamic-wind`、续延跳转与异常处理中的计算
+    fn resume_control(
+        &self,
+        cont: Cont,
+        value: Value,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        match cont {
+            Cont::WindBody {
+                winder,
+                thunk,
+                form,
+                env,
+            } => {
+                machine.winders = Some(Rc::clone(&winder));
+                machine.push(
+                    Cont::Unwind {
+                        winder,
+                        form: form.clone(),
+                        env: Rc::clone(&env),
+                    },
+                    form.span(),
+                );
+                self.apply(thunk, &[], &form, &env, machine)
+            }
+            Cont::Unwind { winder, form, env } => {
+                machine.winders.clone_from(&winder.parent);
+                machine.push(
+                    Cont::Return {
+                        value,
+                        winders: winder.parent.clone(),
+                    },
+                    None,
+                );
+                self.apply(winder.after.clone(), &[], &form, &env, machine)
+            }
+            Cont::Wind {
+                thunk,
+                winders,
+                form,
+                env,
+            } => {
+                machine.winders = winders;
+                self.apply(thunk, &[], &form, &env, machine)
+            }
+            Cont::Return {
+                value: saved,
+                winders,
+            } => {
+                machine.winders = winders;
+                Ok(Step::Done(saved))
+            }
+            Cont::Escape { .. } | Cont::Handler { .. } | Cont::Guard { .. } => {
+                Ok(Step::Done(value))
+            }
+            Cont::Raise {
+                value: raised,
+                marker,
+                continuable,
+            } => {
+                if continuable {
+                    return Ok(Step::Done(value));
+                }
+                // `raise` 的处理器返回时，在处理器所在的动态环境中再次引发，交给更外层的处理器
+                machine.push(
+                    Cont::Raise {
+                        value: raised.clone(),
+                        marker,
+                        continuable,
+                    },
+                    None,
+                );
+                Err(RuntimeError::raise(raised))
+            }
+            Cont::Catch { node, env } => Ok(Self::eval_guard_clauses(value, &node, &env)),
+            _ => unreachable!(),
+        }
+    }
+
+    // 运算符求值完毕后依次求值参数并调用过程
+    fn eval_call(
+        &self,
+        procedure: Value,
+        call: &Rc<Call>,
+        env: &Rc<Environment>,
+        span: Option<Span>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        // 宏调用在分析时已经展开，运行时得到的宏不能调用
+        if !matches!(
+            procedure,
+            Value::Closure(_)
+                | Value::CaseLambda(_)
+                | Value::InternalFunction(_)
+                | Value::Control(_)
+                | Value::Continuation(_)
+                | Value::Procedure(_)
+        ) {
+            return Err(RuntimeErrorKind::NonCallableValue(procedure).into());
+        }
+        let args = Vec::with_capacity(call.args.len());
+        self.eval_args(procedure, call, args, env, span, machine)
+    }
+
+    // 从左到右求值其余的参数，没有子表达式的参数直接求值，其他参数则先保存已求值的参数
+    fn eval_args(
+        &self,
+        procedure: Value,
+        call: &Rc<Call>,
+        mut args: Vec<Value>,
+        env: &Rc<Environment>,
+        span: Option<Span>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        while let Some(expr) = call.args.get(args.len()) {
+            if let Some(value) = Self::eval_atom(expr, env) {
+                args.push(value?);
+                continue;
+            }
+            let cont = Cont::Args {
+                procedure,
+                call: Rc::clone(call),
+                args,
+                env: Rc::clone(env),
+            };
+            machine.push(cont, span);
+            return Ok(Step::Tail {
+                node: Rc::clone(expr),
+                env: Rc::clone(env),
+            });
+        }
+
+        self.apply(procedure, &args, &call.form, env, machine)
+    }
+    // 参数求值完毕后才进入被调用的过程，此后的错误会在调用栈中记录这一帧
+    fn apply(
+        &self,
+        procedure: Value,
+        args: &[Value],
+        form: &List,
+        env: &Rc<Environment>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        let frame = match &procedure {
+            Value::Closure(closure) => {
+                Frame::new(closure.name.as_deref().map(str::to_string), form)
+            }
+            Value::CaseLambda(_) | Value::Continuation(_) => Frame::new(None, form),
+            Value::InternalFunction(internal_fn) => {
+                Frame::new(Some(internal_fn.name.to_string()), form)
+            }
+            Value::Control(control) => Frame::new(Some(control.to_string()), form),
+            Value::Procedure(procedure) => Frame::new(procedure.name().map(str::to_string), form),
+            _ => return Err(RuntimeErrorKind::NonCallableValue(procedure).into()),
+        };
+        match procedure {
             Value::Closure(closure) => {
-                let params: Vec<Value> = rest
+                machine.record(frame);
+                Self::eval_closure(&closure, args, machine)
+            }
+            // 选择第一个接受该数量参数的分支
+            Value::CaseLambda(clauses) => {
+                match clauses
                     .iter()
-                    .map(|value| self.eval_value(value, env))
-                    .try_collect()?;
-                self.eval_closure(closure, &params, env)
-            }
-            Value::Symbol(_) | Value::List(_) => {
-                let value = self.eval_value(first, env)?;
-                match value {
-                    Value::Closure(closure) => self.eval_closure(&closure, rest, env),
-                    Value::TailCall(tail_call) => self.eval_tail_call(&tail_call, rest, env),
-                    Value::InternalFunction(internal_fn) => {
-                        self.eval_internal_fn(&internal_fn, rest, env)
+                    .find(|closure| closure.params.arity().accepts(args.len()))
+                {
+                    Some(closure) => {
+                        machine.record(frame);
+                        Self::eval_closure(closure, args, machine)
                     }
-                    _ => Err(RuntimeError::NonCallableValue(value.clone())),
+                    None => Err(RuntimeError::from(RuntimeErrorKind::ArityMismatch {
+                        expected: clauses
+                            .iter()
+                            .map(|closure| closure.params.arity())
+                            .collect(),
+                        founded: args.len(),
+                    })
+                    .with_frame(frame)),
                 }
             }
-            Value::Keyword(keyword) => match keyword {
-                Keyword::Define => self.eval_keyword_define(rest, env),
-                Keyword::Lambda => Self::eval_keyword_lambda(rest, env),
-                Keyword::If => self.eval_keyword_if(rest, env),
-            },
-            _ => Err(RuntimeError::NonCallableValue(first.clone())),
+            Value::InternalFunction(internal_fn) => (internal_fn.function)(args, env)
+                .map(Step::Done)
+                .map_err(|err| err.with_frame(frame)),
+            Value::Control(control) => {
+                machine.record(frame);
+                self.apply_control(control, args, form, env, machine)
+            }
+            Value::Continuation(continuation) => {
+                machine.record(frame);
+                machine.throw(&continuation, args, (form, env))
+            }
+            // 编译后的过程在虚拟机中运行
+            Value::Procedure(procedure) => {
+                machine.record(frame);
+                Vm::apply(&procedure, args).map(Step::Done)
+            }
+            _ => unreachable!(),
         }
     }
 
-    fn eval_closure(&self, closure: &Closure, args: &[Value], env: &Rc<Environment>) -> EvalResult {
-        if closure.params.len() != args.len() {
-            return Err(RuntimeError::InvalidArity {
-                expected: closure.params.len(),
+    fn apply_control(
+        &self,
+        control: Control,
+        args: &[Value],
+        form: &List,
+        env: &Rc<Environment>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        match (control, args) {
+            // 接收者在尾部位置调用，续延就是 `call/cc` 返回之后要继续的计算
+            (Control::CallCc, [receiver]) => {
+                let continuation =
+                    Continuation::full(machine.stack.clone(), machine.winders.clone(), env.heap());
+                let args = [Value::Continuation(continuation)];
+                self.apply(receiver.clone(), &args, form, env, machine)
+            }
+            // 逃逸续延不复制续延栈，只在栈中留下标记，跳转时丢弃标记之上的部分
+            (Control::CallEc, [receiver]) => {
+                let id = NEXT_ESCAPE.fetch_add(1, Ordering::Relaxed);
+                machine.push(Cont::Escape { id }, form.span());
+                let continuation = Continuation::escape(id, machine.winders.clone());
+                let args = [Value::Continuation(continuation)];
+                self.apply(receiver.clone(), &args, form, env, machine)
+            }
+            (Control::DynamicWind, [before, thunk, after]) => {
+                let winder = Rc::new(Winder::new(
+                    before.clone(),
+                    after.clone(),
+                    machine.winders.clone(),
+                ));
+                let cont = Cont::WindBody {
+                    winder,
+                    thunk: thunk.clone(),
+                    form: form.clone(),
+                    env: Rc::clone(env),
+                };
+                machine.push(cont, form.span());
+                self.apply(before.clone(), &[], form, env, machine)
+            }
+            // 引发的值先交给求值循环，与内置错误一样由最内层的处理器处理
+            (Control::Raise, [value]) => Err(RuntimeError::raise(value.clone())),
+            (Control::RaiseContinuable, [value]) => match machine.find_handler() {
+                Some(index) => machine.raise(value.clone(), index, true),
+                None => Err(RuntimeError::raise(value.clone())),
+            },
+            (Control::WithExceptionHandler, [handler, thunk]) => {
+                // 处理器在引发异常时才被调用，因此安装时就检查参数的类型
+                handler.try_as_procedure()?;
+                thunk.try_as_procedure()?;
+                let cont = Cont::Handler {
+                    handler: handler.clone(),
+                    form: form.clone(),
+                    env: Rc::clone(env),
+                };
+                machine.push(cont, form.span());
+                self.apply(thunk.clone(), &[], form, env, machine)
+            }
+            // 在 `dynamic-wind` 的动态范围中调用过程，离开时恢复原来的精度
+            (Control::WithPrecision, [bits, thunk]) => {
+                let (before, after) = math::precision_winders(bits)?;
+                let args = [before, thunk.clone(), after];
+                self.apply_control(Control::DynamicWind, &args, form, env, machine)
+            }
+            (Control::SetPrecision(bits), []) => {
+                Numeric::set_precision(bits)?;
+                Ok(Step::Done(Value::Void))
+            }
+            (Control::CallCc | Control::CallEc | Control::Raise | Control::RaiseContinuable, _) => {
+                Err(RuntimeErrorKind::InvalidArity {
+                    expected: 1,
+                    founded: args.len(),
+                }
+                .into())
+            }
+            (Control::WithExceptionHandler | Control::WithPrecision, _) => {
+                Err(RuntimeErrorKind::InvalidArity {
+                    expected: 2,
+                    founded: args.len(),
+                }
+                .into())
+            }
+            (Control::SetPrecision(_), _) => Err(RuntimeErrorKind::InvalidArity {
+                expected: 0,
                 founded: args.len(),
-            });
+            }
+            .into()),
+            (Control::DynamicWind, _) => Err(RuntimeErrorKind::InvalidArity {
+                expected: 3,
+                founded: args.len(),
+            }
+            .into()),
         }
+    }
 
-        let closure_env = closure
-            .environment
-            .upgrade()
-            .ok_or_else(|| RuntimeError::InvalidClosure)?;
-        let new_env = Environment::extend(&closure_env);
-        for (i, param) in closure.params.iter().enumerate() {
-            let arg = self.eval_value(&args[i], env)?;
-            new_env.set(param, arg);
+    // 绑定参数并开始求值过程体，没有提供的可选参数与关键字参数先依次求值默认值
+    fn eval_closure(
+        closure: &Closure,
+        args: &[Value],
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        let lambda = match &closure.lambda {
+            Some(lambda) => Rc::clone(lambda),
+            None => Analyzer::new(&closure.environment).analyze_closure(closure),
+        };
+        let params = &lambda.params;
+        let Some((positional_args, rest_args)) = params.split_args(args) else {
+            return Err(Self::arity_error(params, closure.span, args.len()));
+        };
+
+        // 没有提供的可选参数与关键字参数保持未绑定，之后求值默认值
+        let env = Environment::with_slots(&closure.environment, lambda.size);
+        for (index, arg) in positional_args.iter().enumerate() {
+            env.set_slot(0, index, arg.clone());
+        }
+        let mut index = params.required.len() + params.optional.len();
+        if !params.keys.is_empty() {
+            let keyword_args = params.keyword_args(rest_args)?;
+            for (param, _) in &params.keys {
+                if let Some((_, arg)) = keyword_args.iter().find(|(name, _)| param == name) {
+                    env.set_slot(0, index, (*arg).clone());
+                }
+                index += 1;
+            }
         }
+        if params.rest.is_some() {
+            let list = rest_args
+                .iter()
+                .rev()
+                .fold(Value::List(List::default()), |cdr, car| {
+                    Value::Pair(Pair::alloc(car.clone(), cdr, env.heap()))
+                });
+            env.set_slot(0, index, list);
+        }
+
+        let first = params.required.len();
+        if (first..first + lambda.defaults.len()).all(|index| env.slot(0, index).is_some()) {
+            return Ok(Self::eval_body(&lambda.body, env, machine));
+        }
+        // 默认值在新环境中求值，可以引用之前的参数
+        let body = Cont::Sequence {
+            body: Rc::clone(&lambda.body),
+            index: 0,
+            env: Rc::clone(&env),
+        };
+        machine.push(body, None);
+        Ok(Self::eval_default(lambda, 0, env, machine))
+    }
 
-        let (last_expr, preceding_expr) = closure.body.split_last().unwrap();
-        for expr in preceding_expr {
-            self.eval_value(expr, &new_env)?;
+    // 从第 `index` 个默认值开始，求值下一个没有提供的参数的默认值
+    fn eval_default(
+        lambda: Rc<Lambda>,
+        index: usize,
+        env: Rc<Environment>,
+        machine: &mut Machine,
+    ) -> Step {
+        let first = lambda.params.required.len();
+        let Some(index) = (index..lambda.defaults.len()).find(|i| env.slot(0, first + i).is_none())
+        else {
+            return Step::Done(Value::Void);
+        };
+        let node = Rc::clone(&lambda.defaults[index]);
+        let cont = Cont::Default {
+            lambda,
+            index,
+            env: Rc::clone(&env),
+        };
+        machine.push(cont, None);
+        Step::Tail { node, env }
+    }
+
+    // 在 `env` 中求值过程体，最后一个表达式处于尾部位置
+    fn eval_body(body: &Body, env: Rc<Environment>, machine: &mut Machine) -> Step {
+        match &body[..] {
+            [] => Step::Done(Value::Void),
+            [node] => Step::Tail {
+                node: Rc::clone(node),
+                env,
+            },
+            [node, ..] => {
+                let cont = Cont::Sequence {
+                    body: Rc::clone(body),
+                    index: 1,
+                    env: Rc::clone(&env),
+                };
+                machine.push(cont, None);
+                Step::Tail {
+                    node: Rc::clone(node),
+                    env,
+                }
+            }
         }
-        self.eval_value(last_expr, &new_env)
     }
 
-    fn eval_tail_call(
+    // 从第 `index` 个表达式继续求值过程体
+    fn eval_sequence(
+        body: Body,
+        index: usize,
+        env: Rc<Environment>,
+        machine: &mut Machine,
+    ) -> Step {
+        let Some(node) = body.get(index).cloned() else {
+            return Step::Done(Value::Void);
+        };
+        if index + 1 < body.len() {
+            let cont = Cont::Sequence {
+                body,
+                index: index + 1,
+                env: Rc::clone(&env),
+            };
+            machine.push(cont, None);
+        }
+        Step::Tail { node, env }
+    }
+
+    // `let` 的初始值在外层环境中求值，`values` 为已经求值的初始值，全部求值之后才创建绑定
+    fn eval_let(
+        node: &Rc<Let>,
+        values: Vec<Value>,
+        env: &Rc<Environment>,
+        span: Option<Span>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        if let Some(init) = node.inits.get(values.len()) {
+            let cont = Cont::Let {
+                node: Rc::clone(node),
+                values,
+                env: Rc::clone(env),
+            };
+            return Self::eval_then(init, env, cont, span, machine);
+        }
+
+        let new_env = Environment::with_slots(env, node.size);
+        for (index, value) in values.into_iter().enumerate() {
+            new_env.set_slot(0, index, value);
+        }
+        Ok(Self::eval_body(&node.body, new_env, machine))
+    }
+
+    // 命名 `let` 的初始值同样在外层环境中求值，然后以它们调用循环过程
+    fn eval_named_let(
         &self,
-        tail_call: &TailCall,
-        args: &[Value],
+        node: &Rc<NamedLet>,
+        values: Vec<Value>,
         env: &Rc<Environment>,
-    ) -> Result<Value, RuntimeError> {
-        let TailCall {
-            closure,
-            updates,
-            break_condition,
-            return_expr,
-        } = tail_call;
-
-        if closure.params.len() != args.len() {
-            return Err(RuntimeError::InvalidArity {
-                expected: closure.params.len(),
-                founded: args.len(),
-            });
+        span: Option<Span>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        if let Some(init) = node.inits.get(values.len()) {
+            let cont = Cont::NamedLet {
+                node: Rc::clone(node),
+                values,
+                env: Rc::clone(env),
+            };
+            return Self::eval_then(init, env, cont, span, machine);
         }
 
-        let closure_env = closure
-            .environment
-            .upgrade()
-            .ok_or_else(|| RuntimeError::InvalidClosure)?;
-        let new_env = Environment::extend(&closure_env);
-        for (i, param) in closure.params.iter().enumerate() {
-            let arg = self.eval_value(&args[i], env)?;
-            new_env.set(param, arg);
+        // 循环过程绑定在只对过程体可见的环境中，对它的调用都是尾调用
+        let loop_env = Environment::with_slots(env, 1);
+        let closure = Self::closure(&node.lambda, &loop_env);
+        loop_env.set_slot(0, 0, Value::Closure(closure.clone()));
+        self.apply(Value::Closure(closure), &values, &node.form, env, machine)
+    }
+
+    // 初始值在新的环境 `env` 中求值，`letrec` 在所有初始值求值完毕后才绑定，
+    // `letrec*` 与 `let*` 则依次求值并绑定
+    fn eval_letrec(
+        node: &Rc<Letrec>,
+        index: usize,
+        values: Vec<Value>,
+        env: Rc<Environment>,
+        span: Option<Span>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        if let Some(init) = node.inits.get(index) {
+            let cont = Cont::Letrec {
+                node: Rc::clone(node),
+                index,
+                values,
+                env: Rc::clone(&env),
+            };
+            return Self::eval_then(init, &env, cont, span, machine);
         }
+        for (index, value) in values.into_iter().enumerate() {
+            env.set_slot(0, index, value);
+        }
+        Ok(Self::eval_body(&node.body, env, machine))
+    }
 
-        for expr in &closure.body {
-            self.eval_value(expr, &new_env)?;
+    // 从第 `index` 个子句开始测试 `cond`
+    fn eval_cond(
+        clauses: &Rc<[Clause]>,
+        index: usize,
+        env: &Rc<Environment>,
+        span: Option<Span>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        let Some(clause) = clauses.get(index) else {
+            return Ok(Step::Done(Value::Void));
+        };
+        match &clause.test {
+            Test::Expr(test) => {
+                let cont = Cont::Cond {
+                    clauses: Rc::clone(clauses),
+                    index,
+                    env: Rc::clone(env),
+                };
+                Self::eval_then(test, env, cont, span, machine)
+            }
+            Test::Else | Test::Data(_) => {
+                Self::eval_clause(Value::Bool(true), clause, env, machine)
+            }
         }
+    }
 
-        loop {
-            let args: Vec<Value> = updates
-                .iter()
-                .map(|update| self.eval_value(update, &new_env))
-                .try_collect()?;
-            closure.params.iter().zip(args).for_each(|(param, arg)| {
-                new_env.set(param, arg);
-            });
+    // 以值相等判断 `case` 的键是否与子句中的数据匹配
+    fn eval_case_clauses(
+        key: Value,
+        clauses: &[Clause],
+        env: &Rc<Environment>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        for clause in clauses {
+            let matched = match &clause.test {
+                Test::Data(data) => data.contains(&key),
+                Test::Else | Test::Expr(_) => true,
+            };
+            if matched {
+                return Self::eval_clause(key, clause, env, machine);
+            }
+        }
+        Ok(Step::Done(Value::Void))
+    }
+
+    // 求值被选中的子句，`value` 为测试的结果
+    fn eval_clause(
+        value: Value,
+        clause: &Clause,
+        env: &Rc<Environment>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        match &clause.consequent {
+            Consequent::Value => Ok(Step::Done(value)),
+            Consequent::Receive(receiver) => {
+                let cont = Cont::Receive {
+                    clause: clause.form.clone(),
+                    value,
+                    env: Rc::clone(env),
+                };
+                Self::eval_then(receiver, env, cont, clause.form.span(), machine)
+            }
+            Consequent::Body(body) => Ok(Self::eval_body(body, Rc::clone(env), machine)),
+        }
+    }
 
-            for expr in &closure.body {
-                self.eval_value(expr, &new_env)?;
+    // 从第 `index` 个表达式继续求值 `and`，最后一个表达式处于尾部位置
+    fn eval_and(
+        body: &Body,
+        index: usize,
+        env: &Rc<Environment>,
+        span: Option<Span>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        match &body[index..] {
+            [] => Ok(Step::Done(Value::Bool(true))),
+            [last] => Ok(Step::Tail {
+                node: Rc::clone(last),
+                env: Rc::clone(env),
+            }),
+            [expr, ..] => {
+                let cont = Cont::And {
+                    body: Rc::clone(body),
+                    index: index + 1,
+                    env: Rc::clone(env),
+                };
+                Self::eval_then(expr, env, cont, span, machine)
             }
-            if self.eval_value(break_condition, &new_env)?.try_as_bool()? {
-                break self.eval_value(return_expr, &new_env);
+        }
+    }
+
+    fn eval_or(
+        body: &Body,
+        index: usize,
+        env: &Rc<Environment>,
+        span: Option<Span>,
+        machine: &mut Machine,
+    ) -> Result<Step, RuntimeError> {
+        match &body[index..] {
+            [] => Ok(Step::Done(Value::Bool(false))),
+            [last] => Ok(Step::Tail {
+                node: Rc::clone(last),
+                env: Rc::clone(env),
+            }),
+            [expr, ..] => {
+                let cont = Cont::Or {
+                    body: Rc::clone(body),
+                    index: index + 1,
+                    env: Rc::clone(env),
+                };
+                Self::eval_then(expr, env, cont, span, machine)
             }
         }
     }
 
-    fn eval_internal_fn(
+    // 求值 `guard` 的过程体，其中引发的值由续延栈中的 `guard` 捕获
+    fn eval_guard(
+        node: &Rc<Guard>,
+        env: &Rc<Environment>,
+        span: Option<Span>,
+        machine: &mut Machine,
+    ) -> Step {
+        let cont = Cont::Guard {
+            node: Rc::clone(node),
+            env: Rc::clone(env),
+            winders: machine.winders.clone(),
+        };
+        machine.push(cont, span);
+        Self::eval_body(&node.body, Rc::clone(env), machine)
+    }
+
+    // 以捕获的值绑定 `guard` 的变量后测试子句
+    fn eval_guard_clauses(value: Value, node: &Guard, env: &Rc<Environment>) -> Step {
+        let env = Environment::with_slots(env, node.size);
+        env.set_slot(0, 0, value);
+        Step::Tail {
+            node: Rc::clone(&node.clauses),
+            env,
+        }
+    }
+
+    /// 展开一次宏调用，`form` 不是宏调用时返回 `None`
+    pub fn macroexpand_1(
         &self,
-        internal_fn: &InternalFunction,
-        args: &[Value],
+        form: &Value,
         env: &Rc<Environment>,
-    ) -> EvalResult {
-        let args: Vec<Value> = args
+    ) -> Result<Option<Value>, RuntimeError> {
+        let Value::List(list) = form.to_form() else {
+            return Ok(None);
+        };
+        let Some(Value::Symbol(name)) = list.first() else {
+            return Ok(None);
+        };
+        match env.lookup(name) {
+            Some(procedure) => self.expand(&procedure, &list, env),
+            None => Ok(None),
+        }
+    }
+
+    // 展开宏调用，`procedure` 不是宏时返回 `None`
+    fn expand(
+        &self,
+        procedure: &Value,
+        form: &List,
+        env: &Rc<Environment>,
+    ) -> Result<Option<Value>, RuntimeError> {
+        let (name, expansion) = match procedure {
+            Value::Macro(transformer) => (
+                transformer.name.clone(),
+                self.expand_macro(transformer, &form[1..]),
+            ),
+            Value::Syntax(syntax) => (
+                syntax.name.clone(),
+                expander::expand(syntax, form, &|literal, input| {
+                    is_same_binding(env, &syntax.environment, literal, input, None)
+                })
+                .map(|expansion| expansion.to_form()),
+            ),
+            _ => return Ok(None),
+        };
+        expansion
+            .map(Some)
+            .map_err(|err| err.with_frame(Frame::new(name.as_deref().map(str::to_string), form)))
+    }
+
+    // 以未求值的参数调用宏的转换器，转换器在独立的续延栈中运行
+    pub(crate) fn expand_macro(&self, transformer: &Closure, args: &[Value]) -> EvalResult {
+        let mut machine = Machine::default();
+        let step = Self::eval_closure(transformer, args, &mut machine);
+        Ok(self.run(machine, step, None)?.to_form())
+    }
+
+    // 参数数量不符的错误，附带定义过程的位置
+    pub(crate) fn arity_error(params: &Params, span: Option<Span>, founded: usize) -> RuntimeError {
+        let kind = match params.arity() {
+            Arity {
+                min,
+                max: Some(max),
+            } if min == max => RuntimeErrorKind::InvalidArity {
+                expected: min,
+                founded,
+            },
+            arity => RuntimeErrorKind::ArityMismatch {
+                expected: vec![arity],
+                founded,
+            },
+        };
+        RuntimeError::from(kind).with_note("closure defined here", span)
+    }
+
+    // 解析 `(name . params)` 形式的过程签名
+    pub(crate) fn parse_signature(signature: &Value) -> Result<(Symbol, Params), RuntimeError> {
+        let (name, params) = if let Value::Pair(pair) = signature {
+            (pair.car(), pair.cdr())
+        } else {
+            let (first, rest) = signature
+                .try_as_list()?
+                .split_first()
+                .ok_or(RuntimeErrorKind::EmptyList)?;
+            (first.clone(), Value::List(rest.to_vec().into()))
+        };
+        Ok((name.try_as_symbol()?.clone(), Self::parse_params(&params)?))
+    }
+
+    // 解析形参列表，剩余参数可以写作 `(a b . rest)`、`(a #!rest rest)` 或单独的 `args`，
+    // `#!optional` 与 `#!key` 之后的参数为可选参数与关键字参数，写作 `name` 或 `(name default)`
+    pub(crate) fn parse_params(params: &Value) -> Result<Params, RuntimeError> {
+        let invalid_params = || {
+            RuntimeError::new(
+                RuntimeErrorKind::InvalidSyntax(params.to_datum()),
+                params.span(),
+            )
+        };
+        let (items, tail) = match params {
+            Value::Symbol(rest) => {
+                return Ok(Params {
+                    rest: Some(rest.name().clone()),
+                    ..Params::default()
+                })
+            }
+            Value::List(list) => (list.to_vec(), Value::List(List::default())),
+            Value::Pair(pair) => pair.flatten(),
+            value => {
+                return Err(RuntimeErrorKind::TypeError {
+                    expected: "symbol or list",
+                    founded: value.clone(),
+                }
+                .into())
+            }
+        };
+
+        let mut result = Params::default();
+        match tail {
+            Value::Symbol(rest) => result.rest = Some(rest.name().clone()),
+            Value::List(list) if list.is_empty() => {}
+            _ => return Err(invalid_params()),
+        }
+
+        let mut marker = None;
+        let mut items = items.iter();
+        while let Some(item) = items.next() {
+            match item {
+                Value::Symbol(symbol) if *symbol == "#!optional" || *symbol == "#!key" => {
+                    marker = Some(symbol.original().as_str());
+                }
+                Value::Symbol(symbol) if *symbol == "#!rest" => {
+                    match (items.next(), items.next(), &result.rest) {
+                        (Some(Value::Symbol(rest)), None, None) => {
+                            result.rest = Some(rest.name().clone());
+                        }
+                        _ => return Err(invalid_params()),
+                    }
+                }
+                _ => {
+                    let Some(marker) = marker else {
+                        result.required.push(Self::param_name(item)?);
+                        continue;
+                    };
+                    let param = match item {
+                        Value::Symbol(name) => (name.name().clone(), Value::Bool(false)),
+                        Value::List(list) => match list.as_slice() {
+                            [Value::Symbol(name), default] => {
+                                (name.name().clone(), default.clone())
+                            }
+                            _ => return Err(invalid_params()),
+                        },
+                        _ => return Err(invalid_params()),
+                    };
+                    if marker == "#!optional" {
+                        result.optional.push(param);
+                    } else {
+                        result.keys.push(param);
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    // 参数绑定在符号的名称上，宏展开时重命名的参数不会与使用处的绑定冲突
+    fn param_name(value: &Value) -> Result<Name, RuntimeError> {
+        value.try_as_symbol().map(|symbol| symbol.name().clone())
+    }
+
+    // 解析 `((name init) ...)` 形式的绑定列表
+    pub(crate) fn parse_bindings<'a>(
+        form: &List,
+        bindings: &'a Value,
+    ) -> Result<Vec<(&'a Symbol, &'a Value)>, RuntimeError> {
+        let Value::List(bindings) = bindings else {
+            return Err(Self::invalid_syntax(form));
+        };
+        bindings
             .iter()
-            .map(|value| self.eval_value(value, env))
-            .try_collect()?;
-        (internal_fn.function)(&args, env)
+            .map(|binding| match binding {
+                Value::List(binding) => match binding.as_slice() {
+                    [Value::Symbol(name), init] => Ok((name, init)),
+                    _ => Err(Self::invalid_syntax(form)),
+                },
+                _ => Err(Self::invalid_syntax(form)),
+            })
+            .try_collect()
+    }
+
+    // `guard` 中绑定捕获值的变量与 `cond` 形式的子句，
+    // 没有子句成立时，在 `guard` 所在的动态环境中以 `raise` 再次引发
+    pub(crate) fn guard_clauses(form: &List) -> Result<(Symbol, List), RuntimeError> {
+        let Value::List(spec) = &form[1] else {
+            return Err(Self::invalid_syntax(form));
+        };
+        let Value::Symbol(name) = &spec[0] else {
+            return Err(Self::invalid_syntax(form));
+        };
+
+        let reraise = vec![Value::Control(Control::Raise), Value::Symbol(name.clone())];
+        let fallback = vec![Value::Bool(true), Value::List(reraise.into())];
+        let mut clauses = spec.to_vec();
+        clauses.push(Value::List(fallback.into()));
+        let clauses = match spec.span() {
+            Some(span) => List::from(clauses).with_span(span),
+            None => List::from(clauses),
+        };
+        Ok((name.clone(), clauses))
+    }
+
+    // 只有 `#f` 被视为假
+    pub(crate) fn is_true(value: &Value) -> bool {
+        !matches!(value, Value::Bool(false))
     }
 
-    fn eval_keyword_define(&self, list: &[Value], env: &Rc<Environment>) -> EvalResult {
-        match list {
-            [Value::Symbol(name), value] => {
-                env.set(name, self.eval_value(value, env)?);
-                Ok(Value::Void)
+    // 将 `(quasiquote template)` 转换为构造数据的表达式
+    pub(crate) fn expand_quasiquote(form: &List) -> EvalResult {
+        let [_, template] = form.as_slice() else {
+            return Err(RuntimeErrorKind::InvalidArity {
+                expected: 1,
+                founded: form.len() - 1,
             }
-            [Value::List(lambda_info), body @ ..] => {
-                let (first, rest) = lambda_info.split_first().ok_or(RuntimeError::EmptyList)?;
-                let name = first.try_as_symbol()?;
-                let params: Vec<String> = rest
-                    .iter()
-                    .map(|x| x.try_as_symbol().map(String::from))
-                    .try_collect()?;
+            .into());
+        };
+        // 构造过程中出错时报告整个准引用表达式的位置
+        let expr = match (Self::quasiquote(template, 1)?, form.span()) {
+            (Value::List(code), Some(span))
+                if matches!(code.first(), Some(Value::InternalFunction(_))) =>
+            {
+                Value::List(code.with_span(span))
+            }
+            (expr, _) => expr,
+        };
+        Ok(expr)
+    }
 
-                let closure = Closure::new(Some(name.to_string()), params, body.to_vec(), env);
-                let closure = optimize_closure(closure);
+    // 生成构造准引用模板的表达式，`depth` 为当前所在的准引用层数，只有最外层的反引用会被求值
+    fn quasiquote(template: &Value, depth: usize) -> EvalResult {
+        let empty = || Value::List(List::default());
 
-                env.set(name, closure);
-                Ok(Value::Void)
+        match template {
+            Value::List(list) => match list.as_slice() {
+                [Value::Symbol(head), inner] if head == "unquote" && depth == 1 => {
+                    Ok(inner.clone())
+                }
+                [Value::Symbol(head), ..] if head == "unquote-splicing" && depth == 1 => {
+                    Err(RuntimeErrorKind::SyntaxError(ParseErrorKind::InvalidSyntax(
+                        Token::UnquoteSplicing,
+                    ))
+                    .into())
+                }
+                // 嵌套的准引用与反引用改变层数，自身原样保留
+                [head @ Value::Symbol(symbol), rest @ ..] if symbol == "quasiquote" => {
+                    Self::quasiquote_items(Some(head), rest, empty(), depth + 1)
+                }
+                [head @ Value::Symbol(symbol), rest @ ..]
+                    if depth > 1 && (symbol == "unquote" || symbol == "unquote-splicing") =>
+                {
+                    Self::quasiquote_items(Some(head), rest, empty(), depth - 1)
+                }
+                items => Self::quasiquote_list(items, empty(), depth),
+            },
+            // 带点号的模板，例如 `(a . ,b)
+            Value::Pair(pair) => {
+                let (items, tail) = pair.flatten();
+                Self::quasiquote_list(&items, tail, depth)
             }
-            [value, ..] => Err(RuntimeError::TypeError {
-                expected: "symbol or list",
-                founded: value.clone(),
-            }),
-            [] => Err(RuntimeError::EmptyList),
+            value => Ok(Self::quote(value.clone())),
         }
     }
 
-    fn eval_keyword_lambda(list: &[Value], env: &Rc<Environment>) -> EvalResult {
-        match list {
-            [Value::List(first), body @ ..] => {
-                let params: Vec<String> = first
-                    .iter()
-                    .map(|x| x.try_as_symbol().map(String::from))
-                    .try_collect()?;
-                let closure = Closure::new(None, params, body.to_vec(), env);
-                Ok(Value::Closure(closure))
-            }
-            [Value::Symbol(first), body @ ..] => {
-                let params = vec![first.to_string()];
-                let closure = Closure::new(None, params, body.to_vec(), env);
-                Ok(Value::Closure(closure))
-            }
-            [value, ..] => Err(RuntimeError::TypeError {
-                expected: "symbol or list",
-                founded: value.clone(),
-            }),
-            [] => Err(RuntimeError::EmptyList),
+    // 生成构造以 `tail` 结尾的列表模板的表达式
+    fn quasiquote_list(items: &[Value], tail: Value, depth: usize) -> EvalResult {
+        // `(a . ,b)` 与 `(a unquote b)` 相同，列表末尾的反引用是整个列表的尾部
+        let (items, tail) = match items {
+            [init @ .., head @ Value::Symbol(symbol), last]
+                if symbol == "unquote" && matches!(&tail, Value::List(tail) if tail.is_empty()) =>
+            {
+                (init, Value::List(vec![head.clone(), last.clone()].into()))
+            }
+            _ => (items, tail),
+        };
+        Self::quasiquote_items(None, items, tail, depth)
+    }
+
+    // 原样保留的 `head` 与各个元素由 `list` 构造，最外层的 `,@` 求值得到的列表与之用 `append` 拼接，
+    // 没有需要求值的部分时直接引用整个模板。
+    // 正规列表模板末尾的 `,@` 拼接的值是整个列表的尾部，因此可以是不正规的列表
+    fn quasiquote_items(
+        head: Option<&Value>,
+        items: &[Value],
+        tail: Value,
+        depth: usize,
+    ) -> EvalResult {
+        // 最外层的 `,@` 中求值的表达式
+        let splicing = |item: &Value| match item {
+            Value::List(list) if depth == 1 => match list.as_slice() {
+                [Value::Symbol(head), expr] if head == "unquote-splicing" => Some(expr.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let spliced_tail = match items.split_last() {
+            Some((last, init)) if matches!(&tail, Value::List(tail) if tail.is_empty()) => {
+                splicing(last).map(|expr| (init, expr))
+            }
+            _ => None,
+        };
+        let (items, tail) = match spliced_tail {
+            Some((init, expr)) => (
+                init,
+                Self::call("splice-tail", list::splice_tail, vec![expr]),
+            ),
+            None => match tail {
+                Value::List(tail) if tail.is_empty() => (items, Self::quote(Value::List(tail))),
+                tail => (items, Self::quasiquote(&tail, depth)?),
+            },
+        };
+
+        let mut segments = Vec::new();
+        let mut elements: Vec<Value> = head.cloned().map(Self::quote).into_iter().collect();
+        for item in items {
+            match splicing(item) {
+                Some(expr) => {
+                    if !elements.is_empty() {
+                        segments.push(Self::call("list", list::list, mem::take(&mut elements)));
+                    }
+                    segments.push(expr);
+                }
+                None => elements.push(Self::quasiquote(item, depth)?),
+            }
+        }
+
+        if segments.is_empty() {
+            let quoted: Option<Vec<Value>> = elements.iter().map(Self::unquote).collect();
+            if let (Some(items), Some(tail)) = (quoted, Self::unquote(&tail)) {
+                let datum = match tail {
+                    Value::List(tail) if tail.is_empty() => Value::List(items.into()),
+                    tail => items
+                        .into_iter()
+                        .rev()
+                        .fold(tail, |cdr, car| Value::Pair(Rc::new(Pair::new(car, cdr)))),
+                };
+                return Ok(Self::quote(datum));
+            }
+        }
+        if !elements.is_empty() {
+            segments.push(Self::call("list", list::list, elements));
+        }
+        // 只有最后一个参数可以不是正规列表
+        segments.push(tail);
+        Ok(Self::call("append", list::append, segments))
+    }
+
+    fn quote(value: Value) -> Value {
+        Value::List(vec![Value::Keyword(Keyword::Quote), value].into())
+    }
+
+    // 常量表达式 `(quote datum)` 中的 `datum`
+    fn unquote(expr: &Value) -> Option<Value> {
+        match expr {
+            Value::List(list) => match list.as_slice() {
+                [Value::Keyword(Keyword::Quote), datum] => Some(datum.clone()),
+                _ => None,
+            },
+            _ => None,
         }
     }
 
-    fn eval_keyword_if(&self, list: &[Value], env: &Rc<Environment>) -> EvalResult {
-        match list {
-            [condition, then_expr, else_expr] => {
-                let cond_result = self.eval_value(condition, env)?;
-                match cond_result {
-                    Value::Bool(false) => self.eval_value(else_expr, env),
-                    _ => self.eval_value(then_expr, env),
+    // 以内部过程本身作为运算符的调用，不受同名变量的影响
+    fn call(name: &'static str, function: Function, args: Vec<Value>) -> Value {
+        let procedure = Value::InternalFunction(InternalFunction { name, function });
+        Value::List(
+            std::iter::once(procedure)
+                .chain(args)
+                .collect::<Vec<_>>()
+                .into(),
+        )
+    }
+
+    /// 将求值得到的过程转换为宏的转换器，转换器以闭包的形式保存过程的形参与过程体
+    pub(crate) fn macro_transformer(
+        name: &Symbol,
+        value: Value,
+        span: Option<Span>,
+    ) -> Result<Value, RuntimeError> {
+        let transformer = match value {
+            Value::Closure(transformer) => Closure {
+                name: Some(name.as_str().into()),
+                ..transformer
+            },
+            Value::Procedure(procedure) if procedure.code.clauses.is_none() => Closure {
+                name: Some(name.as_str().into()),
+                params: Rc::clone(&procedure.code.params),
+                body: procedure.code.body.clone(),
+                environment: procedure.environment,
+                span: None,
+                lambda: None,
+            },
+            value => {
+                return Err(RuntimeErrorKind::TypeError {
+                    expected: "procedure",
+                    founded: value,
                 }
+                .into())
             }
-            _ => Err(RuntimeError::InvalidArity {
-                expected: 3,
-                founded: list.len(),
-            }),
+        };
+        Ok(Value::Macro(transformer.with_span(span)))
+    }
+
+    // 记录宏的名称，`value` 不是宏的转换器时报错
+    pub(crate) fn name_transformer(name: &Symbol, value: Value) -> EvalResult {
+        match value {
+            Value::Syntax(syntax) => Ok(Value::Syntax(SyntaxRules {
+                name: Some(name.as_str().into()),
+                ..syntax
+            })),
+            Value::Macro(transformer) => Ok(Value::Macro(Closure {
+                name: Some(name.as_str().into()),
+                ..transformer
+            })),
+            value => Err(RuntimeErrorKind::TypeError {
+                expected: "syntax transformer",
+                founded: value,
+            }
+            .into()),
         }
     }
+
+    // (syntax-rules [ellipsis] (literal ...) (pattern template) ...)
+    pub(crate) fn eval_keyword_syntax_rules(form: &List, env: &Rc<Environment>) -> EvalResult {
+        let (ellipsis, rest) = match &form[1..] {
+            [Value::Symbol(ellipsis), rest @ ..] => (ellipsis.original().name().clone(), rest),
+            rest => (Name::from("..."), rest),
+        };
+        let Some((literals, rules)) = rest.split_first() else {
+            return Err(Self::invalid_syntax(form));
+        };
+
+        let literals: Vec<Name> = literals
+            .try_as_vec()?
+            .iter()
+            .map(Self::param_name)
+            .try_collect()?;
+        let rules: Vec<(Value, Value)> = rules
+            .iter()
+            .map(|rule| match rule.try_as_list()?.as_slice() {
+                [pattern @ (Value::List(_) | Value::Pair(_)), template]
+                    if pattern.try_as_list().map_or(true, |list| !list.is_empty()) =>
+                {
+                    Ok((pattern.clone(), template.clone()))
+                }
+                _ => Err(RuntimeError::new(
+                    RuntimeErrorKind::InvalidSyntax(rule.to_datum()),
+                    rule.span(),
+                )),
+            })
+            .try_collect()?;
+
+        let syntax = SyntaxRules {
+            name: None,
+            ellipsis,
+            literals: literals.into(),
+            rules: rules.into(),
+            environment: Rc::clone(env),
+        };
+        expander::check(&syntax)?;
+        Ok(Value::Syntax(syntax))
+    }
+
+    pub(crate) fn invalid_syntax(form: &List) -> RuntimeError {
+        RuntimeError::new(
+            RuntimeErrorKind::InvalidSyntax(Value::List(form.clone()).to_datum()),
+            form.span(),
+        )
+    }
 }
@@ -0,0 +1,418 @@
+//! `syntax-rules` 的模式匹配与模板展开
+
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::model::{List, Name, Pair, RuntimeError, RuntimeErrorKind, Symbol, SyntaxRules, Value};
+
+// 每次展开使用不同的编号重命名模板中的标识符
+static NEXT_EXPANSION: AtomicUsize = AtomicUsize::new(0);
+
+// 模式变量绑定的输入，省略号之后的变量绑定多个输入
+#[derive(Debug, Clone)]
+enum Binding {
+    One(Value),
+    Many(Vec<Binding>),
+}
+
+type Bindings = HashMap<Name, Binding>;
+
+/// 使用 `syntax-rules` 展开一次宏调用
+///
+/// 依次尝试每条规则，模式的第一个元素对应宏关键字，不参与匹配。
+/// 模式中的字面量只与 `same_binding` 判断为引用同一个绑定的输入标识符匹配，
+/// 模板中不是模式变量的标识符都会被重命名，从而不会捕获使用处的绑定。
+pub fn expand(
+    syntax: &SyntaxRules,
+    form: &List,
+    same_binding: &dyn Fn(&Symbol, &Symbol) -> bool,
+) -> Result<Value, RuntimeError> {
+    let invalid_syntax = || RuntimeErrorKind::InvalidSyntax(Value::List(form.clone()).to_datum());
+    let (_, args) = form.split_first().ok_or_else(invalid_syntax)?;
+
+    for (pattern, template) in syntax.rules.iter() {
+        let (patterns, tail) = split_list(pattern).ok_or_else(invalid_syntax)?;
+        let matcher = Matcher {
+            syntax,
+            same_binding,
+        };
+        let mut bindings = Bindings::new();
+        if matcher.match_sequence(&patterns[1..], &tail, args, &empty_list(), &mut bindings) {
+            let mut expansion = Expansion {
+                syntax,
+                id: NEXT_EXPANSION.fetch_add(1, Ordering::Relaxed),
+                renames: HashMap::new(),
+            };
+            return expansion.expand(template, &bindings, true);
+        }
+    }
+
+    Err(RuntimeError::new(invalid_syntax(), form.span()))
+}
+
+/// 检查 `syntax-rules` 的每个模式
+///
+/// 同一个模式中的模式变量不能重复，每一层中最多只能有一个省略号，否则返回无效语法的错误。
+pub fn check(syntax: &SyntaxRules) -> Result<(), RuntimeError> {
+    // 检查模式时不比较字面量与输入
+    let matcher = Matcher {
+        syntax,
+        same_binding: &|_, _| false,
+    };
+    for (pattern, _) in syntax.rules.iter() {
+        let invalid_syntax = || {
+            RuntimeError::new(
+                RuntimeErrorKind::InvalidSyntax(pattern.to_datum()),
+                pattern.span(),
+            )
+        };
+        // 模式的第一个元素对应宏关键字，不是模式变量
+        let (patterns, tail) = split_list(pattern).ok_or_else(invalid_syntax)?;
+        let rest = build_list(patterns[1..].to_vec(), tail);
+        let mut variables = HashSet::new();
+        if !matcher.has_single_ellipsis(&rest)
+            || !matcher
+                .variables(&rest)
+                .into_iter()
+                .all(|variable| variables.insert(variable))
+        {
+            return Err(invalid_syntax());
+        }
+    }
+    Ok(())
+}
+
+fn empty_list() -> Value {
+    Value::List(List::default())
+}
+
+fn is_empty_list(value: &Value) -> bool {
+    matches!(value, Value::List(list) if list.is_empty())
+}
+
+// 将列表或序对链拆分为元素与尾部，正规列表的尾部为空列表
+fn split_list(value: &Value) -> Option<(Vec<Value>, Value)> {
+    match value {
+        Value::List(list) => Some((list.to_vec(), empty_list())),
+        Value::Pair(pair) => Some(pair.flatten()),
+        _ => None,
+    }
+}
+
+// 由元素与尾部构造列表，尾部不是空列表时构造序对链
+fn build_list(items: Vec<Value>, tail: Value) -> Value {
+    if is_empty_list(&tail) {
+        Value::List(items.into())
+    } else {
+        items
+            .into_iter()
+            .rev()
+            .fold(tail, |cdr, car| Value::Pair(Rc::new(Pair::new(car, cdr))))
+    }
+}
+
+struct Matcher<'a> {
+    syntax: &'a SyntaxRules,
+    // 比较字面量与输入的标识符是否引用同一个绑定
+    same_binding: &'a dyn Fn(&Symbol, &Symbol) -> bool,
+}
+
+impl Matcher<'_> {
+    fn is_ellipsis(&self, value: &Value) -> bool {
+        matches!(value, Value::Symbol(symbol) if *symbol.original().name() == self.syntax.ellipsis)
+    }
+
+    fn is_literal(&self, symbol: &Symbol) -> bool {
+        self.syntax
+            .literals
+            .iter()
+            .any(|literal| literal == symbol.name())
+    }
+
+    fn match_pattern(&self, pattern: &Value, form: &Value, bindings: &mut Bindings) -> bool {
+        match pattern {
+            Value::Symbol(symbol) if symbol.original().as_str() == "_" => true,
+            // 字面量只与引用同一个绑定的标识符匹配
+            Value::Symbol(symbol) if self.is_literal(symbol) => {
+                matches!(form, Value::Symbol(input) if (self.same_binding)(symbol, input))
+            }
+            Value::Symbol(symbol) => {
+                bindings.insert(symbol.name().clone(), Binding::One(form.clone()));
+                true
+            }
+            Value::List(_) | Value::Pair(_) => {
+                let (patterns, pattern_tail) = split_list(pattern).unwrap();
+                match split_list(form) {
+                    Some((forms, form_tail)) => {
+                        self.match_sequence(&patterns, &pattern_tail, &forms, &form_tail, bindings)
+                    }
+                    None => false,
+                }
+            }
+            _ => pattern == form,
+        }
+    }
+
+    // 匹配 `(p ... pe <ellipsis> q ... . tail)` 形式的模式
+    fn match_sequence(
+        &self,
+        patterns: &[Value],
+        pattern_tail: &Value,
+        forms: &[Value],
+        form_tail: &Value,
+        bindings: &mut Bindings,
+    ) -> bool {
+        let Some(position) = patterns
+            .iter()
+            .position(|pattern| self.is_ellipsis(pattern))
+        else {
+            if forms.len() < patterns.len() {
+                return false;
+            }
+            let (head, rest) = forms.split_at(patterns.len());
+            if !patterns
+                .iter()
+                .zip(head)
+                .all(|(pattern, form)| self.match_pattern(pattern, form, bindings))
+            {
+                return false;
+            }
+            // 模式的尾部匹配剩余的所有输入
+            return if is_empty_list(pattern_tail) {
+                rest.is_empty() && is_empty_list(form_tail)
+            } else {
+                let rest = build_list(rest.to_vec(), form_tail.clone());
+                self.match_pattern(pattern_tail, &rest, bindings)
+            };
+        };
+
+        let Some((repeated, before)) = patterns[..position].split_last() else {
+            return false;
+        };
+        let after = &patterns[position + 1..];
+        if forms.len() < before.len() + after.len() {
+            return false;
+        }
+        let count = forms.len() - before.len() - after.len();
+
+        if !before
+            .iter()
+            .zip(forms)
+            .all(|(pattern, form)| self.match_pattern(pattern, form, bindings))
+        {
+            return false;
+        }
+
+        let mut matches = Vec::with_capacity(count);
+        for form in &forms[before.len()..before.len() + count] {
+            let mut inner = Bindings::new();
+            if !self.match_pattern(repeated, form, &mut inner) {
+                return false;
+            }
+            matches.push(inner);
+        }
+        // 即使没有匹配任何输入，省略号之前的变量也要绑定为空序列
+        for variable in self.variables(repeated) {
+            let many = matches
+                .iter_mut()
+                .map(|inner| inner.remove(&variable).unwrap())
+                .collect();
+            bindings.insert(variable, Binding::Many(many));
+        }
+
+        self.match_sequence(
+            after,
+            pattern_tail,
+            &forms[before.len() + count..],
+            form_tail,
+            bindings,
+        )
+    }
+
+    // 模式的每一层中最多只能有一个省略号
+    fn has_single_ellipsis(&self, pattern: &Value) -> bool {
+        match pattern {
+            Value::List(_) | Value::Pair(_) => {
+                let (mut patterns, tail) = split_list(pattern).unwrap();
+                if patterns
+                    .iter()
+                    .filter(|pattern| self.is_ellipsis(pattern))
+                    .count()
+                    > 1
+                {
+                    return false;
+                }
+                if !is_empty_list(&tail) {
+                    patterns.push(tail);
+                }
+                patterns
+                    .iter()
+                    .all(|pattern| self.has_single_ellipsis(pattern))
+            }
+            _ => true,
+        }
+    }
+
+    // 模式中出现的所有模式变量
+    fn variables(&self, pattern: &Value) -> Vec<Name> {
+        match pattern {
+            Value::Symbol(symbol)
+                if symbol.original().as_str() != "_"
+                    && !self.is_literal(symbol)
+                    && !self.is_ellipsis(pattern) =>
+            {
+                vec![symbol.name().clone()]
+            }
+            Value::List(_) | Value::Pair(_) => {
+                let (mut patterns, tail) = split_list(pattern).unwrap();
+                if !is_empty_list(&tail) {
+                    patterns.push(tail);
+                }
+                patterns
+                    .iter()
+                    .flat_map(|pattern| self.variables(pattern))
+                    .collect()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+struct Expansion<'a> {
+    syntax: &'a SyntaxRules,
+    id: usize,
+    // 同一次展开中相同的标识符重命名为同一个符号
+    renames: HashMap<Name, Symbol>,
+}
+
+impl Expansion<'_> {
+    fn is_ellipsis(&self, value: &Value, enabled: bool) -> bool {
+        enabled
+            && matches!(value, Value::Symbol(symbol) if *symbol.original().name() == self.syntax.ellipsis)
+    }
+
+    fn invalid_template(template: &Value) -> RuntimeError {
+        RuntimeError::new(
+            RuntimeErrorKind::InvalidSyntax(template.to_datum()),
+            template.span(),
+        )
+    }
+
+    // `ellipsis` 为假时省略号被 `(... template)` 转义，只作为普通标识符
+    fn expand(
+        &mut self,
+        template: &Value,
+        bindings: &Bindings,
+        ellipsis: bool,
+    ) -> Result<Value, RuntimeError> {
+        match template {
+            Value::Symbol(symbol) => match bindings.get(symbol.name()) {
+                Some(Binding::One(value)) => Ok(value.clone()),
+                // 模式变量之后的省略号数量少于模式中的数量
+                Some(Binding::Many(_)) => Err(Self::invalid_template(template)),
+                None => {
+                    let renamed = self
+                        .renames
+                        .entry(symbol.name().clone())
+                        .or_insert_with(|| {
+                            Symbol::rename(symbol, &self.syntax.environment, self.id)
+                        })
+                        .clone();
+                    Ok(Value::Symbol(renamed))
+                }
+            },
+            Value::List(_) | Value::Pair(_) => {
+                let (templates, tail) = split_list(template).unwrap();
+                if let [first, escaped] = templates.as_slice() {
+                    if self.is_ellipsis(first, ellipsis) && is_empty_list(&tail) {
+                        return self.expand(escaped, bindings, false);
+                    }
+                }
+
+                let mut items = Vec::with_capacity(templates.len());
+                let mut templates = templates.iter().peekable();
+                while let Some(item) = templates.next() {
+                    let mut depth = 0;
+                    while templates
+                        .next_if(|next| self.is_ellipsis(next, ellipsis))
+                        .is_some()
+                    {
+                        depth += 1;
+                    }
+                    if depth == 0 {
+                        items.push(self.expand(item, bindings, ellipsis)?);
+                    } else {
+                        items.extend(self.expand_ellipsis(item, depth, bindings, ellipsis)?);
+                    }
+                }
+                let tail = if is_empty_list(&tail) {
+                    tail
+                } else {
+                    self.expand(&tail, bindings, ellipsis)?
+                };
+
+                Ok(match (build_list(items, tail), template.span()) {
+                    (Value::List(list), Some(span)) => Value::List(list.with_span(span)),
+                    (value, _) => value,
+                })
+            }
+            value => Ok(value.clone()),
+        }
+    }
+
+    // 展开 `template` 后跟 `depth` 个省略号的部分，由其中绑定了多个输入的变量驱动重复
+    fn expand_ellipsis(
+        &mut self,
+        template: &Value,
+        depth: usize,
+        bindings: &Bindings,
+        ellipsis: bool,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let variables: Vec<(&Name, &Vec<Binding>)> = bindings
+            .iter()
+            .filter_map(|(name, binding)| match binding {
+                Binding::Many(many) if Self::mentions(template, name) => Some((name, many)),
+                _ => None,
+            })
+            .collect();
+        let Some(count) = variables.first().map(|(_, many)| many.len()) else {
+            return Err(Self::invalid_template(template));
+        };
+        if variables.iter().any(|(_, many)| many.len() != count) {
+            return Err(Self::invalid_template(template));
+        }
+
+        let mut items = Vec::with_capacity(count);
+        for i in 0..count {
+            let mut inner = bindings.clone();
+            for (name, many) in &variables {
+                inner.insert((*name).clone(), many[i].clone());
+            }
+            if depth == 1 {
+                items.push(self.expand(template, &inner, ellipsis)?);
+            } else {
+                items.extend(self.expand_ellipsis(template, depth - 1, &inner, ellipsis)?);
+            }
+        }
+        Ok(items)
+    }
+
+    fn mentions(template: &Value, name: &Name) -> bool {
+        match template {
+            Value::Symbol(symbol) => symbol.name() == name,
+            Value::List(_) | Value::Pair(_) => {
+                let (mut templates, tail) = split_list(template).unwrap();
+                if !is_empty_list(&tail) {
+                    templates.push(tail);
+                }
+                templates
+                    .iter()
+                    .any(|template| Self::mentions(template, name))
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::rc::Rc;
+
+use crate::model::{Condition, Environment, RuntimeError, RuntimeErrorKind, Value};
+
+use super::list;
+
+fn expect_condition(args: &[Value]) -> Result<&Condition, RuntimeError> {
+    match args {
+        [Value::Condition(condition)] => Ok(condition),
+        [value] => Err(RuntimeErrorKind::TypeError {
+            expected: "error object",
+            founded: value.clone(),
+        }
+        .into()),
+        _ => Err(RuntimeErrorKind::InvalidArity {
+            expected: 1,
+            founded: args.len(),
+        }
+        .into()),
+    }
+}
+
+pub fn error(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (error "message" irritant ...) 以新的错误对象引发错误
+    let Some((message, irritants)) = args.split_first() else {
+        return Err(RuntimeErrorKind::InvalidArity {
+            expected: 1,
+            founded: 0,
+        }
+        .into());
+    };
+    let Value::String(message) = message else {
+        return Err(RuntimeErrorKind::TypeError {
+            expected: "string",
+            founded: message.clone(),
+        }
+        .into());
+    };
+    Err(RuntimeErrorKind::Error {
+        message: message.to_string(),
+        irritants: irritants.to_vec(),
+    }
+    .into())
+}
+
+pub fn is_error_object(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    match args {
+        [value] => Ok(matches!(value, Value::Condition(_)).into()),
+        _ => Err(RuntimeErrorKind::InvalidArity {
+            expected: 1,
+            founded: args.len(),
+        }
+        .into()),
+    }
+}
+
+pub fn error_object_message(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    Ok(Value::String(expect_condition(args)?.message().into()))
+}
+
+pub fn error_object_irritants(
+    args: &[Value],
+    env: &Rc<Environment>,
+) -> Result<Value, RuntimeError> {
+    list::list(&expect_condition(args)?.irritants(), env)
+}
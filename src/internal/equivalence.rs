@@ -0,0 +1,42 @@
+use std::rc::Rc;
+
+use crate::model::{Environment, RuntimeError, RuntimeErrorKind, Value};
+
+pub fn is_eq(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (eq? obj1 obj2) 判断两个对象是否为同一个对象
+    match args {
+        [lhs, rhs] => Ok(Value::Bool(eq(lhs, rhs))),
+        _ => Err(RuntimeErrorKind::InvalidArity {
+            expected: 2,
+            founded: args.len(),
+        }
+        .into()),
+    }
+}
+
+fn eq(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        // 拼写相同的符号共享符号表中的同一个名称
+        (Value::Symbol(lhs), Value::Symbol(rhs)) => lhs.name() == rhs.name(),
+        // 空列表只有一个
+        (Value::List(lhs), Value::List(rhs)) => {
+            (lhs.is_empty() && rhs.is_empty()) || lhs.ptr_eq(rhs)
+        }
+        (Value::String(lhs), Value::String(rhs)) => Rc::ptr_eq(lhs, rhs),
+        (Value::Pair(lhs), Value::Pair(rhs)) => Rc::ptr_eq(lhs, rhs),
+        // 过程与错误对象按身份比较，分别创建的过程即使代码相同也不是同一个对象
+        (Value::Closure(lhs), Value::Closure(rhs)) | (Value::Macro(lhs), Value::Macro(rhs)) => {
+            lhs.ptr_eq(rhs)
+        }
+        (Value::CaseLambda(lhs), Value::CaseLambda(rhs)) => Rc::ptr_eq(lhs, rhs),
+        (Value::Procedure(lhs), Value::Procedure(rhs)) => lhs == rhs,
+        (Value::Continuation(lhs), Value::Continuation(rhs)) => Rc::ptr_eq(lhs, rhs),
+        (Value::Condition(lhs), Value::Condition(rhs)) => Rc::ptr_eq(lhs, rhs),
+        // 精确数与非精确数不是同一个对象
+        (Value::Numeric(lhs), Value::Numeric(rhs)) => {
+            lhs.is_exact() == rhs.is_exact() && lhs == rhs
+        }
+        // 其余的值没有独立的身份，按值比较
+        _ => lhs == rhs,
+    }
+}
@@ -0,0 +1,34 @@
+use std::rc::Rc;
+
+use crate::{
+    evaluator::Evaluator,
+    model::{Environment, RuntimeError, RuntimeErrorKind, Value},
+};
+
+fn expect_form(args: &[Value]) -> Result<&Value, RuntimeError> {
+    match args {
+        [form] => Ok(form),
+        _ => Err(RuntimeErrorKind::InvalidArity {
+            expected: 1,
+            founded: args.len(),
+        }
+        .into()),
+    }
+}
+
+pub fn macroexpand_1(args: &[Value], env: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (macroexpand-1 '(my-macro x)) => 展开一次的结果
+    let form = expect_form(args)?;
+    Ok(Evaluator
+        .macroexpand_1(form, env)?
+        .unwrap_or_else(|| form.clone()))
+}
+
+pub fn macroexpand(args: &[Value], env: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // 反复展开直到形式的开头不再是宏
+    let mut form = expect_form(args)?.clone();
+    while let Some(expansion) = Evaluator.macroexpand_1(&form, env)? {
+        form = expansion;
+    }
+    Ok(form)
+}
@@ -0,0 +1,42 @@
+use std::rc::Rc;
+
+use rug::Integer;
+
+use crate::model::{Environment, RuntimeError, RuntimeErrorKind, Symbol, Value};
+
+fn expect_no_args(args: &[Value]) -> Result<(), RuntimeError> {
+    if args.is_empty() {
+        Ok(())
+    } else {
+        Err(RuntimeErrorKind::InvalidArity {
+            expected: 0,
+            founded: args.len(),
+        }
+        .into())
+    }
+}
+
+pub fn gc(args: &[Value], env: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (gc) => 回收的对象数量
+    expect_no_args(args)?;
+    Ok(Integer::from(env.heap().collect()).into())
+}
+
+pub fn gc_stats(args: &[Value], env: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (gc-stats) => ((live-objects n) (bytes n) (collections n) (freed-objects n))
+    expect_no_args(args)?;
+    let stats = env.heap().stats();
+    let entry = |name: &str, value: usize| {
+        Value::List(vec![Symbol::from(name).into(), Integer::from(value).into()].into())
+    };
+
+    Ok(Value::List(
+        vec![
+            entry("live-objects", stats.live_objects),
+            entry("bytes", stats.bytes),
+            entry("collections", stats.collections),
+            entry("freed-objects", stats.freed_objects),
+        ]
+        .into(),
+    ))
+}
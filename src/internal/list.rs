@@ -0,0 +1,136 @@
+use std::rc::Rc;
+
+use crate::model::{Environment, List, Pair, RuntimeError, RuntimeErrorKind, Value};
+
+fn expect_arity(args: &[Value], expected: usize) -> Result<(), RuntimeError> {
+    if args.len() == expected {
+        Ok(())
+    } else {
+        Err(RuntimeErrorKind::InvalidArity {
+            expected,
+            founded: args.len(),
+        }
+        .into())
+    }
+}
+
+fn pair_type_error(value: &Value) -> RuntimeError {
+    RuntimeErrorKind::TypeError {
+        expected: "pair",
+        founded: value.clone(),
+    }
+    .into()
+}
+
+// 非空的列表字面量也是序对，但不可修改
+fn expect_mutable_pair(value: &Value) -> Result<&Rc<Pair>, RuntimeError> {
+    match value {
+        Value::Pair(pair) => Ok(pair),
+        Value::List(list) if !list.is_empty() => Err(RuntimeErrorKind::TypeError {
+            expected: "mutable pair",
+            founded: value.clone(),
+        }
+        .into()),
+        value => Err(pair_type_error(value)),
+    }
+}
+
+pub fn cons(args: &[Value], env: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (cons 1 2) => (1 . 2)
+    expect_arity(args, 2)?;
+    let pair = Pair::alloc(args[0].clone(), args[1].clone(), env.heap());
+    Ok(Value::Pair(pair))
+}
+
+pub fn car(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (car '(1 2)) => 1
+    expect_arity(args, 1)?;
+    match &args[0] {
+        Value::Pair(pair) => Ok(pair.car()),
+        Value::List(list) if !list.is_empty() => Ok(list[0].clone()),
+        value => Err(pair_type_error(value)),
+    }
+}
+
+pub fn cdr(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (cdr '(1 2)) => (2)
+    expect_arity(args, 1)?;
+    match &args[0] {
+        Value::Pair(pair) => Ok(pair.cdr()),
+        Value::List(list) if !list.is_empty() => Ok(Value::List(list.tail())),
+        value => Err(pair_type_error(value)),
+    }
+}
+
+pub fn set_car(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // 字面量列表不可修改，只有序对可以
+    expect_arity(args, 2)?;
+    expect_mutable_pair(&args[0])?.set_car(args[1].clone());
+    Ok(Value::Void)
+}
+
+pub fn set_cdr(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    expect_arity(args, 2)?;
+    expect_mutable_pair(&args[0])?.set_cdr(args[1].clone());
+    Ok(Value::Void)
+}
+
+pub fn is_pair(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    expect_arity(args, 1)?;
+    let result = match &args[0] {
+        Value::Pair(_) => true,
+        Value::List(list) => !list.is_empty(),
+        _ => false,
+    };
+    Ok(result.into())
+}
+
+pub fn is_null(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    expect_arity(args, 1)?;
+    Ok(matches!(&args[0], Value::List(list) if list.is_empty()).into())
+}
+
+pub fn list(args: &[Value], env: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (list 1 2 3) => (1 . (2 . (3 . ())))
+    let list = args
+        .iter()
+        .rev()
+        .fold(Value::List(List::default()), |cdr, car| {
+            Value::Pair(Pair::alloc(car.clone(), cdr, env.heap()))
+        });
+    Ok(list)
+}
+
+pub fn append(args: &[Value], env: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (append '(1) '(2 3) 4) => (1 2 3 . 4)，结果与最后一个参数共享结构
+    let Some((last, lists)) = args.split_last() else {
+        return Ok(Value::List(List::default()));
+    };
+    let mut items = Vec::new();
+    for list in lists {
+        items.extend(list.try_as_vec()?);
+    }
+    Ok(items.into_iter().rev().fold(last.clone(), |cdr, car| {
+        Value::Pair(Pair::alloc(car, cdr, env.heap()))
+    }))
+}
+
+/// 准引用模板末尾的 `,@` 拼接的值，可以是以任意值结尾的列表，结果是新分配的序对链
+pub fn splice_tail(args: &[Value], env: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // `(1 ,@'(2 . 3)) => (1 2 . 3)
+    expect_arity(args, 1)?;
+    let (items, tail) = match &args[0] {
+        Value::List(list) => (list.to_vec(), Value::List(List::default())),
+        Value::Pair(pair) => pair.flatten(),
+        value => {
+            return Err(RuntimeErrorKind::TypeError {
+                expected: "list",
+                founded: value.clone(),
+            }
+            .into())
+        }
+    };
+    Ok(items.into_iter().rev().fold(tail, |cdr, car| {
+        Value::Pair(Pair::alloc(car, cdr, env.heap()))
+    }))
+}
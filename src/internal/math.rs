@@ -1,13 +1,13 @@
 use std::rc::Rc;
 
-use rug::Float;
+use rug::{float::Constant, Complex, Float, Integer, Rational};
 
-use crate::model::{Environment, Numeric, RuntimeError, Value};
+use crate::model::{Control, Environment, Numeric, RuntimeError, RuntimeErrorKind, Value};
 
 pub fn add(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
     // (+ num1 num2 num3) => 0 + num1 + num2 + num3
     args.iter()
-        .try_fold(Numeric::Integer(0.into()), |acc, arg| {
+        .try_fold(Numeric::Fixnum(0), |acc, arg| {
             arg.try_as_numeric().map(|n| acc + n)
         })
         .map(Into::into)
@@ -17,13 +17,14 @@ pub fn sub(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
     // (- num) => 0 - num
     // (- num1 num2 num3) => num1 - num2 - num3
     match args {
-        [] => Err(RuntimeError::InvalidArity {
+        [] => Err(RuntimeErrorKind::InvalidArity {
             expected: 1,
             founded: 0,
-        }),
+        }
+        .into()),
         [single_arg] => single_arg
             .try_as_numeric()
-            .map(|n| Numeric::Integer(0.into()) - n)
+            .map(|n| Numeric::Fixnum(0) - n)
             .map(Into::into),
         [first_arg, rest @ ..] => rest
             .iter()
@@ -37,7 +38,7 @@ pub fn sub(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
 pub fn mul(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
     // (* num1 num2 num3) => 1 * num1 * num2 * num3
     args.iter()
-        .try_fold(Numeric::Integer(1.into()), |acc, arg| {
+        .try_fold(Numeric::Fixnum(1), |acc, arg| {
             arg.try_as_numeric().map(|n| acc * n)
         })
         .map(Into::into)
@@ -47,17 +48,18 @@ pub fn div(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
     // (/ num) => 1 / num
     // (/ num1 num2 num3) => num1 / num2 / num3
     match args {
-        [] => Err(RuntimeError::InvalidArity {
+        [] => Err(RuntimeErrorKind::InvalidArity {
             expected: 1,
             founded: 0,
-        }),
+        }
+        .into()),
         [single_arg] => single_arg
             .try_as_numeric()
             .and_then(|n| {
                 if n.is_zero() {
-                    Err(RuntimeError::DivideByZero)
+                    Err(RuntimeErrorKind::DivideByZero.into())
                 } else {
-                    Ok(Numeric::Float(Float::with_val(53, 1.0)) / n)
+                    Ok(Numeric::Fixnum(1) / n)
                 }
             })
             .map(Into::into),
@@ -66,7 +68,7 @@ pub fn div(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
             let result = rest.iter().try_fold(first_numeric, |acc, arg| {
                 arg.try_as_numeric().and_then(|n| {
                     if n.is_zero() {
-                        Err(RuntimeError::DivideByZero)
+                        Err(RuntimeErrorKind::DivideByZero.into())
                     } else {
                         Ok(acc / n)
                     }
@@ -79,10 +81,11 @@ pub fn div(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
 
 pub fn numeric_equal(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
     if args.is_empty() {
-        return Err(RuntimeError::InvalidArity {
+        return Err(RuntimeErrorKind::InvalidArity {
             expected: 1,
             founded: 0,
-        });
+        }
+        .into());
     }
 
     let numbers: Vec<Numeric> = args.iter().map(|arg| arg.try_as_numeric()).try_collect()?;
@@ -90,3 +93,172 @@ pub fn numeric_equal(args: &[Value], _: &Rc<Environment>) -> Result<Value, Runti
 
     Ok(result.into())
 }
+
+fn expect_numeric(args: &[Value]) -> Result<Numeric, RuntimeError> {
+    match args {
+        [value] => value.try_as_numeric(),
+        _ => Err(RuntimeErrorKind::InvalidArity {
+            expected: 1,
+            founded: args.len(),
+        }
+        .into()),
+    }
+}
+
+// 与参数值相等的有理数，无穷大与 NaN 报错
+fn rational_of(args: &[Value]) -> Result<(Numeric, Rational), RuntimeError> {
+    let n = expect_numeric(args)?;
+    let rational = n.to_rational().ok_or_else(|| RuntimeErrorKind::TypeError {
+        expected: "finite real number",
+        founded: args[0].clone(),
+    })?;
+    Ok((n, rational))
+}
+
+// 结果与参数同样精确
+fn with_exactness(n: &Numeric, result: Numeric) -> Value {
+    if n.is_exact() {
+        result.into()
+    } else {
+        result.to_inexact().into()
+    }
+}
+
+pub fn numerator(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (numerator 6/4) => 3
+    let (n, rational) = rational_of(args)?;
+    Ok(with_exactness(&n, rational.into_numer_denom().0.into()))
+}
+
+pub fn denominator(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (denominator 6/4) => 2
+    let (n, rational) = rational_of(args)?;
+    Ok(with_exactness(&n, rational.into_numer_denom().1.into()))
+}
+
+pub fn exact(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (exact 0.5) => 1/2
+    Ok(rational_of(args)?.1.into())
+}
+
+pub fn inexact(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (inexact 1/2) => 0.5
+    Ok(expect_numeric(args)?.to_inexact().into())
+}
+
+// 实数参数转换为浮点数，复数报错
+fn real_of(value: &Value) -> Result<(Numeric, Float), RuntimeError> {
+    let n = value.try_as_numeric()?;
+    let float = n.to_float().ok_or_else(|| RuntimeErrorKind::TypeError {
+        expected: "real number",
+        founded: value.clone(),
+    })?;
+    Ok((n, float))
+}
+
+fn expect_two(args: &[Value]) -> Result<(&Value, &Value), RuntimeError> {
+    match args {
+        [first, second] => Ok((first, second)),
+        _ => Err(RuntimeErrorKind::InvalidArity {
+            expected: 2,
+            founded: args.len(),
+        }
+        .into()),
+    }
+}
+
+pub fn make_rectangular(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (make-rectangular 1 2) => 1+2i
+    let (real, imag) = expect_two(args)?;
+    let (real, real_float) = real_of(real)?;
+    let (imag, imag_float) = real_of(imag)?;
+    // 虚部为精确的零时结果是实部本身
+    if imag.is_exact() && imag.is_zero() {
+        return Ok(real.into());
+    }
+    let precision = real_float.prec().max(imag_float.prec());
+    Ok(Complex::with_val(precision, (real_float, imag_float)).into())
+}
+
+pub fn make_polar(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (make-polar 2 0) => 2
+    let (magnitude, angle) = expect_two(args)?;
+    let (magnitude, magnitude_float) = real_of(magnitude)?;
+    let (angle, angle_float) = real_of(angle)?;
+    if angle.is_exact() && angle.is_zero() {
+        return Ok(magnitude.into());
+    }
+    let precision = magnitude_float.prec().max(angle_float.prec());
+    let real = Float::with_val(precision, angle_float.cos_ref()) * &magnitude_float;
+    let imag = Float::with_val(precision, angle_float.sin_ref()) * &magnitude_float;
+    Ok(Complex::with_val(precision, (real, imag)).into())
+}
+
+pub fn real_part(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (real-part 1+2i) => 1.0
+    Ok(match expect_numeric(args)? {
+        Numeric::Complex(c) => Numeric::Float(c.into_real_imag().0),
+        real => real,
+    }
+    .into())
+}
+
+pub fn imag_part(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (imag-part 1+2i) => 2.0，实数的虚部为精确的零
+    Ok(match expect_numeric(args)? {
+        Numeric::Complex(c) => Numeric::Float(c.into_real_imag().1),
+        _ => Numeric::Fixnum(0),
+    }
+    .into())
+}
+
+pub fn magnitude(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (magnitude 3+4i) => 5.0，实数的模为其绝对值
+    Ok(match expect_numeric(args)? {
+        Numeric::Fixnum(n) => n
+            .checked_abs()
+            .map_or_else(|| Integer::from(n).abs().into(), Numeric::Fixnum),
+        Numeric::Integer(n) => n.abs().into(),
+        Numeric::Rational(n) => n.abs().into(),
+        Numeric::Float(f) => Numeric::Float(f.abs()),
+        Numeric::Complex(c) => Numeric::Float(Float::with_val(c.prec().0, c.abs_ref())),
+    }
+    .into())
+}
+
+pub fn angle(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (angle +i) => pi/2，负实数的辐角为 pi
+    let n = expect_numeric(args)?;
+    let negative = match &n {
+        Numeric::Fixnum(n) => *n < 0,
+        Numeric::Integer(n) => *n < 0,
+        Numeric::Rational(n) => *n < 0,
+        Numeric::Float(f) => *f < 0,
+        Numeric::Complex(c) => return Ok(Float::with_val(c.prec().0, c.arg_ref()).into()),
+    };
+    Ok(if negative {
+        Float::with_val(Numeric::precision(), Constant::Pi).into()
+    } else if n.is_exact() {
+        Numeric::Fixnum(0).into()
+    } else {
+        Float::new(Numeric::precision()).into()
+    })
+}
+
+/// `with-precision` 进入与离开动态范围时调用的过程，分别设置精度为 `bits` 与当前的精度
+pub(crate) fn precision_winders(bits: &Value) -> Result<(Value, Value), RuntimeError> {
+    let precision = match bits {
+        Value::Numeric(Numeric::Fixnum(n)) => u32::try_from(*n)
+            .ok()
+            .filter(|&n| Numeric::is_valid_precision(n)),
+        _ => None,
+    }
+    .ok_or_else(|| RuntimeErrorKind::TypeError {
+        expected: "precision in bits",
+        founded: bits.clone(),
+    })?;
+    Ok((
+        Value::Control(Control::SetPrecision(precision)),
+        Value::Control(Control::SetPrecision(Numeric::precision())),
+    ))
+}
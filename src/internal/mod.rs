@@ -2,11 +2,17 @@ use std::rc::Rc;
 
 use crate::model::{Environment, RuntimeError, Value};
 
+pub mod condition;
+pub mod equivalence;
+pub mod expand;
+pub mod gc;
+pub mod list;
 pub mod math;
+pub mod symbol;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct InternalFunction {
-    pub name: String,
+    pub name: &'static str,
     pub function: Function,
 }
 
@@ -0,0 +1,32 @@
+use std::rc::Rc;
+
+use crate::model::{Environment, RuntimeError, RuntimeErrorKind, Symbol, Value};
+
+fn expect_one(args: &[Value]) -> Result<&Value, RuntimeError> {
+    match args {
+        [value] => Ok(value),
+        _ => Err(RuntimeErrorKind::InvalidArity {
+            expected: 1,
+            founded: args.len(),
+        }
+        .into()),
+    }
+}
+
+pub fn string_to_symbol(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (string->symbol "name") => 符号表中拼写为 name 的符号
+    match expect_one(args)? {
+        Value::String(name) => Ok(Value::Symbol(Symbol::new(&**name))),
+        value => Err(RuntimeErrorKind::TypeError {
+            expected: "string",
+            founded: value.clone(),
+        }
+        .into()),
+    }
+}
+
+pub fn symbol_to_string(args: &[Value], _: &Rc<Environment>) -> Result<Value, RuntimeError> {
+    // (symbol->string 'name) => "name"
+    let symbol = expect_one(args)?.try_as_symbol()?;
+    Ok(Value::String(symbol.original().as_str().into()))
+}
@@ -2,16 +2,31 @@ use std::rc::Rc;
 
 use crate::{
     evaluator::Evaluator,
-    internal::{math, InternalFunction},
+    internal::{condition, equivalence, expand, gc, list, math, symbol, InternalFunction},
     lexer::TokenStream,
-    model::{Environment, RuntimeError, Value},
+    model::{Control, Environment, GcStats, Numeric, RuntimeError, SourceId, Value},
     parser::Parser,
+    vm::Vm,
 };
 
+/// 执行表达式的方式
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Backend {
+    /// 直接遍历语法树求值
+    #[default]
+    TreeWalking,
+    /// 先编译为字节码，再由虚拟机执行
+    Bytecode,
+}
+
 #[derive(Default)]
 pub struct Interpreter {
     environment: Rc<Environment>,
     evaluator: Evaluator,
+    vm: Vm,
+    backend: Backend,
+    /// 浮点数的默认精度，未指定时使用 [`Numeric::DEFAULT_PRECISION`]
+    precision: Option<u32>,
 }
 
 impl Interpreter {
@@ -19,60 +34,328 @@ impl Interpreter {
         Self {
             environment: Self::initialize_environment(),
             evaluator: Evaluator,
+            vm: Vm,
+            backend: Backend::default(),
+            precision: None,
         }
     }
 
+    /// 使用指定的方式执行表达式
+    #[must_use]
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// 使用指定位数的浮点数精度读取与计算表达式，`bits` 超出 MPFR 支持的范围时返回错误
+    pub fn with_precision(mut self, bits: u32) -> Result<Self, RuntimeError> {
+        self.precision = Some(Numeric::check_precision(bits)?);
+        Ok(self)
+    }
+
     fn initialize_environment() -> Rc<Environment> {
         let env = Environment::new();
 
         env.set(
             "+",
             Value::InternalFunction(InternalFunction {
-                name: "+".to_string(),
+                name: "+",
                 function: math::add,
             }),
         );
         env.set(
             "-",
             Value::InternalFunction(InternalFunction {
-                name: "-".to_string(),
+                name: "-",
                 function: math::sub,
             }),
         );
         env.set(
             "*",
             Value::InternalFunction(InternalFunction {
-                name: "*".to_string(),
+                name: "*",
                 function: math::mul,
             }),
         );
         env.set(
             "/",
             Value::InternalFunction(InternalFunction {
-                name: "/".to_string(),
+                name: "/",
                 function: math::div,
             }),
         );
         env.set(
             "=",
             Value::InternalFunction(InternalFunction {
-                name: "=".to_string(),
+                name: "=",
                 function: math::numeric_equal,
             }),
         );
+        env.set(
+            "numerator",
+            Value::InternalFunction(InternalFunction {
+                name: "numerator",
+                function: math::numerator,
+            }),
+        );
+        env.set(
+            "denominator",
+            Value::InternalFunction(InternalFunction {
+                name: "denominator",
+                function: math::denominator,
+            }),
+        );
+        env.set(
+            "exact",
+            Value::InternalFunction(InternalFunction {
+                name: "exact",
+                function: math::exact,
+            }),
+        );
+        env.set(
+            "inexact",
+            Value::InternalFunction(InternalFunction {
+                name: "inexact",
+                function: math::inexact,
+            }),
+        );
+        env.set(
+            "make-rectangular",
+            Value::InternalFunction(InternalFunction {
+                name: "make-rectangular",
+                function: math::make_rectangular,
+            }),
+        );
+        env.set(
+            "make-polar",
+            Value::InternalFunction(InternalFunction {
+                name: "make-polar",
+                function: math::make_polar,
+            }),
+        );
+        env.set(
+            "real-part",
+            Value::InternalFunction(InternalFunction {
+                name: "real-part",
+                function: math::real_part,
+            }),
+        );
+        env.set(
+            "imag-part",
+            Value::InternalFunction(InternalFunction {
+                name: "imag-part",
+                function: math::imag_part,
+            }),
+        );
+        env.set(
+            "magnitude",
+            Value::InternalFunction(InternalFunction {
+                name: "magnitude",
+                function: math::magnitude,
+            }),
+        );
+        env.set(
+            "angle",
+            Value::InternalFunction(InternalFunction {
+                name: "angle",
+                function: math::angle,
+            }),
+        );
+        env.set(
+            "cons",
+            Value::InternalFunction(InternalFunction {
+                name: "cons",
+                function: list::cons,
+            }),
+        );
+        env.set(
+            "car",
+            Value::InternalFunction(InternalFunction {
+                name: "car",
+                function: list::car,
+            }),
+        );
+        env.set(
+            "cdr",
+            Value::InternalFunction(InternalFunction {
+                name: "cdr",
+                function: list::cdr,
+            }),
+        );
+        env.set(
+            "set-car!",
+            Value::InternalFunction(InternalFunction {
+                name: "set-car!",
+                function: list::set_car,
+            }),
+        );
+        env.set(
+            "set-cdr!",
+            Value::InternalFunction(InternalFunction {
+                name: "set-cdr!",
+                function: list::set_cdr,
+            }),
+        );
+        env.set(
+            "pair?",
+            Value::InternalFunction(InternalFunction {
+                name: "pair?",
+                function: list::is_pair,
+            }),
+        );
+        env.set(
+            "null?",
+            Value::InternalFunction(InternalFunction {
+                name: "null?",
+                function: list::is_null,
+            }),
+        );
+        env.set(
+            "list",
+            Value::InternalFunction(InternalFunction {
+                name: "list",
+                function: list::list,
+            }),
+        );
+        env.set(
+            "append",
+            Value::InternalFunction(InternalFunction {
+                name: "append",
+                function: list::append,
+            }),
+        );
+        for (name, control) in [
+            ("call-with-current-continuation", Control::CallCc),
+            ("call/cc", Control::CallCc),
+            ("call-with-escape-continuation", Control::CallEc),
+            ("call/ec", Control::CallEc),
+            ("dynamic-wind", Control::DynamicWind),
+            ("raise", Control::Raise),
+            ("raise-continuable", Control::RaiseContinuable),
+            ("with-exception-handler", Control::WithExceptionHandler),
+            ("with-precision", Control::WithPrecision),
+        ] {
+            env.set(name, Value::Control(control));
+        }
+        env.set(
+            "error",
+            Value::InternalFunction(InternalFunction {
+                name: "error",
+                function: condition::error,
+            }),
+        );
+        env.set(
+            "error-object?",
+            Value::InternalFunction(InternalFunction {
+                name: "error-object?",
+                function: condition::is_error_object,
+            }),
+        );
+        env.set(
+            "error-object-message",
+            Value::InternalFunction(InternalFunction {
+                name: "error-object-message",
+                function: condition::error_object_message,
+            }),
+        );
+        env.set(
+            "error-object-irritants",
+            Value::InternalFunction(InternalFunction {
+                name: "error-object-irritants",
+                function: condition::error_object_irritants,
+            }),
+        );
+        env.set(
+            "eq?",
+            Value::InternalFunction(InternalFunction {
+                name: "eq?",
+                function: equivalence::is_eq,
+            }),
+        );
+        env.set(
+            "string->symbol",
+            Value::InternalFunction(InternalFunction {
+                name: "string->symbol",
+                function: symbol::string_to_symbol,
+            }),
+        );
+        env.set(
+            "symbol->string",
+            Value::InternalFunction(InternalFunction {
+                name: "symbol->string",
+                function: symbol::symbol_to_string,
+            }),
+        );
+        env.set(
+            "macroexpand-1",
+            Value::InternalFunction(InternalFunction {
+                name: "macroexpand-1",
+                function: expand::macroexpand_1,
+            }),
+        );
+        env.set(
+            "macroexpand",
+            Value::InternalFunction(InternalFunction {
+                name: "macroexpand",
+                function: expand::macroexpand,
+            }),
+        );
+        env.set(
+            "gc",
+            Value::InternalFunction(InternalFunction {
+                name: "gc",
+                function: gc::gc,
+            }),
+        );
+        env.set(
+            "gc-stats",
+            Value::InternalFunction(InternalFunction {
+                name: "gc-stats",
+                function: gc::gc_stats,
+            }),
+        );
 
         env
     }
 
     pub fn eval(&self, input: &str) -> Result<Value, RuntimeError> {
-        let token_stream = TokenStream::new(input);
+        self.eval_source(input, SourceId::default())
+    }
+
+    /// 执行编号为 `source` 的源码，错误中的位置都指向这段源码
+    pub fn eval_source(&self, input: &str, source: SourceId) -> Result<Value, RuntimeError> {
+        // 精度属于当前线程，执行期间使用这个解释器的精度，字面量也按它读取，
+        // 无论是否出错，返回时都恢复原来的精度
+        let _precision =
+            Numeric::scoped_precision(self.precision.unwrap_or(Numeric::DEFAULT_PRECISION))?;
+        let token_stream = TokenStream::new(input).with_source(source);
         let mut parser = Parser::new(token_stream);
         let parse_resuilt = parser.parse()?;
 
         let mut last_result = Value::Void;
         for expr in parse_resuilt {
-            last_result = self.evaluator.eval_value(&expr, &self.environment)?;
+            last_result = match self.backend {
+                Backend::TreeWalking => self.evaluator.eval_value(&expr, &self.environment)?,
+                Backend::Bytecode => self.vm.eval_value(&expr, &self.environment)?,
+            };
         }
         Ok(last_result)
     }
+
+    /// 立即执行一次垃圾回收，返回回收的对象数量
+    pub fn collect_garbage(&self) -> usize {
+        self.environment.heap().collect()
+    }
+
+    pub fn gc_stats(&self) -> GcStats {
+        self.environment.heap().stats()
+    }
+}
+
+impl Drop for Interpreter {
+    // 全局环境总是作为根存活，释放解释器时先清空全局环境，再回收由此变得不可达的对象
+    fn drop(&mut self) {
+        self.environment.clear();
+        self.environment.heap().collect();
+    }
 }
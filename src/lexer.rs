@@ -1,8 +1,8 @@
 use rug::ops::CompleteRound;
-use rug::{Complete, Float, Integer};
+use rug::{Complete, Complex, Float, Integer, Rational};
 use std::str::Chars;
 
-use crate::model::{Token, TokenizeError};
+use crate::model::{Numeric, SourceId, Span, Spanned, Token, TokenizeError, TokenizeErrorKind};
 
 // 字符串解析状态，普通或者转义
 enum State {
@@ -11,7 +11,16 @@ enum State {
 }
 
 /// 词法解析结果
-pub type LexResult = Result<Token, TokenizeError>;
+pub type LexResult = Result<Spanned<Token>, TokenizeError>;
+
+// 源码中的某个字符位置
+#[derive(Clone, Copy)]
+struct Cursor {
+    offset: usize,
+    line: usize,
+    column: usize,
+    source: SourceId,
+}
 
 /// 标记流
 pub struct TokenStream<'a> {
@@ -19,8 +28,19 @@ pub struct TokenStream<'a> {
     pending_token: Option<LexResult>,
     // 字符缓冲区
     char_buffer: String,
+    // 缓冲区内容的起始位置与结束偏移
+    buffer_start: Cursor,
+    buffer_end: usize,
     // 字符迭代器
     input_chars: Chars<'a>,
+    // 下一个字符的位置
+    cursor: Cursor,
+}
+
+impl Cursor {
+    fn span_to(self, end: usize) -> Span {
+        Span::new(self.offset, end, self.line, self.column).with_source(self.source)
+    }
 }
 
 impl<'a> TokenStream<'a> {
@@ -32,11 +52,40 @@ impl<'a> TokenStream<'a> {
     /// let token_stream = TokenStream::new(expression);
     /// ```
     pub fn new(s: &'a str) -> Self {
+        let start = Cursor {
+            offset: 0,
+            line: 1,
+            column: 1,
+            source: SourceId::default(),
+        };
         Self {
             pending_token: None,
             char_buffer: String::new(),
+            buffer_start: start,
+            buffer_end: 0,
             input_chars: s.chars(),
+            cursor: start,
+        }
+    }
+
+    /// 标记的位置属于编号为 `source` 的源码
+    pub fn with_source(mut self, source: SourceId) -> Self {
+        self.cursor.source = source;
+        self.buffer_start.source = source;
+        self
+    }
+
+    // 读取下一个字符并更新位置
+    fn next_char(&mut self) -> Option<char> {
+        let ch = self.input_chars.next()?;
+        self.cursor.offset += ch.len_utf8();
+        if ch == '\n' {
+            self.cursor.line += 1;
+            self.cursor.column = 1;
+        } else {
+            self.cursor.column += 1;
         }
+        Some(ch)
     }
 
     fn parse_buffered_char(&mut self) -> Option<LexResult> {
@@ -46,26 +95,38 @@ impl<'a> TokenStream<'a> {
             return self.next();
         }
 
-        // 如果不是数字这解析为 Symbol
-        if let Ok(v) = Integer::parse(&token_str) {
-            Some(Ok(Token::Integer(v.complete())))
+        // 单独的点用于序对，如果不是数字这解析为 Symbol
+        let token = if token_str == "." {
+            Token::Dot
+        } else if let Ok(v) = Integer::parse(&token_str) {
+            Token::Integer(v.complete())
+        } else if let Ok(v) = Rational::parse(&token_str) {
+            Token::Rational(v.complete())
         } else if let Ok(v) = Float::parse(&token_str) {
-            Some(Ok(Token::Float(v.complete(53))))
+            Token::Float(v.complete(Numeric::precision()))
+        } else if let Some(v) = parse_complex(&token_str) {
+            Token::Complex(v)
         } else {
-            Some(Ok(Token::Symbol(token_str)))
-        }
+            Token::Symbol(token_str.into())
+        };
+        let span = self.buffer_start.span_to(self.buffer_end);
+        Some(Ok(Spanned::new(token, span)))
     }
 
-    fn parse_string(&mut self) -> LexResult {
+    fn parse_string(&mut self, start: Cursor) -> LexResult {
         let mut state = State::Normal;
         let mut string_content = String::new();
 
-        for ch in self.input_chars.by_ref() {
+        while let Some(ch) = self.next_char() {
             match state {
                 State::Normal => match ch {
-                    '\\' => state = State::Escaped,                  // 进入转义状态
-                    '"' => return Ok(Token::String(string_content)), // 字符串结束
-                    _ => string_content.push(ch),                    // 添加字符到缓存中
+                    '\\' => state = State::Escaped, // 进入转义状态
+                    // 字符串结束
+                    '"' => {
+                        let span = start.span_to(self.cursor.offset);
+                        return Ok(Spanned::new(Token::String(string_content), span));
+                    }
+                    _ => string_content.push(ch), // 添加字符到缓存中
                 },
                 State::Escaped => {
                     string_content.push(match ch {
@@ -78,33 +139,59 @@ impl<'a> TokenStream<'a> {
             }
         }
         // 字符串未闭合，正常应该在之前的循环中 return
-        Err(TokenizeError::UnclosedString)
+        Err(TokenizeError::new(
+            TokenizeErrorKind::UnclosedString,
+            start.span_to(self.cursor.offset),
+        ))
     }
 
-    /// 进行词法解析，返回标记列表
+    /// 进行词法解析，返回带有位置信息的标记列表
     /// ```rust
     /// # use lemon_lisp::{
     /// #     lexer::TokenStream,
-    /// #     model::Token
+    /// #     model::{Span, Spanned, Token}
     /// # };
     /// #
     /// let token_stream = TokenStream::new("(+ 1)");
     ///
     /// assert_eq!(
     ///     Ok(vec![
-    ///         Token::LParen,
-    ///         Token::Symbol("+".into()),
-    ///         Token::Integer(1.into()),
-    ///         Token::RParen
+    ///         Spanned::new(Token::LParen, Span::new(0, 1, 1, 1)),
+    ///         Spanned::new(Token::Symbol("+".into()), Span::new(1, 2, 1, 2)),
+    ///         Spanned::new(Token::Integer(1.into()), Span::new(3, 4, 1, 4)),
+    ///         Spanned::new(Token::RParen, Span::new(4, 5, 1, 5)),
     ///     ]),
     ///     token_stream.tokenize()
     /// );
     /// ```
-    pub fn tokenize(mut self) -> Result<Vec<Token>, TokenizeError> {
+    pub fn tokenize(mut self) -> Result<Vec<Spanned<Token>>, TokenizeError> {
         self.try_collect()
     }
 }
 
+// 解析 `a+bi`、`a-bi`、`+bi` 与 `+i` 形式的复数，虚部必须带有符号
+fn parse_complex(token: &str) -> Option<Complex> {
+    let precision = Numeric::precision();
+    let body = token.strip_suffix('i')?;
+    // 虚部从最后一个不属于指数的符号开始
+    let (split, _) = body
+        .char_indices()
+        .rev()
+        .find(|&(i, ch)| matches!(ch, '+' | '-') && !body[..i].ends_with(['e', 'E']))?;
+    let (real, imag) = body.split_at(split);
+    let real = if real.is_empty() {
+        Float::new(precision)
+    } else {
+        Float::parse(real).ok()?.complete(precision)
+    };
+    let imag = match imag {
+        "+" => Float::with_val(precision, 1),
+        "-" => Float::with_val(precision, -1),
+        imag => Float::parse(imag).ok()?.complete(precision),
+    };
+    Some(Complex::with_val(precision, (real, imag)))
+}
+
 impl<'a> Iterator for TokenStream<'a> {
     type Item = LexResult;
 
@@ -113,8 +200,9 @@ impl<'a> Iterator for TokenStream<'a> {
             return Some(token);
         }
 
-        match self.input_chars.next() {
-            Some(ch) => self.process_char(ch),
+        let start = self.cursor;
+        match self.next_char() {
+            Some(ch) => self.process_char(ch, start),
             // 没有字符的时候检查缓冲区是否还有内容
             None => {
                 if !self.char_buffer.is_empty() {
@@ -128,17 +216,18 @@ impl<'a> Iterator for TokenStream<'a> {
 }
 
 impl<'a> TokenStream<'a> {
-    fn process_char(&mut self, ch: char) -> Option<LexResult> {
+    fn process_char(&mut self, ch: char, start: Cursor) -> Option<LexResult> {
+        let char_span = start.span_to(self.cursor.offset);
         match ch {
             // 左右括号
             // 对应设置下一个 Token
             // 解析缓冲区内容
             '(' | '[' => {
-                self.pending_token = Some(Ok(Token::LParen));
+                self.pending_token = Some(Ok(Spanned::new(Token::LParen, char_span)));
                 self.parse_buffered_char()
             }
             ')' | ']' => {
-                self.pending_token = Some(Ok(Token::RParen));
+                self.pending_token = Some(Ok(Spanned::new(Token::RParen, char_span)));
                 self.parse_buffered_char()
             }
 
@@ -149,12 +238,15 @@ impl<'a> TokenStream<'a> {
             // 如果缓冲区为空，表示字符串解析成功
             // 否则解析成功，但存在多余字符
             '"' => {
-                let result = self.parse_string();
+                let result = self.parse_string(start);
                 if self.char_buffer.is_empty() {
                     Some(result)
                 } else {
                     self.pending_token = Some(result);
-                    Some(Err(TokenizeError::UnexpectedChar('"')))
+                    Some(Err(TokenizeError::new(
+                        TokenizeErrorKind::UnexpectedChar('"'),
+                        char_span,
+                    )))
                 }
             }
 
@@ -163,25 +255,55 @@ impl<'a> TokenStream<'a> {
             // 不为空时说明在符号中间插入了单引号，不合语法
             '\'' => {
                 if self.char_buffer.is_empty() {
-                    Some(Ok(Token::Quote))
+                    Some(Ok(Spanned::new(Token::Quote, char_span)))
                 } else {
-                    Some(Err(TokenizeError::UnexpectedChar('\'')))
+                    Some(Err(TokenizeError::new(
+                        TokenizeErrorKind::UnexpectedChar('\''),
+                        char_span,
+                    )))
+                }
+            }
+
+            // 准引用与反引用，规则与引用相同
+            // 逗号后紧跟 @ 时解析为 UnquoteSplicing
+            '`' | ',' => {
+                if !self.char_buffer.is_empty() {
+                    return Some(Err(TokenizeError::new(
+                        TokenizeErrorKind::UnexpectedChar(ch),
+                        char_span,
+                    )));
                 }
+                let token = match ch {
+                    '`' => Token::Quasiquote,
+                    _ if self.input_chars.clone().next() == Some('@') => {
+                        self.next_char();
+                        Token::UnquoteSplicing
+                    }
+                    _ => Token::Unquote,
+                };
+                Some(Ok(Spanned::new(token, start.span_to(self.cursor.offset))))
             }
 
             // 注释符忽略此行
             ';' => {
-                while self.input_chars.next().is_some_and(|c| c != '\n') {}
+                while self.next_char().is_some_and(|c| c != '\n') {}
                 self.parse_buffered_char()
             }
 
             // 不是非法字符就加入缓冲区
             _ => {
-                if !matches!(ch, '\\' | '{' | '}' | ',' | '`' | '|') {
+                if !matches!(ch, '\\' | '{' | '}' | '|') {
+                    if self.char_buffer.is_empty() {
+                        self.buffer_start = start;
+                    }
                     self.char_buffer.push(ch);
+                    self.buffer_end = self.cursor.offset;
                     self.next()
                 } else {
-                    Some(Err(TokenizeError::UnexpectedChar(ch)))
+                    Some(Err(TokenizeError::new(
+                        TokenizeErrorKind::UnexpectedChar(ch),
+                        char_span,
+                    )))
                 }
             }
         }
@@ -2,10 +2,24 @@
 #![feature(let_chains)]
 #[warn(clippy::all, clippy::pedantic)]
 #[allow(clippy::missing_errors_doc)]
+pub mod analyzer;
+#[warn(clippy::all, clippy::pedantic)]
+#[allow(clippy::missing_errors_doc)]
+pub mod compiler;
+pub mod diagnostic;
+#[warn(clippy::all, clippy::pedantic)]
+#[allow(clippy::missing_errors_doc)]
 pub mod evaluator;
+pub mod expander;
 pub mod internal;
 pub mod interpreter;
 pub mod lexer;
 pub mod model;
-pub mod optimizer;
 pub mod parser;
+#[warn(clippy::all, clippy::pedantic)]
+mod scope;
+#[warn(clippy::all, clippy::pedantic)]
+#[allow(clippy::missing_errors_doc)]
+pub mod vm;
+#[warn(clippy::all, clippy::pedantic)]
+mod winding;
@@ -0,0 +1,117 @@
+use std::{collections::VecDeque, rc::Rc};
+
+use super::{Code, Environment, Frame, List, Span, Tracer, Value, Winder};
+
+/// 调用处，即代码中记录的第 `index` 个调用表达式
+#[derive(Clone)]
+pub(crate) struct Site {
+    pub code: Rc<Code>,
+    pub index: usize,
+}
+
+impl Site {
+    pub fn form(&self) -> &List {
+        &self.code.forms[self.index]
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.form().span()
+    }
+}
+
+/// 虚拟机记录的一次调用，出错时才转换为回溯中的 [`Frame`]
+#[derive(Clone)]
+pub(crate) struct Invocation {
+    pub procedure: Option<Rc<str>>,
+    pub site: Site,
+}
+
+impl Invocation {
+    pub fn frame(&self) -> Frame {
+        Frame::new(
+            self.procedure.as_deref().map(str::to_string),
+            self.site.form(),
+        )
+    }
+}
+
+/// 虚拟机的续延栈中保存的计算，与求值器的 [`Cont`](super::Cont) 对应
+#[derive(Clone)]
+pub(crate) enum Resume {
+    /// 调用返回后继续执行的代码，值栈先恢复到调用之前的高度 `height`
+    Frame {
+        code: Rc<Code>,
+        pc: usize,
+        env: Rc<Environment>,
+        base: usize,
+        height: usize,
+    },
+    /// 顶层 `begin` 中其余的表达式，在前一个表达式执行之后才编译
+    Toplevel { forms: List, env: Rc<Environment> },
+    /// `dynamic-wind` 的 `before` 返回后进入 `thunk`
+    WindBody {
+        winder: Rc<Winder>,
+        thunk: Value,
+        site: Site,
+    },
+    /// `dynamic-wind` 的 `thunk` 返回后离开动态范围
+    Unwind { winder: Rc<Winder>, site: Site },
+    /// 续延跳转时在动态范围 `winders` 中调用的 `before` 或 `after`
+    Wind {
+        thunk: Value,
+        winders: Option<Rc<Winder>>,
+        site: Site,
+    },
+    /// 忽略收到的值，在动态范围 `winders` 中返回保存的值
+    Return {
+        value: Value,
+        winders: Option<Rc<Winder>>,
+    },
+    /// `call/ec` 的返回处
+    Escape { id: usize },
+    /// 由 `with-exception-handler` 安装的处理器
+    Handler { handler: Value, site: Site },
+    /// `guard` 的处理器，捕获值后从 `handler` 处继续执行
+    Guard {
+        code: Rc<Code>,
+        handler: usize,
+        env: Rc<Environment>,
+        base: usize,
+        height: usize,
+        winders: Option<Rc<Winder>>,
+        site: Site,
+    },
+    /// 正在以引发的值调用第 `marker` 层的处理器
+    Raise {
+        value: Value,
+        marker: usize,
+        continuable: bool,
+    },
+}
+
+/// 续延栈中的一层，记录恢复时使用的位置与该层通过尾调用进入的过程
+#[derive(Clone)]
+pub(crate) struct Activation {
+    pub resume: Resume,
+    pub span: Option<Span>,
+    pub backtrace: VecDeque<Invocation>,
+}
+
+impl Activation {
+    // 动态范围由多个续延共享，不能逐个追踪；代码中的常量同样如此
+    pub fn trace(&self, tracer: &mut Tracer) {
+        match &self.resume {
+            Resume::Frame { env, .. } | Resume::Guard { env, .. } => tracer.visit(env),
+            Resume::Toplevel { forms, env } => {
+                forms.trace(tracer);
+                tracer.visit(env);
+            }
+            Resume::WindBody { thunk, .. }
+            | Resume::Wind { thunk, .. }
+            | Resume::Handler { handler: thunk, .. }
+            | Resume::Return { value: thunk, .. }
+            | Resume::Raise { value: thunk, .. } => thunk.trace(tracer),
+            Resume::Unwind { .. } | Resume::Escape { .. } => {}
+        }
+    }
+}
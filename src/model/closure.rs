@@ -1,37 +1,54 @@
-use std::rc::{Rc, Weak};
+use core::fmt;
+use std::rc::Rc;
 
-use super::{Environment, Value};
+use super::{Environment, Lambda, Params, Span, Tracer, Value};
 
-#[derive(Debug, Clone)]
+/// 复制闭包只增加引用计数，名称、形参与过程体都由所有副本共享
+#[derive(Clone)]
 pub struct Closure {
-    pub name: Option<String>,
-    pub params: Vec<String>,
-    pub body: Vec<Value>,
-    pub environment: Weak<Environment>,
-}
-
-#[derive(Debug, PartialEq, Clone)]
-pub struct TailCall {
-    pub closure: Closure,
-    pub updates: Vec<Value>,
-    pub break_condition: Box<Value>,
-    pub return_expr: Box<Value>,
+    pub name: Option<Rc<str>>,
+    pub params: Rc<Params>,
+    pub body: Rc<[Value]>,
+    /// 定义闭包时的环境，闭包存活期间该环境也一直存活
+    pub environment: Rc<Environment>,
+    /// 定义闭包的表达式所在的位置
+    pub span: Option<Span>,
+    /// 分析后的过程体，由分析得到的 `lambda` 创建的闭包直接使用，否则在调用时分析
+    pub(crate) lambda: Option<Rc<Lambda>>,
 }
 
 impl Closure {
     pub fn new(
         name: Option<String>,
-        params: Vec<String>,
+        params: Params,
         body: Vec<Value>,
         env: &Rc<Environment>,
     ) -> Self {
         Self {
-            name,
-            params,
-            body,
-            environment: Rc::downgrade(env),
+            name: name.map(Rc::from),
+            params: Rc::new(params),
+            body: body.into(),
+            environment: Rc::clone(env),
+            span: None,
+            lambda: None,
         }
     }
+
+    pub fn with_span(self, span: Option<Span>) -> Self {
+        Self { span, ..self }
+    }
+
+    /// 两个闭包是否为同一个过程：同一个过程体在同一个环境中创建
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.body, &other.body) && Rc::ptr_eq(&self.environment, &other.environment)
+    }
+
+    /// 访问闭包持有的堆对象，供垃圾回收器追踪
+    ///
+    /// 形参与过程体由所有副本共享，不能逐个副本追踪，其中引用的对象因此总被视为存活。
+    pub fn trace(&self, tracer: &mut Tracer) {
+        tracer.visit(&self.environment);
+    }
 }
 
 impl PartialEq for Closure {
@@ -39,3 +56,15 @@ impl PartialEq for Closure {
         self.name == other.name && self.params == other.params && self.body == other.body
     }
 }
+
+// 环境中通常保存着闭包自身，因此不输出环境以免无限递归
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .field("body", &self.body)
+            .field("span", &self.span)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,115 @@
+use std::rc::Rc;
+
+use super::{List, Params, RuntimeError, Span, Value};
+
+/// 字节码指令，操作数是 [`Code`] 中各个表中的下标或跳转的目标位置
+///
+/// 指令在值栈上运算：表达式的值压入栈顶，调用时运算符之上依次是各个参数。
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Op {
+    /// 压入常量
+    Constant(usize),
+    Void,
+    /// 压入向外第 `depth` 层环境中第 `index` 个位置的值，`name` 为变量名常量，位置尚未绑定时报错
+    Local {
+        depth: usize,
+        index: usize,
+        name: usize,
+    },
+    /// 按名称查找编译时不在任何局部作用域中的变量，操作数为符号常量
+    Global(usize),
+    /// 弹出栈顶的值赋给局部变量
+    SetLocal {
+        depth: usize,
+        index: usize,
+    },
+    /// 弹出栈顶的值绑定到当前环境中的位置
+    DefineLocal(usize),
+    SetGlobal(usize),
+    DefineGlobal(usize),
+    /// 弹出栈顶的转换器，记录名称后定义为宏
+    DefineSyntax(usize),
+    /// 弹出栈顶的过程，转换为接收未求值参数的宏
+    DefineMacro(usize),
+    Pop,
+    Dup,
+    /// 交换栈顶的两个值
+    Swap,
+    Jump(usize),
+    /// 弹出栈顶的值，为假时跳转
+    JumpIfFalse(usize),
+    JumpIfTrue(usize),
+    /// 栈顶的值为假时保留它并跳转，否则弹出
+    And(usize),
+    /// 栈顶的值为真时保留它并跳转，否则弹出
+    Or(usize),
+    /// 栈顶的值是否等于列表常量中的某个元素，结果压入栈顶
+    Memv(usize),
+    /// 以当前环境创建过程
+    Lambda(usize),
+    /// 创建命名 `let` 的循环过程，过程绑定在只对过程体可见的环境中
+    Loop(usize),
+    /// 进入包含给定数量位置的子环境
+    PushEnv(usize),
+    PopEnv,
+    /// 参数在调用时已经提供时跳过计算默认值的指令
+    Default {
+        index: usize,
+        target: usize,
+    },
+    /// 检查运算符可以被调用，在求值参数之前进行
+    Callable,
+    /// 以栈顶的 `argc` 个参数调用其下的过程，`site` 为调用处的表达式
+    Call {
+        argc: usize,
+        site: usize,
+    },
+    /// 尾调用，被调用的过程返回时直接返回到当前过程的调用者
+    TailCall {
+        argc: usize,
+        site: usize,
+    },
+    Return,
+    /// 安装 `guard` 的处理器，捕获值后从 `handler` 继续执行
+    PushGuard {
+        handler: usize,
+        site: usize,
+    },
+    /// 过程体正常返回，移除 `guard` 的处理器
+    PopGuard,
+    /// 引发编译时发现的错误
+    Fail(usize),
+}
+
+/// 编译得到的过程体或顶层表达式
+///
+/// 过程的局部变量按位置保存在环境中，编译时已确定每个变量引用所在的层数与位置。
+#[derive(Debug, Default)]
+pub struct Code {
+    pub(crate) name: Option<Rc<str>>,
+    /// 过程的形参，形参依次占用环境中最前面的位置
+    pub(crate) params: Rc<Params>,
+    /// 过程体的源码，定义宏时转换为闭包
+    pub(crate) body: Rc<[Value]>,
+    /// 定义过程的表达式所在的位置
+    pub(crate) span: Option<Span>,
+    /// 过程的环境包含的位置数量，包括形参与过程体中的内部定义
+    pub(crate) size: usize,
+    pub(crate) ops: Vec<Op>,
+    /// 每条指令出错时报告的位置
+    pub(crate) spans: Vec<Option<Span>>,
+    pub(crate) constants: Vec<Value>,
+    /// 调用处的表达式，出错时记录在回溯中
+    pub(crate) forms: Vec<List>,
+    pub(crate) lambdas: Vec<Rc<Code>>,
+    pub(crate) errors: Vec<RuntimeError>,
+    /// `case-lambda` 的各个分支，按参数数量选择第一个匹配的分支，普通过程为 `None`
+    pub(crate) clauses: Option<Vec<Rc<Code>>>,
+}
+
+impl Code {
+    /// 过程的名称
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
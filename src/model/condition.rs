@@ -0,0 +1,55 @@
+use core::fmt;
+
+use super::{RuntimeErrorKind, Tracer, Value};
+
+/// 错误对象，由 `error` 创建，或由处理器捕获的内置错误转换而来
+#[derive(Debug, PartialEq, Clone)]
+pub struct Condition {
+    pub kind: RuntimeErrorKind,
+}
+
+impl Condition {
+    pub fn new(kind: RuntimeErrorKind) -> Self {
+        Self { kind }
+    }
+
+    /// `error-object-message` 的结果，内置错误使用错误的描述
+    pub fn message(&self) -> String {
+        match &self.kind {
+            RuntimeErrorKind::Error { message, .. } => message.clone(),
+            kind => kind.to_string(),
+        }
+    }
+
+    /// `error-object-irritants` 的结果，内置错误为出错时涉及的值
+    pub fn irritants(&self) -> Vec<Value> {
+        match &self.kind {
+            RuntimeErrorKind::Error { irritants, .. } => irritants.clone(),
+            RuntimeErrorKind::TypeError { founded, .. } => vec![founded.clone()],
+            RuntimeErrorKind::NonCallableValue(value)
+            | RuntimeErrorKind::InvalidSyntax(value)
+            | RuntimeErrorKind::Raise(value) => vec![value.clone()],
+            _ => Vec::new(),
+        }
+    }
+
+    /// 访问错误中持有的值，供垃圾回收器追踪
+    pub fn trace(&self, tracer: &mut Tracer) {
+        match &self.kind {
+            RuntimeErrorKind::Error { irritants, .. } => {
+                irritants.iter().for_each(|value| value.trace(tracer));
+            }
+            RuntimeErrorKind::TypeError { founded: value, .. }
+            | RuntimeErrorKind::NonCallableValue(value)
+            | RuntimeErrorKind::InvalidSyntax(value)
+            | RuntimeErrorKind::Raise(value) => value.trace(tracer),
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#<condition:{}>", self.kind.name())
+    }
+}
@@ -0,0 +1,417 @@
+use core::fmt;
+use std::{cell::RefCell, collections::VecDeque, mem, rc::Rc};
+
+use super::{
+    Activation, Body, Call, Clause, Environment, Frame, Guard, Heap, Lambda, Let, Letrec, List,
+    NamedLet, Node, Span, Symbol, Trace, Tracer, Value, Variable,
+};
+
+/// 由 `dynamic-wind` 建立的动态范围，与外层的范围构成链表
+///
+/// 续延跳转时，离开的范围依次调用 `after`，进入的范围依次调用 `before`。
+pub struct Winder {
+    pub before: Value,
+    pub after: Value,
+    pub parent: Option<Rc<Winder>>,
+    depth: usize,
+}
+
+impl Winder {
+    pub fn new(before: Value, after: Value, parent: Option<Rc<Winder>>) -> Self {
+        let depth = parent.as_ref().map_or(0, |parent| parent.depth + 1);
+        Self {
+            before,
+            after,
+            parent,
+            depth,
+        }
+    }
+
+    /// 外层范围的数量
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+/// 子表达式求值完毕后要继续进行的计算
+///
+/// 每个变体保存继续计算所需的全部状态，因此续延栈可以被复制并在之后重新进入。
+#[derive(Clone)]
+pub(crate) enum Cont {
+    /// 过程调用的运算符
+    Operator {
+        call: Rc<Call>,
+        env: Rc<Environment>,
+    },
+    /// 已求值的参数，`call` 中其余的参数依次求值
+    Args {
+        procedure: Value,
+        call: Rc<Call>,
+        args: Vec<Value>,
+        env: Rc<Environment>,
+    },
+    /// 从 `index` 开始依次求值的表达式，最后一个处于尾部位置
+    Sequence {
+        body: Body,
+        index: usize,
+        env: Rc<Environment>,
+    },
+    /// 顶层 `begin` 中其余的表达式，在前一个表达式求值之后才分析
+    Toplevel {
+        forms: List,
+        env: Rc<Environment>,
+    },
+    Define {
+        variable: Variable,
+        env: Rc<Environment>,
+    },
+    /// 顶层的 `define-macro` 与 `define-syntax`
+    DefineMacro {
+        name: Symbol,
+        env: Rc<Environment>,
+    },
+    DefineSyntax {
+        name: Symbol,
+        env: Rc<Environment>,
+    },
+    Set {
+        variable: Variable,
+        env: Rc<Environment>,
+    },
+    If {
+        consequent: Rc<Node>,
+        alternative: Option<Rc<Node>>,
+        env: Rc<Environment>,
+    },
+    /// `cond` 中第 `index` 个子句的测试
+    Cond {
+        clauses: Rc<[Clause]>,
+        index: usize,
+        env: Rc<Environment>,
+    },
+    Case {
+        clauses: Rc<[Clause]>,
+        env: Rc<Environment>,
+    },
+    /// `and` 与 `or` 中 `index` 之前的表达式已经求值
+    And {
+        body: Body,
+        index: usize,
+        env: Rc<Environment>,
+    },
+    Or {
+        body: Body,
+        index: usize,
+        env: Rc<Environment>,
+    },
+    When {
+        body: Body,
+        expected: bool,
+        env: Rc<Environment>,
+    },
+    /// 子句中 `=>` 之后的接收者，以测试的结果 `value` 调用
+    Receive {
+        clause: List,
+        value: Value,
+        env: Rc<Environment>,
+    },
+    /// `let` 中已求值的初始值
+    Let {
+        node: Rc<Let>,
+        values: Vec<Value>,
+        env: Rc<Environment>,
+    },
+    NamedLet {
+        node: Rc<NamedLet>,
+        values: Vec<Value>,
+        env: Rc<Environment>,
+    },
+    /// 第 `index` 个绑定的初始值，`env` 为绑定所在的新环境
+    Letrec {
+        node: Rc<Letrec>,
+        index: usize,
+        values: Vec<Value>,
+        env: Rc<Environment>,
+    },
+    /// 调用时没有提供的第 `index` 个可选参数或关键字参数的默认值
+    Default {
+        lambda: Rc<Lambda>,
+        index: usize,
+        env: Rc<Environment>,
+    },
+    /// `dynamic-wind` 的 `before` 返回后进入 `thunk`
+    WindBody {
+        winder: Rc<Winder>,
+        thunk: Value,
+        form: List,
+        env: Rc<Environment>,
+    },
+    /// `dynamic-wind` 的 `thunk` 返回后离开动态范围
+    Unwind {
+        winder: Rc<Winder>,
+        form: List,
+        env: Rc<Environment>,
+    },
+    /// 续延跳转时在动态范围 `winders` 中调用的 `before` 或 `after`
+    Wind {
+        thunk: Value,
+        winders: Option<Rc<Winder>>,
+        form: List,
+        env: Rc<Environment>,
+    },
+    /// 忽略收到的值，在动态范围 `winders` 中返回保存的值
+    Return {
+        value: Value,
+        winders: Option<Rc<Winder>>,
+    },
+    /// `call/ec` 的返回处，逃逸续延以 `id` 找到它
+    Escape {
+        id: usize,
+    },
+    /// 由 `with-exception-handler` 安装的处理器，在 `thunk` 返回之前有效
+    Handler {
+        handler: Value,
+        form: List,
+        env: Rc<Environment>,
+    },
+    /// `guard` 的返回处，同时作为处理器捕获过程体中引发的值
+    Guard {
+        node: Rc<Guard>,
+        env: Rc<Environment>,
+        winders: Option<Rc<Winder>>,
+    },
+    /// 正在以引发的值 `value` 调用第 `marker` 层的处理器，
+    /// 处理器中再次引发的值交给更外层的处理器
+    Raise {
+        value: Value,
+        marker: usize,
+        continuable: bool,
+    },
+    /// 回到 `guard` 之后以捕获的值测试其中的子句
+    Catch {
+        node: Rc<Guard>,
+        env: Rc<Environment>,
+    },
+}
+
+impl Cont {
+    fn trace(&self, tracer: &mut Tracer) {
+        fn visit_form(form: &List, env: &Rc<Environment>, tracer: &mut Tracer) {
+            form.trace(tracer);
+            tracer.visit(env);
+        }
+
+        // 动态范围与分析得到的节点由多个续延共享，不能逐个追踪
+        match self {
+            Cont::Operator { env, .. }
+            | Cont::Sequence { env, .. }
+            | Cont::Define { env, .. }
+            | Cont::DefineMacro { env, .. }
+            | Cont::DefineSyntax { env, .. }
+            | Cont::Set { env, .. }
+            | Cont::If { env, .. }
+            | Cont::Cond { env, .. }
+            | Cont::Case { env, .. }
+            | Cont::And { env, .. }
+            | Cont::Or { env, .. }
+            | Cont::When { env, .. }
+            | Cont::Default { env, .. }
+            | Cont::Guard { env, .. }
+            | Cont::Catch { env, .. } => tracer.visit(env),
+            Cont::Unwind { form, env, .. } | Cont::Toplevel { forms: form, env } => {
+                visit_form(form, env, tracer);
+            }
+            Cont::Args {
+                procedure,
+                args,
+                env,
+                ..
+            } => {
+                procedure.trace(tracer);
+                args.iter().for_each(|value| value.trace(tracer));
+                tracer.visit(env);
+            }
+            Cont::Receive { clause, value, env } => {
+                value.trace(tracer);
+                visit_form(clause, env, tracer);
+            }
+            Cont::Let { values, env, .. }
+            | Cont::NamedLet { values, env, .. }
+            | Cont::Letrec { values, env, .. } => {
+                values.iter().for_each(|value| value.trace(tracer));
+                tracer.visit(env);
+            }
+            Cont::WindBody {
+                thunk, form, env, ..
+            }
+            | Cont::Wind {
+                thunk, form, env, ..
+            }
+            | Cont::Handler {
+                handler: thunk,
+                form,
+                env,
+            } => {
+                thunk.trace(tracer);
+                visit_form(form, env, tracer);
+            }
+            Cont::Return { value, .. } | Cont::Raise { value, .. } => value.trace(tracer),
+            Cont::Escape { .. } => {}
+        }
+    }
+}
+
+/// 续延栈中的一层，记录恢复时使用的位置与该层通过尾调用进入的过程
+#[derive(Clone)]
+pub(crate) struct Pending {
+    pub cont: Cont,
+    pub span: Option<Span>,
+    pub backtrace: VecDeque<Frame>,
+}
+
+/// 由 `call/cc` 或 `call/ec` 捕获的续延
+pub struct Continuation {
+    kind: Kind,
+    winders: Option<Rc<Winder>>,
+}
+
+enum Kind {
+    /// 保存了捕获时整个续延栈的副本，可以在任意时刻多次重新进入
+    Full(RefCell<Vec<Pending>>),
+    /// 字节码虚拟机捕获的续延栈与值栈
+    Compiled(RefCell<(Vec<Activation>, Vec<Value>)>),
+    /// 只记录 `call/ec` 的返回处，只能在 `call/ec` 返回之前用于提前返回
+    Escape(usize),
+}
+
+impl Continuation {
+    /// 在堆上创建完整的续延，续延栈中的环境可能又引用了续延自身
+    pub(crate) fn full(stack: Vec<Pending>, winders: Option<Rc<Winder>>, heap: &Heap) -> Rc<Self> {
+        let continuation = Rc::new(Self {
+            kind: Kind::Full(RefCell::new(stack)),
+            winders,
+        });
+        heap.register(&continuation);
+        continuation
+    }
+
+    /// 在堆上创建字节码虚拟机的完整续延
+    pub(crate) fn compiled(
+        frames: Vec<Activation>,
+        stack: Vec<Value>,
+        winders: Option<Rc<Winder>>,
+        heap: &Heap,
+    ) -> Rc<Self> {
+        let continuation = Rc::new(Self {
+            kind: Kind::Compiled(RefCell::new((frames, stack))),
+            winders,
+        });
+        heap.register(&continuation);
+        continuation
+    }
+
+    pub(crate) fn escape(id: usize, winders: Option<Rc<Winder>>) -> Rc<Self> {
+        Rc::new(Self {
+            kind: Kind::Escape(id),
+            winders,
+        })
+    }
+
+    /// 完整续延保存的续延栈的副本
+    pub(crate) fn stack(&self) -> Option<Vec<Pending>> {
+        match &self.kind {
+            Kind::Full(stack) => Some(stack.borrow().clone()),
+            Kind::Compiled(_) | Kind::Escape(_) => None,
+        }
+    }
+
+    /// 字节码虚拟机的完整续延保存的续延栈与值栈的副本
+    pub(crate) fn frames(&self) -> Option<(Vec<Activation>, Vec<Value>)> {
+        match &self.kind {
+            Kind::Compiled(frames) => Some(frames.borrow().clone()),
+            Kind::Full(_) | Kind::Escape(_) => None,
+        }
+    }
+
+    /// 逃逸续延对应的 `call/ec` 返回处
+    pub(crate) fn escape_id(&self) -> Option<usize> {
+        match self.kind {
+            Kind::Full(_) | Kind::Compiled(_) => None,
+            Kind::Escape(id) => Some(id),
+        }
+    }
+
+    /// 捕获续延时所在的动态范围
+    pub fn winders(&self) -> Option<&Rc<Winder>> {
+        self.winders.as_ref()
+    }
+}
+
+impl Trace for Continuation {
+    fn trace(&self, tracer: &mut Tracer) {
+        match &self.kind {
+            Kind::Full(stack) => {
+                for pending in stack.borrow().iter() {
+                    pending.cont.trace(tracer);
+                    for frame in &pending.backtrace {
+                        frame.call.trace(tracer);
+                    }
+                }
+            }
+            Kind::Compiled(frames) => {
+                let (frames, stack) = &*frames.borrow();
+                frames
+                    .iter()
+                    .for_each(|activation| activation.trace(tracer));
+                stack.iter().for_each(|value| value.trace(tracer));
+            }
+            Kind::Escape(_) => {}
+        }
+    }
+
+    fn clear(&self) {
+        match &self.kind {
+            Kind::Full(stack) => stack.borrow_mut().clear(),
+            Kind::Compiled(frames) => {
+                let (frames, stack) = &mut *frames.borrow_mut();
+                frames.clear();
+                stack.clear();
+            }
+            Kind::Escape(_) => {}
+        }
+    }
+
+    fn size(&self) -> usize {
+        match &self.kind {
+            Kind::Full(stack) => {
+                mem::size_of::<Self>() + stack.borrow().capacity() * mem::size_of::<Pending>()
+            }
+            Kind::Compiled(frames) => {
+                let (frames, stack) = &*frames.borrow();
+                mem::size_of::<Self>()
+                    + frames.capacity() * mem::size_of::<Activation>()
+                    + stack.capacity() * mem::size_of::<Value>()
+            }
+            Kind::Escape(_) => mem::size_of::<Self>(),
+        }
+    }
+}
+
+impl fmt::Debug for Continuation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            Kind::Full(stack) => f
+                .debug_struct("Continuation")
+                .field("depth", &stack.borrow().len())
+                .finish_non_exhaustive(),
+            Kind::Compiled(frames) => f
+                .debug_struct("Continuation")
+                .field("depth", &frames.borrow().0.len())
+                .finish_non_exhaustive(),
+            Kind::Escape(id) => f
+                .debug_struct("Continuation")
+                .field("escape", id)
+                .finish_non_exhaustive(),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use core::fmt;
+
+/// 需要操作求值器续延栈的内部过程
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Control {
+    /// `call-with-current-continuation`，以当前的续延调用接收者
+    CallCc,
+    /// `call-with-escape-continuation`，续延只能用于从接收者中提前返回
+    CallEc,
+    /// `dynamic-wind`，在进入与离开过程的动态范围时调用前置与后置过程
+    DynamicWind,
+    /// `raise`，处理器返回后引发新的错误
+    Raise,
+    /// `raise-continuable`，处理器的返回值作为 `raise-continuable` 的返回值
+    RaiseContinuable,
+    /// `with-exception-handler`，在调用过程的期间安装异常处理器
+    WithExceptionHandler,
+    /// `with-precision`，在调用过程的期间使用指定的浮点数精度
+    WithPrecision,
+    /// 被调用时设置当前的浮点数精度，作为 `with-precision` 的前置与后置过程
+    SetPrecision(u32),
+}
+
+impl fmt::Display for Control {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Control::CallCc => write!(f, "call-with-current-continuation"),
+            Control::CallEc => write!(f, "call-with-escape-continuation"),
+            Control::DynamicWind => write!(f, "dynamic-wind"),
+            Control::Raise => write!(f, "raise"),
+            Control::RaiseContinuable => write!(f, "raise-continuable"),
+            Control::WithExceptionHandler => write!(f, "with-exception-handler"),
+            Control::WithPrecision | Control::SetPrecision(_) => write!(f, "with-precision"),
+        }
+    }
+}
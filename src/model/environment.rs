@@ -1,52 +1,171 @@
-use std::{
-    cell::RefCell,
-    collections::HashMap,
-    rc::{Rc, Weak},
-};
+use std::{cell::RefCell, collections::HashMap, mem, rc::Rc};
 
-use super::{RuntimeError, Value};
+use super::{Heap, Keyword, Name, RuntimeError, RuntimeErrorKind, Symbol, Trace, Tracer, Value};
 
 #[derive(Debug, Default, Clone)]
 pub struct Environment {
-    parent: Option<Weak<Environment>>,
-    vars: RefCell<HashMap<String, Value>>,
+    // 尾调用会立即释放调用者的环境，因此子环境需要持有父环境
+    parent: Option<Rc<Environment>>,
+    vars: RefCell<HashMap<Name, Value>>,
+    /// 编译后的代码按位置访问的局部变量，`None` 表示尚未绑定
+    slots: RefCell<Vec<Option<Value>>>,
+    heap: Rc<Heap>,
 }
 
 impl Environment {
+    /// 创建顶层环境，同时创建管理该环境及其所有子环境的堆
+    ///
+    /// 关键字与其他标识符一样绑定在顶层环境中，可以被局部变量遮蔽。
     pub fn new() -> Rc<Self> {
-        Rc::new(Self::default())
+        let env = Rc::new(Self::default());
+        env.heap.register(&env);
+        env.heap.set_root(&env);
+        for keyword in Keyword::ALL {
+            env.set(keyword.to_string(), Value::Keyword(keyword));
+        }
+        env
     }
 
     pub fn extend(parent: &Rc<Self>) -> Rc<Self> {
-        Rc::new(Self {
+        let env = Rc::new(Self {
             vars: RefCell::new(HashMap::new()),
-            parent: Some(Rc::downgrade(parent)),
+            parent: Some(Rc::clone(parent)),
+            slots: RefCell::new(Vec::new()),
+            heap: Rc::clone(&parent.heap),
+        });
+        env.heap.register(&env);
+        env
+    }
+
+    /// 创建包含 `size` 个局部变量位置的子环境，供字节码虚拟机使用
+    pub fn with_slots(parent: &Rc<Self>, size: usize) -> Rc<Self> {
+        let env = Rc::new(Self {
+            vars: RefCell::new(HashMap::new()),
+            parent: Some(Rc::clone(parent)),
+            slots: RefCell::new(vec![None; size]),
+            heap: Rc::clone(&parent.heap),
+        });
+        env.heap.register(&env);
+        env
+    }
+
+    pub fn parent(&self) -> Option<&Rc<Environment>> {
+        self.parent.as_ref()
+    }
+
+    // 向外第 `depth` 层环境
+    fn ancestor(&self, depth: usize) -> &Environment {
+        let mut env = self;
+        for _ in 0..depth {
+            env = env.parent.as_ref().expect("lexical address out of scope");
+        }
+        env
+    }
+
+    /// 向外第 `depth` 层环境中第 `index` 个位置的值，尚未绑定时返回 `None`
+    pub fn slot(&self, depth: usize, index: usize) -> Option<Value> {
+        self.ancestor(depth).slots.borrow()[index].clone()
+    }
+
+    pub fn set_slot(&self, depth: usize, index: usize, value: Value) {
+        self.ancestor(depth).slots.borrow_mut()[index] = Some(value);
+    }
+
+    /// 管理当前环境的堆
+    pub fn heap(&self) -> &Rc<Heap> {
+        &self.heap
+    }
+
+    pub fn get(&self, name: impl Into<Name>) -> Option<Value> {
+        self.find(&name.into())
+    }
+
+    fn find(&self, name: &Name) -> Option<Value> {
+        self.vars
+            .borrow()
+            .get(name)
+            .cloned()
+            .or_else(|| self.parent.as_ref().and_then(|parent| parent.find(name)))
+    }
+
+    /// 查找标识符绑定的值
+    ///
+    /// 宏展开时重命名的标识符没有被展开结果绑定时，在定义宏的环境中查找原本的标识符。
+    pub fn lookup(&self, symbol: &Symbol) -> Option<Value> {
+        self.find(symbol.name()).or_else(|| {
+            let renamed = symbol.renamed()?;
+            renamed.environment.lookup(&renamed.symbol)
         })
     }
 
-    pub fn get(&self, name: &str) -> Option<Value> {
-        self.vars.borrow().get(name).cloned().or_else(|| {
-            self.parent
+    pub fn set(&self, name: impl Into<Name>, value: Value) {
+        self.vars.borrow_mut().insert(name.into(), value);
+    }
+
+    /// 清空当前环境中的所有绑定
+    ///
+    /// 闭包会持有定义时的环境，而环境又保存着闭包，清空绑定可以打破这样的循环引用。
+    pub fn clear(&self) {
+        self.vars.borrow_mut().clear();
+        self.slots.borrow_mut().clear();
+    }
+
+    /// 修改标识符绑定的值，查找绑定的规则与 [`Environment::lookup`] 相同
+    pub fn assign(&self, symbol: &Symbol, value: Value) -> Result<(), RuntimeError> {
+        match symbol.renamed() {
+            Some(renamed) if !self.contains(symbol.name()) => {
+                renamed.environment.assign(&renamed.symbol, value)
+            }
+            _ => self.replace(symbol.name(), value),
+        }
+    }
+
+    fn contains(&self, name: &Name) -> bool {
+        self.vars.borrow().contains_key(name)
+            || self
+                .parent
                 .as_ref()
-                .and_then(|weak| weak.upgrade())
-                .and_then(|parent| parent.get(name))
-        })
+                .is_some_and(|parent| parent.contains(name))
     }
 
-    pub fn set(&self, name: &str, value: Value) {
-        self.vars.borrow_mut().insert(name.to_string(), value);
+    /// 修改最近一层环境中 `name` 的绑定，所有绑定都不存在时返回错误
+    pub fn update(&self, name: impl Into<Name>, value: Value) -> Result<(), RuntimeError> {
+        self.replace(&name.into(), value)
     }
 
-    pub fn update(&self, name: &str, value: Value) -> Result<(), RuntimeError> {
-        if self.vars.borrow_mut().contains_key(name) {
-            self.vars.borrow_mut().insert(name.to_string(), value);
+    fn replace(&self, name: &Name, value: Value) -> Result<(), RuntimeError> {
+        if let Some(slot) = self.vars.borrow_mut().get_mut(name) {
+            *slot = value;
             Ok(())
-        } else if let Some(weak) = self.parent.as_ref()
-            && let Some(parent) = weak.upgrade()
-        {
-            parent.update(name, value)
+        } else if let Some(parent) = &self.parent {
+            parent.replace(name, value)
         } else {
-            Err(RuntimeError::UndefinedVariable(name.into()))
+            Err(RuntimeErrorKind::UndefinedVariable(name.to_string()).into())
         }
     }
 }
+
+impl Trace for Environment {
+    fn trace(&self, tracer: &mut Tracer) {
+        if let Some(parent) = &self.parent {
+            tracer.visit(parent);
+        }
+        for value in self.vars.borrow().values() {
+            value.trace(tracer);
+        }
+        for value in self.slots.borrow().iter().flatten() {
+            value.trace(tracer);
+        }
+    }
+
+    fn clear(&self) {
+        Environment::clear(self);
+    }
+
+    fn size(&self) -> usize {
+        let vars = self.vars.borrow();
+        mem::size_of::<Self>()
+            + vars.capacity() * mem::size_of::<(Name, Value)>()
+            + self.slots.borrow().capacity() * mem::size_of::<Option<Value>>()
+    }
+}
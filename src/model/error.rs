@@ -1,29 +1,33 @@
 use core::fmt;
+use std::{
+    ops::{Deref, DerefMut},
+    rc::Rc,
+};
 
-use super::{Token, Value};
+use super::{Arity, List, Span, Token, Value};
 
 /// 词法分析中可能发生的错误
 #[derive(Debug, PartialEq, Clone)]
-pub enum TokenizeError {
+pub enum TokenizeErrorKind {
     UnexpectedChar(char),
     UnclosedString,
 }
 
 /// 语法分析中可能发生的错误
 #[derive(Debug, PartialEq, Clone)]
-pub enum ParseError {
+pub enum ParseErrorKind {
     UnexpectedToken { expected: Token, found: Token },
     MissingToken(Token),
     InvalidSyntax(Token),
     InvalidDigit(String),
-    LexicalError(TokenizeError),
+    LexicalError(TokenizeErrorKind),
     NonConvertibleToken(Token),
     UnexpectedEOF,
 }
 
 /// 解释执行中可能发生的错误
 #[derive(Debug, PartialEq, Clone)]
-pub enum RuntimeError {
+pub enum RuntimeErrorKind {
     UndefinedVariable(String),
     UndefinedFunction(String),
     TypeError {
@@ -43,83 +47,300 @@ pub enum RuntimeError {
         expected: usize,
         founded: usize,
     },
+    /// 参数数量不在过程接受的任何一个范围内
+    ArityMismatch {
+        expected: Vec<Arity>,
+        founded: usize,
+    },
     DivideByZero,
     NonCallableValue(Value),
     EmptyList,
-    SyntaxError(ParseError),
-    InvalidClosure,
+    SyntaxError(ParseErrorKind),
+    InvalidSyntax(Value),
+    /// 逃逸续延在捕获它的 `call/ec` 返回之后被调用
+    ExpiredContinuation,
+    /// 由 `error` 引发的错误
+    Error {
+        message: String,
+        irritants: Vec<Value>,
+    },
+    /// 由 `raise` 引发且没有被处理的值，错误对象会还原为原本的错误种类
+    Raise(Value),
+}
+
+/// 词法错误及出错字符所在的位置
+#[derive(Debug, PartialEq, Clone)]
+pub struct TokenizeError {
+    pub kind: TokenizeErrorKind,
+    pub span: Span,
+}
+
+/// 语法错误及出错标记所在的位置
+///
+/// 与 [`RuntimeError`] 一样，错误的内容放在堆上，字段通过 [`Deref`] 访问。
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError(Box<ParseErrorInner>);
+
+/// [`ParseError`] 的内容
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseErrorInner {
+    pub kind: ParseErrorKind,
+    pub span: Span,
+}
+
+/// 运行时错误及出错表达式所在的位置
+///
+/// 由内部函数产生的错误没有位置信息，求值器在错误向外传递时会补上最内层表达式的位置。
+/// 错误的内容放在堆上，使 `Result<Value, RuntimeError>` 在没有出错的路径上保持较小，
+/// 字段通过 [`Deref`] 访问。
+#[derive(Debug, PartialEq, Clone)]
+pub struct RuntimeError(Box<RuntimeErrorInner>);
+
+/// [`RuntimeError`] 的内容
+#[derive(Debug, PartialEq, Clone)]
+pub struct RuntimeErrorInner {
+    pub kind: RuntimeErrorKind,
+    pub span: Option<Span>,
+    pub notes: Vec<Note>,
+    /// 错误向外传递时经过的调用，最内层的调用在前
+    pub backtrace: Vec<Frame>,
+}
+
+/// 错误的附加说明，例如参数数量不符时闭包定义的位置
+#[derive(Debug, PartialEq, Clone)]
+pub struct Note {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+/// 调用栈中的一帧，记录被调用过程的名称与调用处的表达式
+#[derive(Debug, PartialEq, Clone)]
+pub struct Frame {
+    pub procedure: Option<String>,
+    pub call: Value,
+    pub span: Option<Span>,
+}
+
+impl Frame {
+    pub fn new(procedure: Option<String>, call: &List) -> Self {
+        Self {
+            procedure,
+            call: Value::List(call.clone()),
+            span: call.span(),
+        }
+    }
+}
+
+impl TokenizeErrorKind {
+    /// 错误种类的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenizeErrorKind::UnexpectedChar(_) => "UnexpectedChar",
+            TokenizeErrorKind::UnclosedString => "UnclosedString",
+        }
+    }
+}
+
+impl ParseErrorKind {
+    /// 错误种类的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParseErrorKind::UnexpectedToken { .. } => "UnexpectedToken",
+            ParseErrorKind::MissingToken(_) => "MissingToken",
+            ParseErrorKind::InvalidSyntax(_) => "InvalidSyntax",
+            ParseErrorKind::InvalidDigit(_) => "InvalidDigit",
+            ParseErrorKind::LexicalError(error) => error.name(),
+            ParseErrorKind::NonConvertibleToken(_) => "NonConvertibleToken",
+            ParseErrorKind::UnexpectedEOF => "UnexpectedEOF",
+        }
+    }
+}
+
+impl RuntimeErrorKind {
+    /// 错误种类的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuntimeErrorKind::UndefinedVariable(_) => "UndefinedVariable",
+            RuntimeErrorKind::UndefinedFunction(_) => "UndefinedFunction",
+            RuntimeErrorKind::TypeError { .. } => "TypeError",
+            RuntimeErrorKind::OperationError { .. } => "OperationError",
+            RuntimeErrorKind::InvalidListLength { .. } => "InvalidListLength",
+            RuntimeErrorKind::InvalidArity { .. } | RuntimeErrorKind::ArityMismatch { .. } => {
+                "InvalidArity"
+            }
+            RuntimeErrorKind::DivideByZero => "DivideByZero",
+            RuntimeErrorKind::NonCallableValue(_) => "NonCallableValue",
+            RuntimeErrorKind::EmptyList => "EmptyList",
+            RuntimeErrorKind::SyntaxError(_) => "SyntaxError",
+            RuntimeErrorKind::InvalidSyntax(_) => "InvalidSyntax",
+            RuntimeErrorKind::ExpiredContinuation => "ExpiredContinuation",
+            RuntimeErrorKind::Error { .. } => "Error",
+            RuntimeErrorKind::Raise(_) => "Raise",
+        }
+    }
+}
+
+impl TokenizeError {
+    pub fn new(kind: TokenizeErrorKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, span: Span) -> Self {
+        Self(Box::new(ParseErrorInner { kind, span }))
+    }
+
+    /// 取出错误的种类
+    pub fn into_kind(self) -> ParseErrorKind {
+        self.0.kind
+    }
+}
+
+impl Deref for ParseError {
+    type Target = ParseErrorInner;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind, span: Option<Span>) -> Self {
+        Self(Box::new(RuntimeErrorInner {
+            kind,
+            span,
+            notes: Vec::new(),
+            backtrace: Vec::new(),
+        }))
+    }
+
+    /// 取出错误的种类
+    pub fn into_kind(self) -> RuntimeErrorKind {
+        self.0.kind
+    }
+
+    /// 以 `raise` 引发的值作为错误，错误对象还原为原本的错误种类
+    pub fn raise(value: Value) -> Self {
+        match value {
+            Value::Condition(condition) => Rc::unwrap_or_clone(condition).kind.into(),
+            value => RuntimeErrorKind::Raise(value).into(),
+        }
+    }
+
+    pub fn with_frame(mut self, frame: Frame) -> Self {
+        self.backtrace.push(frame);
+        self
+    }
+
+    pub fn with_note(mut self, message: impl Into<String>, span: Option<Span>) -> Self {
+        self.notes.push(Note {
+            message: message.into(),
+            span,
+        });
+        self
+    }
+
+    /// 错误尚未记录位置时使用给定的位置
+    pub fn or_span(mut self, span: Option<Span>) -> Self {
+        if self.span.is_none() {
+            self.span = span;
+        }
+        self
+    }
+}
+
+impl Deref for RuntimeError {
+    type Target = RuntimeErrorInner;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for RuntimeError {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<RuntimeErrorKind> for RuntimeError {
+    fn from(kind: RuntimeErrorKind) -> Self {
+        Self::new(kind, None)
+    }
 }
 
 impl From<TokenizeError> for ParseError {
     fn from(value: TokenizeError) -> Self {
-        ParseError::LexicalError(value)
+        ParseError::new(ParseErrorKind::LexicalError(value.kind), value.span)
     }
 }
 
 impl From<ParseError> for RuntimeError {
     fn from(value: ParseError) -> Self {
-        RuntimeError::SyntaxError(value)
+        let span = value.span;
+        RuntimeError::new(RuntimeErrorKind::SyntaxError(value.into_kind()), Some(span))
     }
 }
 
-impl fmt::Display for TokenizeError {
+impl fmt::Display for TokenizeErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            TokenizeError::UnexpectedChar(c) => {
+            TokenizeErrorKind::UnexpectedChar(c) => {
                 write!(f, "Unexpected character: {}", c)
             }
-            TokenizeError::UnclosedString => {
+            TokenizeErrorKind::UnclosedString => {
                 write!(f, "Unclosed string")
             }
         }
     }
 }
 
-impl fmt::Display for ParseError {
+impl fmt::Display for ParseErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ParseError::UnexpectedToken { expected, found } => {
+            ParseErrorKind::UnexpectedToken { expected, found } => {
                 write!(
                     f,
                     "Unexpected token: expected {}, found {}",
                     expected, found
                 )
             }
-            ParseError::MissingToken(token_type) => {
+            ParseErrorKind::MissingToken(token_type) => {
                 write!(f, "Missing token: {}", token_type)
             }
-            ParseError::InvalidSyntax(token) => {
+            ParseErrorKind::InvalidSyntax(token) => {
                 write!(f, "Invalid syntax: {}", token)
             }
-            ParseError::InvalidDigit(digit) => {
+            ParseErrorKind::InvalidDigit(digit) => {
                 write!(f, "Invalid digit: {}", digit)
             }
-            ParseError::LexicalError(error) => {
+            ParseErrorKind::LexicalError(error) => {
                 write!(f, "Lexical error: {}", error)
             }
-            ParseError::NonConvertibleToken(token) => {
+            ParseErrorKind::NonConvertibleToken(token) => {
                 write!(f, "Non-convertible token: {}", token)
             }
-            ParseError::UnexpectedEOF => {
+            ParseErrorKind::UnexpectedEOF => {
                 write!(f, "Unexpected EOF")
             }
         }
     }
 }
 
-impl fmt::Display for RuntimeError {
+impl fmt::Display for RuntimeErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            RuntimeError::UndefinedVariable(name) => {
+            RuntimeErrorKind::UndefinedVariable(name) => {
                 write!(f, "Undefined variable: {}", name)
             }
-            RuntimeError::UndefinedFunction(name) => {
+            RuntimeErrorKind::UndefinedFunction(name) => {
                 write!(f, "Undefined function: {}", name)
             }
-            RuntimeError::TypeError { expected, founded } => {
+            RuntimeErrorKind::TypeError { expected, founded } => {
                 write!(f, "TypeError: expected {}, found {}", expected, founded)
             }
-            RuntimeError::OperationError {
+            RuntimeErrorKind::OperationError {
                 operation,
                 lhs_type,
                 rhs_type,
@@ -130,33 +351,94 @@ impl fmt::Display for RuntimeError {
                     operation, lhs_type, rhs_type
                 )
             }
-            RuntimeError::InvalidListLength { expected, founded } => {
+            RuntimeErrorKind::InvalidListLength { expected, founded } => {
                 write!(
                     f,
                     "InvalidListLength: expected {}, found {}",
                     expected, founded
                 )
             }
-            RuntimeError::InvalidArity { expected, founded } => {
+            RuntimeErrorKind::InvalidArity { expected, founded } => {
                 write!(
                     f,
                     "Invalid arity: expected {} arguments, but found {}",
                     expected, founded
                 )
             }
-            RuntimeError::DivideByZero => {
+            RuntimeErrorKind::ArityMismatch { expected, founded } => {
+                let expected = expected
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" or ");
+                write!(
+                    f,
+                    "Invalid arity: expected {} arguments, but found {}",
+                    expected, founded
+                )
+            }
+            RuntimeErrorKind::DivideByZero => {
                 write!(f, "DivideByZero")
             }
-            RuntimeError::NonCallableValue(value) => {
+            RuntimeErrorKind::NonCallableValue(value) => {
                 write!(f, "NonCallableValue: {}", value)
             }
-            RuntimeError::EmptyList => {
+            RuntimeErrorKind::EmptyList => {
                 write!(f, "EmptyList")
             }
-            RuntimeError::SyntaxError(parse_error) => {
+            RuntimeErrorKind::SyntaxError(parse_error) => {
                 write!(f, "SyntaxError: {}", parse_error)
             }
-            RuntimeError::InvalidClosure => write!(f, "InvalidClosure"),
+            RuntimeErrorKind::InvalidSyntax(form) => {
+                write!(f, "Invalid syntax: {}", form)
+            }
+            RuntimeErrorKind::ExpiredContinuation => {
+                write!(f, "Expired continuation")
+            }
+            RuntimeErrorKind::Error { message, irritants } => {
+                write!(f, "Error: {}", message)?;
+                for irritant in irritants {
+                    write!(f, " {}", irritant)?;
+                }
+                Ok(())
+            }
+            RuntimeErrorKind::Raise(value) => {
+                write!(f, "Uncaught exception: {}", value)
+            }
+        }
+    }
+}
+
+impl fmt::Display for TokenizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.span)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.span)
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{} at {}", self.kind, span),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.procedure {
+            Some(name) => write!(f, "{}", name)?,
+            None => write!(f, "<anonymous>")?,
+        }
+        if let Some(span) = self.span {
+            write!(f, " at {}", span)?;
         }
+        write!(f, ": {}", self.call)
     }
 }
@@ -0,0 +1,202 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    rc::{Rc, Weak},
+};
+
+/// 由 [`Heap`] 管理的对象
+pub trait Trace {
+    /// 访问对象直接持有的其他堆对象
+    fn trace(&self, tracer: &mut Tracer);
+
+    /// 释放对象持有的所有引用，用于打破不可达对象之间的循环引用
+    fn clear(&self);
+
+    /// 对象占用的大致字节数
+    fn size(&self) -> usize;
+}
+
+/// 记录追踪过程中访问到的堆对象
+#[derive(Default)]
+pub struct Tracer {
+    edges: Vec<usize>,
+}
+
+impl Tracer {
+    /// 记录一个引用，同一对象被持有几次就应当访问几次
+    pub fn visit<T: Trace>(&mut self, object: &Rc<T>) {
+        self.edges.push(address(Rc::as_ptr(object)));
+    }
+}
+
+/// 堆的统计信息
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct GcStats {
+    /// 当前存活的对象数量
+    pub live_objects: usize,
+    /// 存活对象占用的大致字节数
+    pub bytes: usize,
+    /// 已执行的回收次数
+    pub collections: usize,
+    /// 累计回收的对象数量
+    pub freed_objects: usize,
+}
+
+// 自动回收前最少分配的对象数量
+const MIN_THRESHOLD: usize = 4096;
+
+/// 解释器的堆，以标记-清除的方式回收环境等对象之间的循环引用
+///
+/// 对象本身仍由 [`Rc`] 管理，没有形成循环的对象在引用计数归零时立即释放。
+/// 堆只记录每个对象的弱引用，回收时：
+///
+/// 1. 统计每个对象被其他堆对象持有的次数，强引用计数多于该次数的对象
+///    还被堆以外的地方（解释器、求值器栈上的局部变量等）持有，与全局环境一起作为根；
+/// 2. 从根出发标记所有可达对象；
+/// 3. 清空未标记对象持有的引用，打破它们之间的循环，由引用计数完成释放。
+pub struct Heap {
+    objects: RefCell<Vec<Weak<dyn Trace>>>,
+    root: RefCell<Option<Weak<dyn Trace>>>,
+    allocated: Cell<usize>,
+    threshold: Cell<usize>,
+    collections: Cell<usize>,
+    freed_objects: Cell<usize>,
+}
+
+impl Heap {
+    pub fn new() -> Rc<Self> {
+        Rc::new(Self::default())
+    }
+
+    /// 将对象交由堆管理，分配的对象足够多时自动执行一次回收
+    pub fn register<T: Trace + 'static>(&self, object: &Rc<T>) {
+        let object: Weak<T> = Rc::downgrade(object);
+        self.objects.borrow_mut().push(object);
+
+        self.allocated.set(self.allocated.get() + 1);
+        if self.allocated.get() >= self.threshold.get() {
+            self.collect();
+        }
+    }
+
+    /// 设置根对象，根对象总是存活
+    pub fn set_root<T: Trace + 'static>(&self, object: &Rc<T>) {
+        let object: Weak<T> = Rc::downgrade(object);
+        *self.root.borrow_mut() = Some(object);
+    }
+
+    /// 执行一次回收，返回回收的对象数量
+    pub fn collect(&self) -> usize {
+        let live: Vec<Rc<dyn Trace>> = self
+            .objects
+            .borrow()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect();
+        let index: HashMap<usize, usize> = live
+            .iter()
+            .enumerate()
+            .map(|(i, object)| (address(Rc::as_ptr(object)), i))
+            .collect();
+
+        let edges: Vec<Vec<usize>> = live
+            .iter()
+            .map(|object| {
+                let mut tracer = Tracer::default();
+                object.trace(&mut tracer);
+                tracer
+                    .edges
+                    .iter()
+                    .filter_map(|edge| index.get(edge).copied())
+                    .collect()
+            })
+            .collect();
+
+        let mut internal = vec![0; live.len()];
+        for &edge in edges.iter().flatten() {
+            internal[edge] += 1;
+        }
+
+        // `live` 自身也持有每个对象的一个强引用
+        let root = self
+            .root
+            .borrow()
+            .as_ref()
+            .map(|root| address(root.as_ptr()));
+        let mut stack: Vec<usize> = (0..live.len())
+            .filter(|&i| {
+                Rc::strong_count(&live[i]) > internal[i] + 1
+                    || Some(address(Rc::as_ptr(&live[i]))) == root
+            })
+            .collect();
+
+        let mut marked = vec![false; live.len()];
+        while let Some(i) = stack.pop() {
+            if !marked[i] {
+                marked[i] = true;
+                stack.extend(edges[i].iter().filter(|&&edge| !marked[edge]));
+            }
+        }
+
+        let mut freed = 0;
+        for (object, _) in live.iter().zip(&marked).filter(|(_, &marked)| !marked) {
+            object.clear();
+            freed += 1;
+        }
+        drop(live);
+
+        let mut objects = self.objects.borrow_mut();
+        objects.retain(|object| object.strong_count() > 0);
+        self.allocated.set(0);
+        self.threshold.set(MIN_THRESHOLD.max(objects.len() * 2));
+        self.collections.set(self.collections.get() + 1);
+        self.freed_objects.set(self.freed_objects.get() + freed);
+
+        freed
+    }
+
+    pub fn stats(&self) -> GcStats {
+        let (live_objects, bytes) = self
+            .objects
+            .borrow()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .fold((0, 0), |(count, bytes), object| {
+                (count + 1, bytes + object.size())
+            });
+
+        GcStats {
+            live_objects,
+            bytes,
+            collections: self.collections.get(),
+            freed_objects: self.freed_objects.get(),
+        }
+    }
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self {
+            objects: RefCell::new(Vec::new()),
+            root: RefCell::new(None),
+            allocated: Cell::new(0),
+            threshold: Cell::new(MIN_THRESHOLD),
+            collections: Cell::new(0),
+            freed_objects: Cell::new(0),
+        }
+    }
+}
+
+impl fmt::Debug for Heap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Heap")
+            .field("objects", &self.objects.borrow().len())
+            .field("collections", &self.collections.get())
+            .finish_non_exhaustive()
+    }
+}
+
+fn address<T: ?Sized>(pointer: *const T) -> usize {
+    pointer.cast::<()>() as usize
+}
@@ -3,16 +3,88 @@ use core::fmt;
 #[derive(Debug, PartialEq, Clone)]
 pub enum Keyword {
     Define,
+    DefineMacro,
+    DefineSyntax,
+    LetSyntax,
+    LetrecSyntax,
+    SyntaxRules,
     Lambda,
+    CaseLambda,
+    Let,
+    LetStar,
+    Letrec,
+    LetrecStar,
+    Set,
     If,
+    Cond,
+    Case,
+    And,
+    Or,
+    When,
+    Unless,
+    Begin,
+    Guard,
+    Quote,
+    Quasiquote,
+}
+
+impl Keyword {
+    /// 顶层环境中绑定的所有关键字
+    pub const ALL: [Keyword; 24] = [
+        Keyword::Define,
+        Keyword::DefineMacro,
+        Keyword::DefineSyntax,
+        Keyword::LetSyntax,
+        Keyword::LetrecSyntax,
+        Keyword::SyntaxRules,
+        Keyword::Lambda,
+        Keyword::CaseLambda,
+        Keyword::Let,
+        Keyword::LetStar,
+        Keyword::Letrec,
+        Keyword::LetrecStar,
+        Keyword::Set,
+        Keyword::If,
+        Keyword::Cond,
+        Keyword::Case,
+        Keyword::And,
+        Keyword::Or,
+        Keyword::When,
+        Keyword::Unless,
+        Keyword::Begin,
+        Keyword::Guard,
+        Keyword::Quote,
+        Keyword::Quasiquote,
+    ];
 }
 
 impl fmt::Display for Keyword {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Keyword::Define => write!(f, "define"),
+            Keyword::DefineMacro => write!(f, "define-macro"),
+            Keyword::DefineSyntax => write!(f, "define-syntax"),
+            Keyword::LetSyntax => write!(f, "let-syntax"),
+            Keyword::LetrecSyntax => write!(f, "letrec-syntax"),
+            Keyword::SyntaxRules => write!(f, "syntax-rules"),
             Keyword::Lambda => write!(f, "lambda"),
+            Keyword::CaseLambda => write!(f, "case-lambda"),
+            Keyword::Let => write!(f, "let"),
+            Keyword::LetStar => write!(f, "let*"),
+            Keyword::Letrec => write!(f, "letrec"),
+            Keyword::LetrecStar => write!(f, "letrec*"),
+            Keyword::Set => write!(f, "set!"),
             Keyword::If => write!(f, "if"),
+            Keyword::Cond => write!(f, "cond"),
+            Keyword::Case => write!(f, "case"),
+            Keyword::And => write!(f, "and"),
+            Keyword::Or => write!(f, "or"),
+            Keyword::When => write!(f, "when"),
+            Keyword::Unless => write!(f, "unless"),
+            Keyword::Begin => write!(f, "begin"),
+            Keyword::Guard => write!(f, "guard"),
+            Keyword::Quote => write!(f, "quote"),
+            Keyword::Quasiquote => write!(f, "quasiquote"),
         }
     }
 }
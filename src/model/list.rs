@@ -0,0 +1,91 @@
+use std::{ops::Deref, rc::Rc};
+
+use super::{Span, Tracer, Value};
+
+/// 列表，由语法分析器产生时会记录整个括号表达式在源码中的位置
+///
+/// 元素由列表的所有副本共享，复制列表与取列表的尾部都不会复制其中的元素。位置信息不参与比较。
+#[derive(Debug, Clone, Default)]
+pub struct List {
+    items: Rc<[Value]>,
+    // 列表从 `items` 中的这个位置开始
+    start: usize,
+    span: Option<Span>,
+}
+
+impl List {
+    pub fn new(items: Vec<Value>) -> Self {
+        Self {
+            items: items.into(),
+            start: 0,
+            span: None,
+        }
+    }
+
+    pub fn with_span(self, span: Span) -> Self {
+        Self {
+            span: Some(span),
+            ..self
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// 去掉第一个元素后的列表，与原列表共享元素
+    ///
+    /// # Panics
+    ///
+    /// 列表为空时 panic。
+    pub fn tail(&self) -> List {
+        assert!(!self.is_empty(), "tail of empty list");
+        Self {
+            items: Rc::clone(&self.items),
+            start: self.start + 1,
+            span: None,
+        }
+    }
+
+    /// 两个列表是否为同一份元素的同一部分
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.items, &other.items) && self.start == other.start
+    }
+
+    pub fn as_slice(&self) -> &[Value] {
+        &self.items[self.start..]
+    }
+
+    pub fn into_vec(self) -> Vec<Value> {
+        self.as_slice().to_vec()
+    }
+
+    /// 访问元素持有的堆对象，供垃圾回收器追踪
+    ///
+    /// 元素由列表的所有副本共享，只有这是唯一的副本时才逐个追踪，否则其中引用的对象总被视为存活。
+    pub fn trace(&self, tracer: &mut Tracer) {
+        if Rc::strong_count(&self.items) == 1 {
+            self.iter().for_each(|value| value.trace(tracer));
+        }
+    }
+}
+
+impl Deref for List {
+    type Target = [Value];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl From<Vec<Value>> for List {
+    fn from(value: Vec<Value>) -> Self {
+        Self::new(value)
+    }
+}
+
+impl PartialEq for List {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
@@ -1,15 +1,55 @@
+mod activation;
+mod arity;
 mod closure;
+mod code;
+mod condition;
+mod continuation;
+mod control;
 mod environment;
 mod error;
+mod heap;
 mod keyword;
+mod list;
+mod name;
+mod node;
 mod numeric;
+mod pair;
+mod params;
+mod procedure;
+mod span;
+mod symbol;
+mod syntax_rules;
 mod token;
 mod value;
 
-pub use closure::{Closure, TailCall};
+pub(crate) use activation::{Activation, Invocation, Resume, Site};
+pub use arity::Arity;
+pub use closure::Closure;
+pub use code::Code;
+pub(crate) use code::Op;
+pub use condition::Condition;
+pub(crate) use continuation::{Cont, Pending};
+pub use continuation::{Continuation, Winder};
+pub use control::Control;
 pub use environment::Environment;
-pub use error::{ParseError, RuntimeError, TokenizeError};
+pub use error::{
+    Frame, Note, ParseError, ParseErrorInner, ParseErrorKind, RuntimeError, RuntimeErrorInner,
+    RuntimeErrorKind, TokenizeError, TokenizeErrorKind,
+};
+pub use heap::{GcStats, Heap, Trace, Tracer};
 pub use keyword::Keyword;
-pub use numeric::Numeric;
+pub use list::List;
+pub use name::Name;
+pub(crate) use node::{
+    Body, Call, Clause, Consequent, Guard, Let, Letrec, NamedLet, NodeKind, Test, Variable,
+};
+pub use node::{Lambda, Node};
+pub use numeric::{Numeric, PrecisionGuard};
+pub use pair::Pair;
+pub use params::Params;
+pub use procedure::Procedure;
+pub use span::{SourceId, Span, Spanned};
+pub use symbol::{Renamed, Symbol};
+pub use syntax_rules::SyntaxRules;
 pub use token::Token;
 pub use value::Value;
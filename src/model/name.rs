@@ -0,0 +1,137 @@
+use core::fmt;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    ptr,
+    rc::{Rc, Weak},
+};
+
+use super::Symbol;
+
+/// 驻留在符号表中的名称
+///
+/// 拼写相同的名称共享同一份字符串，复制只增加引用计数，比较与哈希只使用字符串的地址。
+/// 符号表属于当前线程，没有名称再引用的字符串会在之后驻留新名称时从表中清除。
+#[derive(Clone)]
+pub struct Name(Rc<str>);
+
+// 表中的条目数超过上次清除后存活条目数的两倍时清除失效的条目
+#[derive(Default)]
+struct Table {
+    names: HashMap<Box<str>, Weak<str>>,
+    threshold: usize,
+}
+
+thread_local! {
+    static TABLE: RefCell<Table> = RefCell::default();
+}
+
+impl Name {
+    /// 在符号表中查找拼写为 `name` 的名称，不存在时加入符号表
+    pub fn intern(name: &str) -> Self {
+        TABLE.with(|table| {
+            let mut table = table.borrow_mut();
+            if let Some(interned) = table.names.get(name).and_then(Weak::upgrade) {
+                return Self(interned);
+            }
+
+            if table.names.len() >= table.threshold {
+                table
+                    .names
+                    .retain(|_, interned| interned.strong_count() > 0);
+                table.threshold = (table.names.len() * 2).max(1024);
+            }
+            let interned: Rc<str> = Rc::from(name);
+            table.names.insert(name.into(), Rc::downgrade(&interned));
+            Self(interned)
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Name {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<&str> for Name {
+    fn from(value: &str) -> Self {
+        Self::intern(value)
+    }
+}
+
+impl From<String> for Name {
+    fn from(value: String) -> Self {
+        Self::intern(&value)
+    }
+}
+
+impl From<&String> for Name {
+    fn from(value: &String) -> Self {
+        Self::intern(value)
+    }
+}
+
+impl From<&Symbol> for Name {
+    fn from(value: &Symbol) -> Self {
+        value.name().clone()
+    }
+}
+
+impl From<&Name> for Name {
+    fn from(value: &Name) -> Self {
+        value.clone()
+    }
+}
+
+impl PartialEq for Name {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Name {}
+
+impl Hash for Name {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ptr::hash(Rc::as_ptr(&self.0).cast::<u8>(), state);
+    }
+}
+
+impl PartialEq<str> for Name {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Name {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<Name> for String {
+    fn eq(&self, other: &Name) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
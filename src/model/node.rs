@@ -0,0 +1,158 @@
+use std::rc::Rc;
+
+use super::{List, Params, RuntimeError, Span, Symbol, Value};
+
+/// 分析得到的表达式，变量引用已解析为环境中的位置，特殊形式已经确定
+///
+/// 分析结果由闭包的所有副本共享，其中的常量不被垃圾回收器追踪。
+pub struct Node {
+    pub(crate) kind: NodeKind,
+    /// 求值出错时报告的位置
+    pub(crate) span: Option<Span>,
+}
+
+/// 依次求值的表达式，最后一个处于尾部位置
+pub(crate) type Body = Rc<[Rc<Node>]>;
+
+pub(crate) enum NodeKind {
+    Constant(Value),
+    /// 向外第 `depth` 层环境中第 `index` 个位置的变量，尚未绑定时以 `symbol` 报告错误
+    Local {
+        depth: usize,
+        index: usize,
+        symbol: Symbol,
+    },
+    /// 按名称在环境中查找的变量
+    Global(Symbol),
+    /// 局部定义总是位于当前这一层环境中
+    Define {
+        variable: Variable,
+        value: Rc<Node>,
+    },
+    /// 在运行时定义的顶层宏
+    DefineMacro {
+        name: Symbol,
+        value: Rc<Node>,
+    },
+    DefineSyntax {
+        name: Symbol,
+        value: Rc<Node>,
+    },
+    Set {
+        variable: Variable,
+        value: Rc<Node>,
+    },
+    Lambda(Rc<Lambda>),
+    CaseLambda(Rc<[Rc<Lambda>]>),
+    If {
+        test: Rc<Node>,
+        consequent: Rc<Node>,
+        alternative: Option<Rc<Node>>,
+    },
+    Cond(Rc<[Clause]>),
+    Case {
+        key: Rc<Node>,
+        clauses: Rc<[Clause]>,
+    },
+    And(Body),
+    Or(Body),
+    When {
+        test: Rc<Node>,
+        body: Body,
+        expected: bool,
+    },
+    Sequence(Body),
+    Let(Rc<Let>),
+    Letrec(Rc<Letrec>),
+    NamedLet(Rc<NamedLet>),
+    Guard(Rc<Guard>),
+    Call(Rc<Call>),
+    /// 分析时发现的错误，求值到这里时才报告
+    Fail(RuntimeError),
+}
+
+/// 初始值在外层环境中求值，全部求值之后依次绑定到新环境的前几个位置
+pub(crate) struct Let {
+    pub inits: Body,
+    pub size: usize,
+    pub body: Body,
+}
+
+/// 初始值在新环境中求值，也用于 `let*` 与 `let-syntax`。
+/// `sequential` 时每个初始值求值之后立即绑定，否则全部求值之后才绑定
+pub(crate) struct Letrec {
+    pub inits: Body,
+    pub size: usize,
+    pub body: Body,
+    pub sequential: bool,
+}
+
+/// 初始值在外层环境中求值，然后调用只对过程体可见的循环过程
+pub(crate) struct NamedLet {
+    pub lambda: Rc<Lambda>,
+    pub inits: Body,
+    pub form: List,
+}
+
+/// 捕获的值绑定在新环境的第一个位置，然后测试 `cond` 形式的 `clauses`
+pub(crate) struct Guard {
+    pub body: Body,
+    pub size: usize,
+    pub clauses: Rc<Node>,
+    pub form: List,
+}
+
+pub(crate) struct Call {
+    pub operator: Rc<Node>,
+    pub args: Body,
+    pub form: List,
+}
+
+/// `define` 与 `set!` 的目标
+#[derive(Clone)]
+pub(crate) enum Variable {
+    Local { depth: usize, index: usize },
+    Global(Symbol),
+}
+
+/// `cond` 与 `case` 的子句
+pub(crate) struct Clause {
+    pub test: Test,
+    pub consequent: Consequent,
+    pub form: List,
+}
+
+pub(crate) enum Test {
+    Else,
+    Expr(Rc<Node>),
+    /// `case` 中与键比较的数据
+    Data(Vec<Value>),
+}
+
+pub(crate) enum Consequent {
+    /// 没有表达式时结果即为测试的结果
+    Value,
+    /// `=> receiver`，以测试的结果调用 `receiver`
+    Receive(Rc<Node>),
+    Body(Body),
+}
+
+/// 分析得到的过程，形参依次占用过程环境中最前面的位置
+pub struct Lambda {
+    pub(crate) name: Option<Rc<str>>,
+    pub(crate) params: Rc<Params>,
+    /// 未经分析的过程体，创建的闭包保留它们
+    pub(crate) forms: Rc<[Value]>,
+    pub(crate) span: Option<Span>,
+    /// 过程环境中位置的数量
+    pub(crate) size: usize,
+    /// 可选参数与关键字参数的默认值，调用时没有提供的参数依次求值
+    pub(crate) defaults: Body,
+    pub(crate) body: Body,
+}
+
+impl Node {
+    pub(crate) fn new(kind: NodeKind, span: Option<Span>) -> Rc<Self> {
+        Rc::new(Self { kind, span })
+    }
+}
@@ -1,50 +1,294 @@
 use core::fmt;
-use std::ops::{Add, Div, Mul, Sub};
+use std::{
+    cell::Cell,
+    ops::{Add, Div, Mul, Sub},
+};
 
-use rug::{Float, Integer};
+use rug::{Complex, Float, Integer, Rational};
 
+use super::{RuntimeError, RuntimeErrorKind, Value};
+
+/// 数值
+///
+/// 整数在机器字长的范围内直接保存为 `Fixnum`，运算溢出时提升为任意精度的 `Integer`，
+/// 结果重新落入机器字长的范围时再降回 `Fixnum`。
+/// 整数与有理数是精确的，分母为 1 的有理数总是化为整数；
+/// 精确数与浮点数运算的结果为浮点数。
+/// 复数总是不精确的，虚部为零的复数化为浮点数。
+///
+/// 不精确的运算结果的精度取运算数与当前精度 [`Numeric::precision`] 中最高的一个。
 #[derive(Debug, Clone)]
 pub enum Numeric {
+    Fixnum(i64),
     Integer(Integer),
+    Rational(Rational),
     Float(Float),
+    Complex(Complex),
 }
 
 impl fmt::Display for Numeric {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Self::Fixnum(n) => write!(f, "{}", n),
             Self::Integer(n) => write!(f, "{}", n),
+            Self::Rational(n) => write!(f, "{}", n),
             Self::Float(n) => write!(f, "{}", n),
+            Self::Complex(n) => {
+                let sign = if n.imag().is_sign_negative() { "" } else { "+" };
+                write!(f, "{}{}{}i", n.real(), sign, n.imag())
+            }
         }
     }
 }
 
+thread_local! {
+    static PRECISION: Cell<u32> = const { Cell::new(Numeric::DEFAULT_PRECISION) };
+}
+
+/// 离开作用域时恢复当前线程原来的浮点数精度，由 [`Numeric::scoped_precision`] 创建
+#[must_use]
+pub struct PrecisionGuard(u32);
+
+impl Drop for PrecisionGuard {
+    fn drop(&mut self) {
+        PRECISION.set(self.0);
+    }
+}
+
+// 复数参与的运算先将另一个数转换为复数，
+// 浮点数参与的运算直接与另一个数运算，精确数之间的运算先提升到两者中较宽的类型。
+// `$fixnum` 与 `$integer` 分别计算两个 `Fixnum` 与两个整数的结果
 macro_rules! impl_numeric_op {
-    ($trait:ident, $method:ident) => {
+    ($trait:ident, $method:ident, $fixnum:expr, $integer:expr) => {
         impl $trait for Numeric {
             type Output = Numeric;
 
             fn $method(self, rhs: Self) -> Self::Output {
+                let current = Numeric::precision();
                 match (self, rhs) {
-                    (Self::Integer(a), Self::Integer(b)) => Self::Integer(a.$method(b)),
-                    (Self::Integer(a), Self::Float(b)) => Self::Float(a.$method(b)),
-                    (Self::Float(a), Self::Integer(b)) => Self::Float(a.$method(b)),
-                    (Self::Float(a), Self::Float(b)) => Self::Float(a.$method(b)),
+                    (Self::Fixnum(a), Self::Fixnum(b)) => $fixnum(a, b),
+                    (a @ Self::Complex(_), b) | (a, b @ Self::Complex(_)) => {
+                        let (a, b) = (a.into_complex(), b.into_complex());
+                        let prec = current.max(a.prec().0).max(b.prec().0);
+                        Complex::with_val(prec, (&a).$method(&b)).into()
+                    }
+                    (Self::Float(a), Self::Float(b)) => Self::Float(Float::with_val(
+                        current.max(a.prec()).max(b.prec()),
+                        (&a).$method(&b),
+                    )),
+                    (Self::Float(a), Self::Fixnum(b)) => {
+                        Self::Float(Float::with_val(current.max(a.prec()), (&a).$method(b)))
+                    }
+                    (Self::Float(a), Self::Integer(b)) => {
+                        Self::Float(Float::with_val(current.max(a.prec()), (&a).$method(&b)))
+                    }
+                    (Self::Float(a), Self::Rational(b)) => {
+                        Self::Float(Float::with_val(current.max(a.prec()), (&a).$method(&b)))
+                    }
+                    (Self::Fixnum(a), Self::Float(b)) => {
+                        Self::Float(Float::with_val(current.max(b.prec()), a.$method(&b)))
+                    }
+                    (Self::Integer(a), Self::Float(b)) => {
+                        Self::Float(Float::with_val(current.max(b.prec()), (&a).$method(&b)))
+                    }
+                    (Self::Rational(a), Self::Float(b)) => {
+                        Self::Float(Float::with_val(current.max(b.prec()), (&a).$method(&b)))
+                    }
+                    (a @ Self::Rational(_), b) | (a, b @ Self::Rational(_)) => {
+                        a.into_rational().$method(b.into_rational()).into()
+                    }
+                    (a, b) => $integer(a.into_integer(), b.into_integer()),
                 }
             }
         }
     };
 }
 
-impl_numeric_op!(Add, add);
-impl_numeric_op!(Sub, sub);
-impl_numeric_op!(Mul, mul);
-impl_numeric_op!(Div, div);
+impl_numeric_op!(
+    Add,
+    add,
+    |a: i64, b: i64| a
+        .checked_add(b)
+        .map_or_else(|| (Integer::from(a) + b).into(), Numeric::Fixnum),
+    |a: Integer, b: Integer| (a + b).into()
+);
+impl_numeric_op!(
+    Sub,
+    sub,
+    |a: i64, b: i64| a
+        .checked_sub(b)
+        .map_or_else(|| (Integer::from(a) - b).into(), Numeric::Fixnum),
+    |a: Integer, b: Integer| (a - b).into()
+);
+impl_numeric_op!(
+    Mul,
+    mul,
+    |a: i64, b: i64| a
+        .checked_mul(b)
+        .map_or_else(|| (Integer::from(a) * b).into(), Numeric::Fixnum),
+    |a: Integer, b: Integer| (a * b).into()
+);
+// 整数相除的结果是精确的有理数，整除时仍为整数
+impl_numeric_op!(
+    Div,
+    div,
+    |a: i64, b: i64| match a.checked_rem(b) {
+        Some(0) => a
+            .checked_div(b)
+            .map_or_else(|| (Integer::from(a) / b).into(), Numeric::Fixnum),
+        _ => Rational::from((a, b)).into(),
+    },
+    |a: Integer, b: Integer| Rational::from((a, b)).into()
+);
 
 impl Numeric {
+    /// 默认的浮点数精度，与 `f64` 的有效位数相同
+    pub const DEFAULT_PRECISION: u32 = 53;
+
+    /// 当前线程的浮点数精度，即精确数转换为浮点数时的位数
+    pub fn precision() -> u32 {
+        PRECISION.get()
+    }
+
+    /// `bits` 是否在 MPFR 支持的精度范围内
+    pub fn is_valid_precision(bits: u32) -> bool {
+        (rug::float::prec_min()..=rug::float::prec_max()).contains(&bits)
+    }
+
+    /// 检查精度是否在 MPFR 支持的范围内
+    pub fn check_precision(bits: u32) -> Result<u32, RuntimeError> {
+        if Self::is_valid_precision(bits) {
+            Ok(bits)
+        } else {
+            Err(RuntimeErrorKind::TypeError {
+                expected: "precision in bits",
+                founded: Value::Numeric(Numeric::Fixnum(bits.into())),
+            }
+            .into())
+        }
+    }
+
+    /// 设置当前线程的浮点数精度，超出范围时保持原来的精度
+    pub fn set_precision(bits: u32) -> Result<(), RuntimeError> {
+        PRECISION.set(Self::check_precision(bits)?);
+        Ok(())
+    }
+
+    /// 设置当前线程的浮点数精度，返回的守卫离开作用域时恢复原来的精度
+    pub fn scoped_precision(bits: u32) -> Result<PrecisionGuard, RuntimeError> {
+        let previous = Self::precision();
+        Self::set_precision(bits)?;
+        Ok(PrecisionGuard(previous))
+    }
+
     pub fn is_zero(&self) -> bool {
         match self {
+            Numeric::Fixnum(n) => *n == 0,
             Numeric::Integer(n) => n.is_zero(),
+            Numeric::Rational(n) => n.is_zero(),
             Numeric::Float(f) => f.is_zero(),
+            Numeric::Complex(c) => c.real().is_zero() && c.imag().is_zero(),
+        }
+    }
+
+    /// 是否为精确数，即整数或有理数
+    pub fn is_exact(&self) -> bool {
+        !matches!(self, Numeric::Float(_) | Numeric::Complex(_))
+    }
+
+    /// 转换为不精确数，浮点数与复数保持不变
+    #[must_use]
+    pub fn to_inexact(&self) -> Numeric {
+        match self {
+            Numeric::Complex(_) => self.clone(),
+            real => Numeric::Float(real.to_float().unwrap()),
+        }
+    }
+
+    /// 实数转换为浮点数，精确数转换为当前精度的浮点数，复数返回 `None`
+    pub fn to_float(&self) -> Option<Float> {
+        let precision = Self::precision();
+        match self {
+            Numeric::Fixnum(n) => Some(Float::with_val(precision, *n)),
+            Numeric::Integer(n) => Some(Float::with_val(precision, n)),
+            Numeric::Rational(n) => Some(Float::with_val(precision, n)),
+            Numeric::Float(f) => Some(f.clone()),
+            Numeric::Complex(_) => None,
+        }
+    }
+
+    /// 值相等的有理数，无穷大、NaN 与复数没有对应的有理数
+    pub fn to_rational(&self) -> Option<Rational> {
+        match self {
+            Numeric::Fixnum(n) => Some(Rational::from(*n)),
+            Numeric::Integer(n) => Some(Rational::from(n)),
+            Numeric::Rational(n) => Some(n.clone()),
+            Numeric::Float(f) => f.to_rational(),
+            Numeric::Complex(_) => None,
+        }
+    }
+
+    // 精确数转换为有理数，不能用于不精确数
+    fn into_rational(self) -> Rational {
+        match self {
+            Numeric::Fixnum(n) => n.into(),
+            Numeric::Integer(n) => n.into(),
+            Numeric::Rational(n) => n,
+            Numeric::Float(_) | Numeric::Complex(_) => unreachable!("not exact"),
+        }
+    }
+
+    fn into_complex(self) -> Complex {
+        match self {
+            Numeric::Complex(n) => n,
+            real => {
+                let real = real.to_float().unwrap();
+                Complex::with_val(real.prec(), real)
+            }
+        }
+    }
+
+    // 整数转换为 `Integer`，不能用于其他数
+    fn into_integer(self) -> Integer {
+        match self {
+            Numeric::Fixnum(n) => n.into(),
+            Numeric::Integer(n) => n,
+            _ => unreachable!("not an integer"),
+        }
+    }
+}
+
+impl From<i64> for Numeric {
+    fn from(value: i64) -> Self {
+        Self::Fixnum(value)
+    }
+}
+
+impl From<Integer> for Numeric {
+    fn from(value: Integer) -> Self {
+        match value.to_i64() {
+            Some(n) => Self::Fixnum(n),
+            None => Self::Integer(value),
+        }
+    }
+}
+
+impl From<Rational> for Numeric {
+    fn from(value: Rational) -> Self {
+        if *value.denom() == 1 {
+            value.into_numer_denom().0.into()
+        } else {
+            Self::Rational(value)
+        }
+    }
+}
+
+impl From<Complex> for Numeric {
+    fn from(value: Complex) -> Self {
+        if value.imag().is_zero() {
+            Self::Float(value.into_real_imag().0)
+        } else {
+            Self::Complex(value)
         }
     }
 }
@@ -52,10 +296,19 @@ impl Numeric {
 impl PartialEq for Numeric {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
+            (Self::Fixnum(a), Self::Fixnum(b)) => a == b,
             (Self::Integer(a), Self::Integer(b)) => a == b,
+            (Self::Rational(a), Self::Rational(b)) => a == b,
             (Self::Float(a), Self::Float(b)) => a == b,
-            (Self::Integer(a), Self::Float(b)) => &Float::with_val(53, a) == b,
-            (Self::Float(a), Self::Integer(b)) => a == &Float::with_val(53, b),
+            (Self::Complex(a), Self::Complex(b)) => a == b,
+            // 复数的虚部不为零，不与任何实数相等
+            (Self::Complex(_), _) | (_, Self::Complex(_)) => false,
+            (Self::Fixnum(a), Self::Integer(b)) | (Self::Integer(b), Self::Fixnum(a)) => b == a,
+            (Self::Fixnum(a), Self::Rational(b)) | (Self::Rational(b), Self::Fixnum(a)) => b == a,
+            (Self::Integer(a), Self::Rational(b)) | (Self::Rational(b), Self::Integer(a)) => b == a,
+            (Self::Fixnum(a), Self::Float(b)) | (Self::Float(b), Self::Fixnum(a)) => b == a,
+            (Self::Rational(a), Self::Float(b)) | (Self::Float(b), Self::Rational(a)) => b == a,
+            (Self::Integer(a), Self::Float(b)) | (Self::Float(b), Self::Integer(a)) => b == a,
         }
     }
 }
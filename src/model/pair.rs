@@ -0,0 +1,123 @@
+use std::{cell::RefCell, mem, rc::Rc};
+
+use super::{Heap, List, Span, Trace, Tracer, Value};
+
+/// 序对，`car` 与 `cdr` 都可以被修改
+///
+/// 以空列表结尾的序对链表示正规列表，与元素相同的 [`List`] 相等。
+/// 由语法分析器产生的带点号的表达式会记录它在源码中的位置，位置信息不参与比较。
+#[derive(Debug)]
+pub struct Pair {
+    car: RefCell<Value>,
+    cdr: RefCell<Value>,
+    span: Option<Span>,
+}
+
+impl Pair {
+    pub fn new(car: Value, cdr: Value) -> Self {
+        Self {
+            car: RefCell::new(car),
+            cdr: RefCell::new(cdr),
+            span: None,
+        }
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// 在堆上创建序对，序对之间可能通过 `set-cdr!` 等形成循环引用
+    pub fn alloc(car: Value, cdr: Value, heap: &Heap) -> Rc<Self> {
+        let pair = Rc::new(Self::new(car, cdr));
+        heap.register(&pair);
+        pair
+    }
+
+    pub fn car(&self) -> Value {
+        self.car.borrow().clone()
+    }
+
+    pub fn cdr(&self) -> Value {
+        self.cdr.borrow().clone()
+    }
+
+    pub fn set_car(&self, value: Value) {
+        *self.car.borrow_mut() = value;
+    }
+
+    pub fn set_cdr(&self, value: Value) {
+        *self.cdr.borrow_mut() = value;
+    }
+
+    /// 沿 `cdr` 依次取出所有元素，并返回最后的尾部
+    ///
+    /// 尾部为空列表时说明这是一个正规列表，以 [`List`] 结尾的部分会被展开。
+    pub fn flatten(&self) -> (Vec<Value>, Value) {
+        let mut items = vec![self.car()];
+        let mut tail = self.cdr();
+        loop {
+            match tail {
+                Value::Pair(pair) => {
+                    items.push(pair.car());
+                    tail = pair.cdr();
+                }
+                Value::List(list) => {
+                    items.extend(list.into_vec());
+                    return (items, Value::List(List::default()));
+                }
+                tail => return (items, tail),
+            }
+        }
+    }
+}
+
+impl PartialEq for Pair {
+    fn eq(&self, other: &Self) -> bool {
+        self.flatten() == other.flatten()
+    }
+}
+
+// 深层嵌套的序对逐层递归释放会耗尽栈空间，
+// 因此把只被这里引用的 `car` 与 `cdr` 取出放入工作表，逐个释放
+impl Drop for Pair {
+    fn drop(&mut self) {
+        let mut pending = Vec::new();
+        self.take_pairs(&mut pending);
+        while let Some(pair) = pending.pop() {
+            if let Ok(mut pair) = Rc::try_unwrap(pair) {
+                pair.take_pairs(&mut pending);
+            }
+        }
+    }
+}
+
+impl Pair {
+    fn take_pairs(&mut self, pending: &mut Vec<Rc<Pair>>) {
+        for field in [self.car.get_mut(), self.cdr.get_mut()] {
+            if let Value::Pair(pair) = mem::replace(field, Value::Void) {
+                pending.push(pair);
+            }
+        }
+    }
+}
+
+impl Trace for Pair {
+    fn trace(&self, tracer: &mut Tracer) {
+        self.car.borrow().trace(tracer);
+        self.cdr.borrow().trace(tracer);
+    }
+
+    fn clear(&self) {
+        self.set_car(Value::Void);
+        self.set_cdr(Value::Void);
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<Self>()
+    }
+}
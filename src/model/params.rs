@@ -0,0 +1,86 @@
+use super::{Arity, Name, RuntimeError, RuntimeErrorKind, Value};
+
+/// 过程的形参
+///
+/// 调用时实参依次绑定到必需参数与可选参数，之后以 `#:name value` 的形式传入关键字参数，
+/// 位置参数之后剩余的所有实参组成列表绑定到剩余参数。
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Params {
+    pub required: Vec<Name>,
+    /// 可选参数及其默认值表达式，默认值在之前的参数绑定后求值
+    pub optional: Vec<(Name, Value)>,
+    /// 关键字参数及其默认值表达式
+    pub keys: Vec<(Name, Value)>,
+    pub rest: Option<Name>,
+}
+
+impl Params {
+    /// 位置参数的数量范围，接受关键字参数或剩余参数时没有上限
+    pub fn arity(&self) -> Arity {
+        let positional = self.required.len() + self.optional.len();
+        Arity {
+            min: self.required.len(),
+            max: (self.rest.is_none() && self.keys.is_empty()).then_some(positional),
+        }
+    }
+
+    /// 将实参分为位置参数与其余的参数，参数数量不符时返回 `None`
+    ///
+    /// 接受关键字参数时，位置参数在第一个关键字处结束。
+    pub(crate) fn split_args<'a>(&self, args: &'a [Value]) -> Option<(&'a [Value], &'a [Value])> {
+        let positional = args
+            .iter()
+            .take(self.required.len() + self.optional.len())
+            .take_while(|arg| self.keys.is_empty() || Self::keyword_name(arg).is_none())
+            .count();
+        let (positional_args, rest_args) = args.split_at(positional);
+        if positional < self.required.len()
+            || (self.rest.is_none() && self.keys.is_empty() && !rest_args.is_empty())
+        {
+            return None;
+        }
+        Some((positional_args, rest_args))
+    }
+
+    /// 解析 `#:name value ...` 形式的关键字参数
+    pub(crate) fn keyword_args<'a>(
+        &self,
+        args: &'a [Value],
+    ) -> Result<Vec<(&'a str, &'a Value)>, RuntimeError> {
+        args.chunks(2)
+            .map(|chunk| match chunk {
+                [key, value] => match Self::keyword_name(key) {
+                    Some(name) if self.keys.iter().any(|(param, _)| param == name) => {
+                        Ok((name, value))
+                    }
+                    _ => Err(RuntimeErrorKind::TypeError {
+                        expected: "keyword argument",
+                        founded: key.clone(),
+                    }
+                    .into()),
+                },
+                _ => Err(RuntimeErrorKind::TypeError {
+                    expected: "keyword argument",
+                    founded: chunk[0].clone(),
+                }
+                .into()),
+            })
+            .try_collect()
+    }
+
+    fn keyword_name(value: &Value) -> Option<&str> {
+        match value {
+            Value::Symbol(symbol) => symbol.original().strip_prefix("#:"),
+            _ => None,
+        }
+    }
+}
+
+impl From<Vec<Name>> for Params {
+    fn from(required: Vec<Name>) -> Self {
+        Self {
+            required,
+            ..Self::default()
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use core::fmt;
+use std::rc::Rc;
+
+use super::{Code, Environment, Tracer};
+
+/// 由字节码编译器编译的过程
+#[derive(Clone)]
+pub struct Procedure {
+    pub(crate) code: Rc<Code>,
+    /// 创建过程时的环境，过程存活期间该环境也一直存活
+    pub(crate) environment: Rc<Environment>,
+}
+
+impl Procedure {
+    pub fn name(&self) -> Option<&str> {
+        self.code.name()
+    }
+
+    /// 访问过程持有的堆对象，供垃圾回收器追踪
+    ///
+    /// 编译得到的代码由所有副本共享，不能逐个副本追踪其中的常量。
+    pub fn trace(&self, tracer: &mut Tracer) {
+        tracer.visit(&self.environment);
+    }
+}
+
+// 同一段代码在同一个环境中创建的过程才相等
+impl PartialEq for Procedure {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.code, &other.code) && Rc::ptr_eq(&self.environment, &other.environment)
+    }
+}
+
+// 环境中通常保存着过程自身，因此不输出环境
+impl fmt::Debug for Procedure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Procedure")
+            .field("name", &self.name())
+            .field("span", &self.code.span)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,67 @@
+use core::fmt;
+
+/// 源码的编号，用于区分多段分别读取的源码，例如交互式环境中的每一次输入
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct SourceId(pub usize);
+
+/// 源码中的一段位置
+///
+/// `start..end` 为字节范围，`line` 与 `column` 为起始处的行列号（均从 1 开始，列号按字符计），
+/// `source` 为位置所在的源码。
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    pub source: SourceId,
+}
+
+/// 附带位置信息的节点，例如词法分析得到的 [`crate::model::Token`]
+#[derive(Debug, PartialEq, Clone)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+            source: SourceId::default(),
+        }
+    }
+
+    pub fn with_source(self, source: SourceId) -> Self {
+        Self { source, ..self }
+    }
+
+    /// 合并两段位置，结果从 `self` 的起始处延伸到 `other` 的结束处
+    pub fn to(self, other: Span) -> Self {
+        Self {
+            end: other.end,
+            ..self
+        }
+    }
+}
+
+impl Default for Span {
+    fn default() -> Self {
+        Self::new(0, 0, 1, 1)
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Self { node, span }
+    }
+}
@@ -0,0 +1,149 @@
+use core::fmt;
+use std::{ops::Deref, rc::Rc};
+
+use super::{Environment, Name, Span, Tracer};
+
+/// 符号，由语法分析器产生时会记录其在源码中的位置
+///
+/// 位置信息不参与比较，`Symbol::from("a")` 与源码中解析出的 `a` 相等。
+/// 名称驻留在符号表中，比较两个符号只需比较名称的地址。
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    name: Name,
+    span: Option<Span>,
+    renamed: Option<Rc<Renamed>>,
+}
+
+/// 卫生宏展开时模板引入的标识符会被重命名
+///
+/// 重命名后的符号绑定在唯一的名称上，不会与使用处的绑定冲突；
+/// 没有被展开结果绑定时，在定义宏的环境中查找原本的标识符。
+#[derive(Clone)]
+pub struct Renamed {
+    pub symbol: Symbol,
+    pub environment: Rc<Environment>,
+}
+
+impl Symbol {
+    pub fn new(name: impl Into<Name>) -> Self {
+        Self {
+            name: name.into(),
+            span: None,
+            renamed: None,
+        }
+    }
+
+    /// 重命名模板中的标识符，`id` 在每次展开时唯一
+    pub fn rename(symbol: &Symbol, environment: &Rc<Environment>, id: usize) -> Self {
+        Self {
+            // 词法分析器不接受 `|`，重命名后的名称不会与源码中的符号相同
+            name: Name::from(format!("{}|{}", symbol.name, id)),
+            span: symbol.span,
+            renamed: Some(Rc::new(Renamed {
+                symbol: symbol.clone(),
+                environment: Rc::clone(environment),
+            })),
+        }
+    }
+
+    pub fn renamed(&self) -> Option<&Renamed> {
+        self.renamed.as_deref()
+    }
+
+    /// 去掉所有重命名后的原始标识符
+    pub fn original(&self) -> &Symbol {
+        match &self.renamed {
+            Some(renamed) => renamed.symbol.original(),
+            None => self,
+        }
+    }
+
+    pub fn with_span(self, span: Span) -> Self {
+        Self {
+            span: Some(span),
+            ..self
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    /// 绑定所使用的名称，重命名后的符号为重命名后的名称
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// 访问重命名时记录的环境，供垃圾回收器追踪
+    pub fn trace(&self, tracer: &mut Tracer) {
+        let mut symbol = self;
+        while let Some(renamed) = symbol.renamed() {
+            tracer.visit(&renamed.environment);
+            symbol = &renamed.symbol;
+        }
+    }
+}
+
+impl Deref for Symbol {
+    type Target = Name;
+
+    fn deref(&self) -> &Self::Target {
+        &self.name
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<Name> for Symbol {
+    fn from(value: Name) -> Self {
+        Self::new(value)
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+// 与字符串比较时判断的是符号的拼写，重命名后的符号与原本的标识符拼写相同
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.original().name == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.original().name == *other
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.original().name)
+    }
+}
+
+// 定义宏的环境中通常保存着宏自身，因此不输出环境
+impl fmt::Debug for Renamed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Renamed")
+            .field("symbol", &self.symbol)
+            .finish_non_exhaustive()
+    }
+}
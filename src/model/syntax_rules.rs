@@ -0,0 +1,38 @@
+use core::fmt;
+use std::rc::Rc;
+
+use super::{Environment, Name, Value};
+
+/// 由 `syntax-rules` 定义的卫生宏
+#[derive(Clone)]
+pub struct SyntaxRules {
+    pub name: Option<Rc<str>>,
+    /// 省略号标识符，默认为 `...`
+    pub ellipsis: Name,
+    pub literals: Rc<[Name]>,
+    /// 依次尝试的模式与模板
+    pub rules: Rc<[(Value, Value)]>,
+    /// 定义宏时的环境，模板中引入的自由标识符在这里查找
+    pub environment: Rc<Environment>,
+}
+
+impl PartialEq for SyntaxRules {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.ellipsis == other.ellipsis
+            && self.literals == other.literals
+            && self.rules == other.rules
+    }
+}
+
+// 定义宏的环境中通常保存着宏自身，因此不输出环境
+impl fmt::Debug for SyntaxRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyntaxRules")
+            .field("name", &self.name)
+            .field("ellipsis", &self.ellipsis)
+            .field("literals", &self.literals)
+            .field("rules", &self.rules)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,17 +1,27 @@
 use core::fmt;
 
-use rug::{Float, Integer};
+use rug::{Complex, Float, Integer, Rational};
+
+use super::Name;
 
 /// 词法分析器 [`crate::lexer`] 中所有的标记
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     LParen,
     RParen,
-    Symbol(String),
+    Symbol(Name),
     Integer(Integer),
+    /// 形如 `3/4` 的有理数
+    Rational(Rational),
     Float(Float),
+    /// 形如 `1+2i` 或 `+i` 的复数
+    Complex(Complex),
     String(String),
     Quote,
+    Quasiquote,
+    Unquote,
+    UnquoteSplicing,
+    Dot,
 }
 
 impl fmt::Display for Token {
@@ -21,9 +31,22 @@ impl fmt::Display for Token {
             Token::RParen => write!(f, ")"),
             Token::Symbol(symbol) => write!(f, "{}", symbol),
             Token::Integer(integer) => write!(f, "{}", integer),
+            Token::Rational(rational) => write!(f, "{}", rational),
             Token::Float(float) => write!(f, "{}", float),
+            Token::Complex(complex) => {
+                let sign = if complex.imag().is_sign_negative() {
+                    ""
+                } else {
+                    "+"
+                };
+                write!(f, "{}{}{}i", complex.real(), sign, complex.imag())
+            }
             Token::String(string) => write!(f, "\"{}\"", string),
             Token::Quote => write!(f, "'"),
+            Token::Quasiquote => write!(f, "`"),
+            Token::Unquote => write!(f, ","),
+            Token::UnquoteSplicing => write!(f, ",@"),
+            Token::Dot => write!(f, "."),
         }
     }
 }
@@ -1,28 +1,46 @@
 use core::fmt;
-use rug::{Complete, Float, Integer};
+use std::rc::Rc;
+
+use rug::{Complete, Complex, Float, Integer, Rational};
 
 use crate::internal::InternalFunction;
 
-use super::{Closure, Keyword, Numeric, ParseError, RuntimeError, TailCall, Token};
+use super::{
+    Closure, Condition, Continuation, Control, Keyword, List, Numeric, Pair, ParseErrorKind,
+    Procedure, RuntimeError, RuntimeErrorKind, Span, Symbol, SyntaxRules, Token, Tracer,
+};
 
 /// 包含了所有可能的 Lisp 值，包括原子、列表等等。
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, Clone)]
 pub enum Value {
     Void,
     Numeric(Numeric),
     Bool(bool),
-    Symbol(String),
-    String(String),
-    List(Vec<Value>),
-    Quoted(Box<Value>),
+    Symbol(Symbol),
+    String(Rc<str>),
+    List(List),
+    Pair(Rc<Pair>),
     Keyword(Keyword),
     Closure(Closure),
-    TailCall(TailCall),
+    /// 由 `case-lambda` 创建的过程，按参数数量选择第一个匹配的分支
+    CaseLambda(Rc<[Closure]>),
+    /// 由字节码编译器编译的过程
+    Procedure(Procedure),
+    /// 宏，转换器是接收未求值参数并返回新形式的闭包
+    Macro(Closure),
+    /// 卫生宏
+    Syntax(SyntaxRules),
     InternalFunction(InternalFunction),
+    /// 需要操作续延栈的内部过程，例如 `call/cc`
+    Control(Control),
+    /// 被调用时以参数作为捕获处的返回值
+    Continuation(Rc<Continuation>),
+    /// 错误对象
+    Condition(Rc<Condition>),
 }
 
 impl TryFrom<Token> for Value {
-    type Error = ParseError;
+    type Error = ParseErrorKind;
 
     /// 尝试将 [`Token`] 转换为 [`Value`]。
     ///
@@ -46,7 +64,7 @@ impl TryFrom<Token> for Value {
     /// ```rust
     /// # use lemon_lisp::model::{Token, Value};
     /// #
-    /// let symbol_token = Token::Symbol("#t".to_string());
+    /// let symbol_token = Token::Symbol("#t".into());
     ///
     /// assert_eq!(
     ///     Ok(Value::Bool(true)),
@@ -56,50 +74,56 @@ impl TryFrom<Token> for Value {
     ///
     /// # 错误
     ///
-    /// 当遇到错误语法或不可转换的 [`Token`] 时，会返回 [`ParseError`] 。
+    /// 当遇到错误语法或不可转换的 [`Token`] 时，会返回 [`ParseErrorKind`] ，
+    /// 由语法分析器补上出错位置。
     ///
     /// ```rust
-    /// # use lemon_lisp::model::{Token, Value, ParseError};
+    /// # use lemon_lisp::model::{Token, Value, ParseErrorKind};
     /// #
     /// let invalid_token = Token::RParen;
     ///
     /// assert_eq!(
-    ///     Err(ParseError::NonConvertibleToken(Token::RParen)),
+    ///     Err(ParseErrorKind::NonConvertibleToken(Token::RParen)),
     ///     Value::try_from(invalid_token)
     /// );
     /// ```
     fn try_from(token: Token) -> Result<Self, Self::Error> {
         match token {
-            Token::LParen | Token::RParen | Token::Quote => {
-                Err(ParseError::NonConvertibleToken(token))
-            }
+            Token::LParen
+            | Token::RParen
+            | Token::Quote
+            | Token::Quasiquote
+            | Token::Unquote
+            | Token::UnquoteSplicing
+            | Token::Dot => Err(ParseErrorKind::NonConvertibleToken(token)),
 
             Token::Integer(i) => Ok(i.into()),
+            Token::Rational(r) => Ok(r.into()),
             Token::Float(f) => Ok(f.into()),
-            Token::String(s) => Ok(Value::String(s)),
+            Token::Complex(c) => Ok(c.into()),
+            Token::String(s) => Ok(Value::String(s.into())),
 
             Token::Symbol(symbol) => match symbol.as_str() {
                 "#t" => Ok(Value::Bool(true)),
                 "#f" => Ok(Value::Bool(false)),
+                // 形参列表中的 `#!optional` 等标记与关键字参数的名称 `#:name`
+                s if s.starts_with("#!") || s.starts_with("#:") => Ok(Value::Symbol(symbol.into())),
                 s if s.starts_with('#') => {
                     let radix = match s.chars().nth(1) {
                         Some('b') => 2,
                         Some('o') => 8,
                         Some('d') => 10,
                         Some('x') => 16,
-                        _ => return Err(ParseError::InvalidSyntax(Token::Symbol(symbol))),
+                        _ => return Err(ParseErrorKind::InvalidSyntax(Token::Symbol(symbol))),
                     };
                     let digits = &s[2..];
                     let value = Integer::parse_radix(digits, radix)
-                        .map_err(|_| ParseError::InvalidDigit(digits.into()))?
+                        .map_err(|_| ParseErrorKind::InvalidDigit(digits.into()))?
                         .complete();
 
                     Ok(value.into())
                 }
-                "define" => Ok(Value::Keyword(Keyword::Define)),
-                "lambda" => Ok(Value::Keyword(Keyword::Lambda)),
-                "if" => Ok(Value::Keyword(Keyword::If)),
-                _ => Ok(Value::Symbol(symbol)),
+                _ => Ok(Value::Symbol(symbol.into())),
             },
         }
     }
@@ -107,7 +131,13 @@ impl TryFrom<Token> for Value {
 
 impl From<Integer> for Value {
     fn from(value: Integer) -> Self {
-        Value::Numeric(Numeric::Integer(value))
+        Value::Numeric(value.into())
+    }
+}
+
+impl From<Rational> for Value {
+    fn from(value: Rational) -> Self {
+        Value::Numeric(value.into())
     }
 }
 
@@ -117,6 +147,12 @@ impl From<Float> for Value {
     }
 }
 
+impl From<Complex> for Value {
+    fn from(value: Complex) -> Self {
+        Value::Numeric(value.into())
+    }
+}
+
 impl From<Numeric> for Value {
     fn from(value: Numeric) -> Self {
         Value::Numeric(value)
@@ -129,9 +165,45 @@ impl From<bool> for Value {
     }
 }
 
-impl From<TailCall> for Value {
-    fn from(value: TailCall) -> Self {
-        Value::TailCall(value)
+impl From<Symbol> for Value {
+    fn from(value: Symbol) -> Self {
+        Value::Symbol(value)
+    }
+}
+
+impl From<List> for Value {
+    fn from(value: List) -> Self {
+        Value::List(value)
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Void, Value::Void) => true,
+            (Value::Numeric(lhs), Value::Numeric(rhs)) => lhs == rhs,
+            (Value::Bool(lhs), Value::Bool(rhs)) => lhs == rhs,
+            (Value::Symbol(lhs), Value::Symbol(rhs)) => lhs == rhs,
+            (Value::String(lhs), Value::String(rhs)) => lhs == rhs,
+            (Value::List(lhs), Value::List(rhs)) => lhs == rhs,
+            (Value::Pair(lhs), Value::Pair(rhs)) => lhs == rhs,
+            // 以空列表结尾的序对链与列表相等
+            (Value::Pair(pair), Value::List(list)) | (Value::List(list), Value::Pair(pair)) => {
+                let (items, tail) = pair.flatten();
+                matches!(tail, Value::List(tail) if tail.is_empty()) && items == **list
+            }
+            (Value::Keyword(lhs), Value::Keyword(rhs)) => lhs == rhs,
+            (Value::Closure(lhs), Value::Closure(rhs)) => lhs == rhs,
+            (Value::CaseLambda(lhs), Value::CaseLambda(rhs)) => lhs == rhs,
+            (Value::Procedure(lhs), Value::Procedure(rhs)) => lhs == rhs,
+            (Value::Macro(lhs), Value::Macro(rhs)) => lhs == rhs,
+            (Value::Syntax(lhs), Value::Syntax(rhs)) => lhs == rhs,
+            (Value::InternalFunction(lhs), Value::InternalFunction(rhs)) => lhs == rhs,
+            (Value::Control(lhs), Value::Control(rhs)) => lhs == rhs,
+            (Value::Continuation(lhs), Value::Continuation(rhs)) => Rc::ptr_eq(lhs, rhs),
+            (Value::Condition(lhs), Value::Condition(rhs)) => lhs == rhs,
+            _ => false,
+        }
     }
 }
 
@@ -146,43 +218,91 @@ impl fmt::Display for Value {
             },
             Value::Symbol(symbol) => write!(f, "{}", symbol),
             Value::String(string) => write!(f, "\"{}\"", string),
-            Value::List(list) => {
-                write!(
+            // 引用类的形式以读取器的简写输出
+            Value::List(list) => match abbreviation(list) {
+                Some((prefix, value)) => write!(f, "{}{}", prefix, value),
+                None => write!(
                     f,
                     "({})",
                     list.iter()
                         .map(|x| x.to_string())
                         .collect::<Vec<String>>()
                         .join(" ")
-                )
+                ),
+            },
+            Value::Pair(pair) => {
+                let (items, tail) = pair.flatten();
+                // 以空列表结尾的序对链与列表相同，引用类的形式同样使用简写
+                if let (Some((prefix, value)), Value::List(end)) = (abbreviation(&items), &tail) {
+                    if end.is_empty() {
+                        return write!(f, "{}{}", prefix, value);
+                    }
+                }
+                let items = items
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                match tail {
+                    Value::List(tail) if tail.is_empty() => write!(f, "({})", items),
+                    tail => write!(f, "({} . {})", items, tail),
+                }
             }
-            Value::Quoted(value) => write!(f, "'{}", value),
-            Value::Keyword(keyword) => write!(f, "#<keyword:{}>", keyword),
+            Value::Keyword(keyword) => write!(f, "{}", keyword),
             Value::Closure(lambda) => match &lambda.name {
                 Some(name) => write!(f, "#<procedure:{}>", name),
                 None => write!(f, "#<procedure>"),
             },
-            Value::TailCall(tail_recursive_closure) => match &tail_recursive_closure.closure.name {
+            Value::CaseLambda(_) => write!(f, "#<procedure>"),
+            Value::Procedure(procedure) => match procedure.name() {
                 Some(name) => write!(f, "#<procedure:{}>", name),
                 None => write!(f, "#<procedure>"),
             },
+            Value::Macro(transformer) => match &transformer.name {
+                Some(name) => write!(f, "#<macro:{}>", name),
+                None => write!(f, "#<macro>"),
+            },
+            Value::Syntax(syntax) => match &syntax.name {
+                Some(name) => write!(f, "#<syntax:{}>", name),
+                None => write!(f, "#<syntax>"),
+            },
             Value::InternalFunction(internal_function) => {
                 write!(f, "#<procedure:{}>", internal_function.name)
             }
+            Value::Control(control) => write!(f, "#<procedure:{}>", control),
+            Value::Continuation(_) => write!(f, "#<continuation>"),
+            Value::Condition(condition) => write!(f, "{}", condition),
         }
     }
 }
 
+// 引用类的形式 `(quote datum)` 等对应的读取器简写及其中的数据
+fn abbreviation(items: &[Value]) -> Option<(&'static str, &Value)> {
+    let [head, value] = items else {
+        return None;
+    };
+    let prefix = match head {
+        Value::Keyword(Keyword::Quote) => "'",
+        Value::Keyword(Keyword::Quasiquote) => "`",
+        Value::Symbol(symbol) if symbol == "quote" => "'",
+        Value::Symbol(symbol) if symbol == "quasiquote" => "`",
+        Value::Symbol(symbol) if symbol == "unquote" => ",",
+        Value::Symbol(symbol) if symbol == "unquote-splicing" => ",@",
+        _ => return None,
+    };
+    Some((prefix, value))
+}
+
 macro_rules! try_as_type {
     ( $( $name:ident; $variant:pat => $result:expr; $ty:ty; $expected:expr ),* $(,)? ) => {
         $(
             pub fn $name(&self) -> Result<$ty, RuntimeError> {
                 match self {
                     $variant => $result,
-                    _ => Err(RuntimeError::TypeError {
+                    _ => Err(RuntimeErrorKind::TypeError {
                         expected: $expected,
                         founded: self.clone()
-                    }),
+                    }.into()),
                 }
             }
         )*
@@ -193,7 +313,124 @@ impl Value {
     try_as_type! {
         try_as_bool; Value::Bool(b) => Ok(*b); bool; "bool",
         try_as_numeric; Value::Numeric(n) => Ok(n.clone()); Numeric; "numeric",
-        try_as_symbol; Value::Symbol(s) => Ok(s); &String; "symbol",
-        try_as_list; Value::List(l) => Ok(l); &Vec<Value>; "list",
+        try_as_symbol; Value::Symbol(s) => Ok(s); &Symbol; "symbol",
+        try_as_list; Value::List(l) => Ok(l); &List; "list",
+        try_as_pair; Value::Pair(p) => Ok(p); &Rc<Pair>; "pair",
+    }
+
+    /// 检查值是否可以被调用
+    pub fn try_as_procedure(&self) -> Result<&Value, RuntimeError> {
+        match self {
+            Value::Closure(_)
+            | Value::CaseLambda(_)
+            | Value::Procedure(_)
+            | Value::InternalFunction(_)
+            | Value::Control(_)
+            | Value::Continuation(_) => Ok(self),
+            _ => Err(RuntimeErrorKind::TypeError {
+                expected: "procedure",
+                founded: self.clone(),
+            }
+            .into()),
+        }
+    }
+
+    /// 取出正规列表中的所有元素，列表可以是 [`List`] 或以空列表结尾的序对链
+    pub fn try_as_vec(&self) -> Result<Vec<Value>, RuntimeError> {
+        let type_error = || {
+            RuntimeError::from(RuntimeErrorKind::TypeError {
+                expected: "list",
+                founded: self.clone(),
+            })
+        };
+        match self {
+            Value::List(list) => Ok(list.to_vec()),
+            Value::Pair(pair) => match pair.flatten() {
+                (items, Value::List(tail)) if tail.is_empty() => Ok(items),
+                _ => Err(type_error()),
+            },
+            _ => Err(type_error()),
+        }
+    }
+
+    /// 将数据转换为可求值的形式，正规的序对链会被转换为列表
+    ///
+    /// 宏展开的结果可能由 `cons`、`list` 等构造，求值器只接受列表形式的代码。
+    pub fn to_form(&self) -> Value {
+        match self {
+            Value::List(list) => {
+                let form = List::new(list.iter().map(Value::to_form).collect());
+                Value::List(match list.span() {
+                    Some(span) => form.with_span(span),
+                    None => form,
+                })
+            }
+            Value::Pair(pair) => match pair.flatten() {
+                (items, Value::List(tail)) if tail.is_empty() => {
+                    Value::List(items.iter().map(Value::to_form).collect::<Vec<_>>().into())
+                }
+                _ => self.clone(),
+            },
+            value => value.clone(),
+        }
+    }
+
+    /// 去掉宏展开时的重命名，得到引用的数据
+    pub fn to_datum(&self) -> Value {
+        match self {
+            Value::Symbol(symbol) if symbol.renamed().is_some() => {
+                Value::Symbol(symbol.original().clone())
+            }
+            Value::List(list) => {
+                let datum = List::new(list.iter().map(Value::to_datum).collect());
+                Value::List(match list.span() {
+                    Some(span) => datum.with_span(span),
+                    None => datum,
+                })
+            }
+            Value::Pair(pair) => {
+                let (items, tail) = pair.flatten();
+                items.iter().rev().fold(tail.to_datum(), |cdr, car| {
+                    Value::Pair(Rc::new(Pair::new(car.to_datum(), cdr)))
+                })
+            }
+            value => value.clone(),
+        }
+    }
+
+    /// 记录语法分析得到的位置，只有符号与列表会保存位置
+    pub fn with_span(self, span: Span) -> Self {
+        match self {
+            Value::Symbol(symbol) => Value::Symbol(symbol.with_span(span)),
+            Value::List(list) => Value::List(list.with_span(span)),
+            value => value,
+        }
+    }
+
+    /// 访问值中持有的堆对象，供垃圾回收器追踪
+    pub fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            Value::Symbol(symbol) => symbol.trace(tracer),
+            Value::List(list) => list.trace(tracer),
+            Value::Pair(pair) => tracer.visit(pair),
+            Value::Closure(closure) | Value::Macro(closure) => closure.trace(tracer),
+            Value::CaseLambda(clauses) => clauses.iter().for_each(|closure| closure.trace(tracer)),
+            Value::Procedure(procedure) => procedure.trace(tracer),
+            // 规则由所有副本共享，不能逐个副本追踪其中的引用，只追踪各自持有的环境
+            Value::Syntax(syntax) => tracer.visit(&syntax.environment),
+            Value::Continuation(continuation) => tracer.visit(continuation),
+            Value::Condition(condition) => condition.trace(tracer),
+            _ => {}
+        }
+    }
+
+    /// 值在源码中的位置
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Value::Symbol(symbol) => symbol.span(),
+            Value::List(list) => list.span(),
+            Value::Pair(pair) => pair.span(),
+            _ => None,
+        }
     }
 }
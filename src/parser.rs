@@ -1,14 +1,16 @@
 use crate::{
     lexer::LexResult,
-    model::{ParseError, Token, Value},
+    model::{List, Pair, ParseError, ParseErrorKind, Span, Spanned, Token, Value},
 };
-use std::iter::Peekable;
+use std::{iter::Peekable, rc::Rc};
 
 pub struct Parser<I>
 where
     I: Iterator<Item = LexResult>,
 {
     lexer: Peekable<I>,
+    // 最近读取的标记位置，用于定位输入意外结束的错误
+    last_span: Span,
 }
 
 impl<I> Parser<I>
@@ -18,67 +20,152 @@ where
     pub fn new(lexer: I) -> Self {
         Self {
             lexer: lexer.peekable(),
+            last_span: Span::default(),
         }
     }
 
-    fn eat(&mut self, expected: &Token) -> Result<(), ParseError> {
-        if !self.peek_token_is(expected)? {
-            let Some(Ok(current_token)) = self.lexer.next() else {
+    fn next_token(&mut self) -> Option<Result<Spanned<Token>, ParseError>> {
+        let next = self.lexer.next()?;
+        if let Ok(token) = &next {
+            self.last_span = token.span;
+        }
+        Some(next.map_err(Into::into))
+    }
+
+    // 输入末尾处的空位置
+    fn eof_span(&self) -> Span {
+        Span {
+            start: self.last_span.end,
+            ..self.last_span
+        }
+    }
+
+    fn eat(&mut self, expected: &Token, start: Span) -> Result<Span, ParseError> {
+        if !self.peek_token_is(expected, start)? {
+            let Some(Ok(current_token)) = self.next_token() else {
                 unreachable!()
             };
-            Err(ParseError::UnexpectedToken {
-                expected: expected.clone(),
-                found: current_token,
-            })
+            Err(ParseError::new(
+                ParseErrorKind::UnexpectedToken {
+                    expected: expected.clone(),
+                    found: current_token.node,
+                },
+                current_token.span,
+            ))
         } else {
-            self.lexer.next();
-            Ok(())
+            let Some(Ok(token)) = self.next_token() else {
+                unreachable!()
+            };
+            Ok(token.span)
         }
     }
 
-    fn peek_token_is(&mut self, expected: &Token) -> Result<bool, ParseError> {
-        let next = self
-            .lexer
-            .peek()
-            .ok_or(ParseError::MissingToken(expected.clone()))?;
+    // 输入意外结束时，错误位置从 `start` 处一直延伸到输入末尾
+    fn peek_token_is(&mut self, expected: &Token, start: Span) -> Result<bool, ParseError> {
+        let eof_span = start.to(self.eof_span());
+        let next = self.lexer.peek().ok_or(ParseError::new(
+            ParseErrorKind::MissingToken(expected.clone()),
+            eof_span,
+        ))?;
 
         match next {
-            Ok(token) => Ok(token == expected),
-            Err(err) => Err(ParseError::LexicalError(err.clone())),
+            Ok(token) => Ok(&token.node == expected),
+            Err(err) => Err(err.clone().into()),
         }
     }
 
-    fn parse_quoted(&mut self) -> Result<Value, ParseError> {
-        let value = self.parse_atom()?.ok_or(ParseError::UnexpectedEOF)?;
-        Ok(Value::Quoted(Box::new(value)))
+    // 将 `'x` `` `x`` `,x` `,@x` 展开为 `(quote x)` `(quasiquote x)` 等形式
+    fn parse_prefixed(&mut self, head: Value, start: Span) -> Result<Value, ParseError> {
+        let value = self.parse_atom()?.ok_or(ParseError::new(
+            ParseErrorKind::UnexpectedEOF,
+            self.eof_span(),
+        ))?;
+        let list = List::new(vec![head.with_span(start), value]);
+        Ok(Value::List(list.with_span(start.to(self.last_span))))
     }
 
     fn parse_list(&mut self) -> Result<Value, ParseError> {
-        self.eat(&Token::LParen)?;
+        let start = match self.lexer.peek() {
+            Some(Ok(token)) => token.span,
+            _ => self.eof_span(),
+        };
+        self.eat(&Token::LParen, start)?;
         let mut list: Vec<Value> = vec![];
 
-        while !self.peek_token_is(&Token::RParen)? {
+        while !self.peek_token_is(&Token::RParen, start)? {
+            if self.peek_token_is(&Token::Dot, start)? {
+                return self.parse_dotted(list, start);
+            }
             if let Some(value) = self.parse_atom()? {
                 list.push(value);
             }
         }
 
-        self.eat(&Token::RParen)?;
-        Ok(Value::List(list))
+        let end = self.eat(&Token::RParen, start)?;
+        Ok(Value::List(List::new(list).with_span(start.to(end))))
+    }
+
+    // 点号之前至少有一个元素，之后恰好有一个元素，由此构造序对链
+    fn parse_dotted(&mut self, list: Vec<Value>, start: Span) -> Result<Value, ParseError> {
+        let dot = self.eat(&Token::Dot, start)?;
+        if list.is_empty() {
+            return Err(ParseError::new(
+                ParseErrorKind::InvalidSyntax(Token::Dot),
+                dot,
+            ));
+        }
+        if self.peek_token_is(&Token::RParen, start)? {
+            return Err(ParseError::new(
+                ParseErrorKind::InvalidSyntax(Token::Dot),
+                dot,
+            ));
+        }
+        let tail = self.parse_atom()?.ok_or(ParseError::new(
+            ParseErrorKind::UnexpectedEOF,
+            self.eof_span(),
+        ))?;
+        let end = self.eat(&Token::RParen, start)?;
+
+        let mut items = list.into_iter().rev();
+        let last = items.next().expect("dotted list has at least one element");
+        let pair = Pair::new(last, tail);
+        let pair = items.fold(pair, |cdr, car| Pair::new(car, Value::Pair(Rc::new(cdr))));
+        Ok(Value::Pair(Rc::new(pair.with_span(start.to(end)))))
     }
 
     fn parse_atom(&mut self) -> Result<Option<Value>, ParseError> {
         if let Some(next) = self.lexer.peek() {
-            match next.clone()? {
+            let Spanned { node, span } = next.clone()?;
+            match node {
                 Token::LParen => Ok(Some(self.parse_list()?)),
-                Token::RParen => Err(ParseError::InvalidSyntax(Token::RParen)),
+                Token::RParen | Token::Dot => {
+                    Err(ParseError::new(ParseErrorKind::InvalidSyntax(node), span))
+                }
                 Token::Quote => {
-                    self.lexer.next();
-                    Ok(Some(self.parse_quoted()?))
+                    self.next_token();
+                    let head = Value::Symbol("quote".into());
+                    Ok(Some(self.parse_prefixed(head, span)?))
+                }
+                Token::Quasiquote => {
+                    self.next_token();
+                    let head = Value::Symbol("quasiquote".into());
+                    Ok(Some(self.parse_prefixed(head, span)?))
+                }
+                Token::Unquote => {
+                    self.next_token();
+                    let head = Value::Symbol("unquote".into());
+                    Ok(Some(self.parse_prefixed(head, span)?))
+                }
+                Token::UnquoteSplicing => {
+                    self.next_token();
+                    let head = Value::Symbol("unquote-splicing".into());
+                    Ok(Some(self.parse_prefixed(head, span)?))
                 }
                 token => {
-                    self.lexer.next();
-                    Ok(Some(token.try_into()?))
+                    self.next_token();
+                    let value =
+                        Value::try_from(token).map_err(|kind| ParseError::new(kind, span))?;
+                    Ok(Some(value.with_span(span)))
                 }
             }
         } else {
@@ -0,0 +1,256 @@
+//! 编译器与分析器共用的词法作用域
+//!
+//! 每个作用域与运行时的一层环境对应，作用域中的变量按位置保存在环境中。
+
+use std::{
+    cell::{OnceCell, RefCell},
+    rc::Rc,
+};
+
+use crate::model::{Environment, Keyword, Name, Symbol, Value};
+
+#[derive(Default)]
+pub(crate) struct Scope {
+    // 环境中每个位置上的变量名，同名的变量以最后一个为准
+    variables: RefCell<Vec<Name>>,
+    // 局部定义的宏
+    macros: RefCell<Vec<(Name, Value)>>,
+    parent: Option<Rc<Scope>>,
+    // 在该作用域中定义的卫生宏所记录的环境，展开结果中没有被绑定的标识符由此回到该作用域中查找
+    placeholder: OnceCell<Rc<Environment>>,
+}
+
+/// 标识符在编译时解析得到的绑定
+pub(crate) enum Binding {
+    Local { depth: usize, index: usize },
+    Global(Symbol),
+    Macro(Value),
+    Keyword(Keyword),
+}
+
+impl Scope {
+    pub fn new(parent: Option<&Rc<Scope>>) -> Rc<Self> {
+        Rc::new(Self {
+            parent: parent.cloned(),
+            ..Self::default()
+        })
+    }
+
+    /// 为变量分配新的位置
+    pub fn bind(&self, name: &Name) -> usize {
+        let mut variables = self.variables.borrow_mut();
+        variables.push(name.clone());
+        variables.len() - 1
+    }
+
+    /// 内部定义与之前的定义使用同一个位置
+    pub fn define(&self, name: &Name) -> usize {
+        self.variable(name).unwrap_or_else(|| self.bind(name))
+    }
+
+    fn variable(&self, name: &Name) -> Option<usize> {
+        self.variables
+            .borrow()
+            .iter()
+            .rposition(|variable| variable == name)
+    }
+
+    pub fn size(&self) -> usize {
+        self.variables.borrow().len()
+    }
+
+    pub fn define_macro(&self, name: &Name, transformer: Value) {
+        self.macros.borrow_mut().push((name.clone(), transformer));
+    }
+
+    fn lookup_macro(&self, name: &Name) -> Option<Value> {
+        self.macros
+            .borrow()
+            .iter()
+            .rev()
+            .find(|(macro_name, _)| macro_name == name)
+            .map(|(_, transformer)| transformer.clone())
+    }
+}
+
+/// 由内向外查找标识符的绑定，不在任何作用域中的标识符在全局环境 `global` 中查找宏与关键字
+pub(crate) fn resolve(
+    global: &Rc<Environment>,
+    symbol: &Symbol,
+    scope: Option<&Rc<Scope>>,
+) -> Binding {
+    let mut current = scope;
+    let mut depth = 0;
+    while let Some(scope) = current {
+        if let Some(transformer) = scope.lookup_macro(symbol) {
+            return Binding::Macro(transformer);
+        }
+        if let Some(index) = scope.variable(symbol) {
+            return Binding::Local { depth, index };
+        }
+        current = scope.parent.as_ref();
+        depth += 1;
+    }
+
+    // 没有被展开结果绑定的重命名标识符，在定义宏的作用域中查找原本的标识符
+    if let Some(renamed) = symbol.renamed() {
+        if let Some(binding) =
+            resolve_placeholder(global, &renamed.symbol, &renamed.environment, scope)
+        {
+            return binding;
+        }
+    }
+
+    global_binding(symbol, global.lookup(symbol))
+}
+
+// 在定义宏的环境 `environment` 中查找标识符的绑定，
+// `environment` 不是某个作用域中的卫生宏所记录的环境时直接在其中查找
+fn resolve_in(
+    global: &Rc<Environment>,
+    symbol: &Symbol,
+    environment: &Rc<Environment>,
+    scope: Option<&Rc<Scope>>,
+) -> Binding {
+    resolve_placeholder(global, symbol, environment, scope)
+        .unwrap_or_else(|| global_binding(symbol, environment.lookup(symbol)))
+}
+
+// 在记录了环境 `environment` 的作用域中查找标识符，深度从当前作用域算起
+fn resolve_placeholder(
+    global: &Rc<Environment>,
+    symbol: &Symbol,
+    environment: &Rc<Environment>,
+    scope: Option<&Rc<Scope>>,
+) -> Option<Binding> {
+    let mut current = scope;
+    let mut depth = 0;
+    while let Some(scope) = current {
+        if scope
+            .placeholder
+            .get()
+            .is_some_and(|env| Rc::ptr_eq(env, environment))
+        {
+            return Some(match resolve(global, symbol, Some(scope)) {
+                Binding::Local {
+                    depth: inner,
+                    index,
+                } => Binding::Local {
+                    depth: depth + inner,
+                    index,
+                },
+                binding => binding,
+            });
+        }
+        current = scope.parent.as_ref();
+        depth += 1;
+    }
+    None
+}
+
+fn global_binding(symbol: &Symbol, value: Option<Value>) -> Binding {
+    match value {
+        Some(transformer @ (Value::Macro(_) | Value::Syntax(_))) => Binding::Macro(transformer),
+        Some(Value::Keyword(keyword)) => Binding::Keyword(keyword),
+        _ => Binding::Global(symbol.clone()),
+    }
+}
+
+/// 表达式开头的关键字，可以是生成的代码中直接使用的关键字，也可以是绑定为关键字的标识符
+pub(crate) fn keyword(
+    global: &Rc<Environment>,
+    value: &Value,
+    scope: Option<&Rc<Scope>>,
+) -> Option<Keyword> {
+    match value {
+        Value::Keyword(keyword) => Some(keyword.clone()),
+        Value::Symbol(symbol) => match resolve(global, symbol, scope) {
+            Binding::Keyword(keyword) => Some(keyword),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 判断 `value` 是否为辅助语法 `name`，例如 `cond` 中的 `else` 与 `=>`，
+/// 被局部变量或局部宏遮蔽的同名标识符不是辅助语法
+pub(crate) fn is_auxiliary(
+    global: &Rc<Environment>,
+    value: &Value,
+    name: &str,
+    scope: Option<&Rc<Scope>>,
+) -> bool {
+    matches!(value, Value::Symbol(symbol)
+        if *symbol == name && matches!(resolve(global, symbol, scope), Binding::Global(_)))
+}
+
+/// 判断宏定义处的字面量 `literal` 与使用处的标识符 `input` 是否引用同一个绑定（`free-identifier=?`）
+///
+/// 字面量在定义宏的环境 `environment` 中解析，输入在使用处的作用域 `scope` 中解析。
+pub(crate) fn is_same_binding(
+    global: &Rc<Environment>,
+    environment: &Rc<Environment>,
+    literal: &Symbol,
+    input: &Symbol,
+    scope: Option<&Rc<Scope>>,
+) -> bool {
+    match (
+        resolve_in(global, literal, environment, scope),
+        resolve(global, input, scope),
+    ) {
+        (
+            Binding::Local { depth, index },
+            Binding::Local {
+                depth: input_depth,
+                index: input_index,
+            },
+        ) => depth == input_depth && index == input_index,
+        (Binding::Global(literal), Binding::Global(input)) => {
+            literal.original().name() == input.original().name()
+        }
+        (Binding::Macro(literal), Binding::Macro(input)) => literal == input,
+        (Binding::Keyword(literal), Binding::Keyword(input)) => literal == input,
+        _ => false,
+    }
+}
+
+/// 在作用域中定义的卫生宏所使用的环境
+pub(crate) fn environment(global: &Rc<Environment>, scope: Option<&Rc<Scope>>) -> Rc<Environment> {
+    match scope {
+        Some(scope) => Rc::clone(
+            scope
+                .placeholder
+                .get_or_init(|| Environment::extend(global)),
+        ),
+        None => Rc::clone(global),
+    }
+}
+
+/// 过程体中的内部定义在进入过程体时就分配位置，之前定义的过程因此可以引用之后的定义
+pub(crate) fn scan_defines(global: &Rc<Environment>, body: &[Value], scope: &Rc<Scope>) {
+    for form in body {
+        let Value::List(list) = form else {
+            continue;
+        };
+        let Some((head, rest)) = list.split_first() else {
+            continue;
+        };
+        match (keyword(global, head, Some(scope)), rest) {
+            (Some(Keyword::Define), [Value::Symbol(name), ..]) => {
+                scope.define(name);
+            }
+            (Some(Keyword::Define), [Value::List(signature), ..]) => {
+                if let Some(Value::Symbol(name)) = signature.first() {
+                    scope.define(name);
+                }
+            }
+            (Some(Keyword::Define), [Value::Pair(signature), ..]) => {
+                if let Value::Symbol(name) = signature.car() {
+                    scope.define(&name);
+                }
+            }
+            (Some(Keyword::Begin), body) => scan_defines(global, body, scope),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,826 @@
+//! 执行编译得到的字节码的栈式虚拟机
+
+use std::{collections::VecDeque, mem, rc::Rc, sync::atomic::Ordering};
+
+use crate::{
+    compiler::Compiler,
+    evaluator::{Evaluator, MAX_TAIL_FRAMES, NEXT_ESCAPE},
+    internal::math,
+    model::{
+        Activation, Closure, Code, Continuation, Control, Environment, Frame, Invocation, Keyword,
+        List, Numeric, Op, Pair, Procedure, Resume, RuntimeError, RuntimeErrorKind, Site, Span,
+        Symbol, Value, Winder,
+    },
+    scope::keyword,
+    winding::{ControlStack, Role},
+};
+
+#[derive(Default)]
+pub struct Vm;
+
+type EvalResult = Result<Value, RuntimeError>;
+
+// 执行一条指令或恢复一层续延之后的去向
+enum Flow {
+    // 得到的值交给续延栈顶的计算
+    Value(Value),
+    // 继续执行当前代码的下一条指令
+    Next,
+}
+
+// 虚拟机的状态
+//
+// 与求值器一样，调用返回后要继续的计算保存在显式的续延栈中，
+// 捕获续延只需复制续延栈与值栈。
+struct Machine {
+    code: Rc<Code>,
+    pc: usize,
+    env: Rc<Environment>,
+    // 当前过程在值栈中的起始位置，尾调用时丢弃其上的值
+    base: usize,
+    stack: Vec<Value>,
+    frames: Vec<Activation>,
+    // 当前这一层通过尾调用进入的过程
+    backtrace: VecDeque<Invocation>,
+    // 当前所在的动态范围
+    winders: Option<Rc<Winder>>,
+}
+
+impl Vm {
+    /// 编译并执行表达式
+    ///
+    /// 顶层的 `begin` 中的表达式在前一个表达式执行之后才编译，
+    /// 之前的表达式定义的宏因此可以在之后的表达式中使用。
+    /// 整个 `begin` 在同一个虚拟机中运行，其中捕获的续延也包含之后的表达式。
+    pub fn eval_value(&self, value: &Value, env: &Rc<Environment>) -> EvalResult {
+        let mut machine = Machine::new(Rc::default(), Rc::clone(env));
+        let flow = machine.toplevel(value.clone(), env);
+        machine.run(flow)
+    }
+
+    /// 在独立的续延栈中调用过程，供求值器调用编译后的过程
+    pub(crate) fn apply(procedure: &Procedure, args: &[Value]) -> EvalResult {
+        let mut machine = Machine::new(Rc::default(), Rc::clone(&procedure.environment));
+        let flow = machine
+            .enter_procedure(procedure, args)
+            .map(|()| Flow::Next);
+        machine.run(flow)
+    }
+
+    /// 以未求值的参数调用宏的转换器
+    pub(crate) fn expand_macro(transformer: &Closure, args: &[Value]) -> EvalResult {
+        let code = Compiler::new(&transformer.environment).compile_closure(transformer);
+        let procedure = Procedure {
+            code,
+            environment: Rc::clone(&transformer.environment),
+        };
+        Ok(Self::apply(&procedure, args)?.to_form())
+    }
+}
+
+impl Machine {
+    fn new(code: Rc<Code>, env: Rc<Environment>) -> Self {
+        Self {
+            code,
+            pc: 0,
+            env,
+            base: 0,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            backtrace: VecDeque::new(),
+            winders: None,
+        }
+    }
+
+    // 展开顶层表达式开头的宏调用并编译执行，顶层的 `begin` 之后的表达式留待前面的表达式执行之后再编译
+    fn toplevel(&mut self, mut expr: Value, env: &Rc<Environment>) -> Result<Flow, RuntimeError> {
+        let compiler = Compiler::new(env);
+        while let Value::List(list) = &expr {
+            match list.first() {
+                Some(head) if keyword(env, head, None) == Some(Keyword::Begin) => {
+                    return self.toplevel_forms(&list.tail(), env);
+                }
+                Some(Value::Symbol(_)) => match compiler.macroexpand_1(list) {
+                    Ok(Some(expansion)) => expr = expansion,
+                    Ok(None) => break,
+                    Err(err) => return Err(err.or_span(list.span())),
+                },
+                _ => break,
+            }
+        }
+
+        self.code = compiler.compile(&expr);
+        self.pc = 0;
+        self.env = Rc::clone(env);
+        self.base = self.stack.len();
+        Ok(Flow::Next)
+    }
+
+    // 执行顶层 `begin` 中的第一个表达式，其余的表达式保存在续延栈中
+    fn toplevel_forms(
+        &mut self,
+        forms: &List,
+        env: &Rc<Environment>,
+    ) -> Result<Flow, RuntimeError> {
+        let Some(first) = forms.first().cloned() else {
+            return Ok(Flow::Value(Value::Void));
+        };
+        if forms.len() > 1 {
+            let resume = Resume::Toplevel {
+                forms: forms.tail(),
+                env: Rc::clone(env),
+            };
+            self.push(resume, None);
+        }
+        self.toplevel(first, env)
+    }
+
+    // 保存之后要继续的计算，`span` 为继续计算出错时报告的位置
+    fn push(&mut self, resume: Resume, span: Option<Span>) {
+        self.frames.push(Activation {
+            resume,
+            span,
+            backtrace: mem::take(&mut self.backtrace),
+        });
+    }
+
+    fn record(&mut self, invocation: Invocation) {
+        if self.backtrace.len() == MAX_TAIL_FRAMES {
+            self.backtrace.pop_front();
+        }
+        self.backtrace.push_back(invocation);
+    }
+
+    // 错误向外传递时依次补上每一层的位置与调用帧
+    fn unwind(mut self, err: RuntimeError) -> RuntimeError {
+        let mut err = self
+            .backtrace
+            .iter()
+            .rev()
+            .fold(err, |err, invocation| err.with_frame(invocation.frame()));
+        while let Some(activation) = self.frames.pop() {
+            err = activation
+                .backtrace
+                .iter()
+                .rev()
+                .fold(err.or_span(activation.span), |err, invocation| {
+                    err.with_frame(invocation.frame())
+                });
+        }
+        err
+    }
+
+    // 运行直到续延栈为空
+    fn run(mut self, mut next: Result<Flow, RuntimeError>) -> EvalResult {
+        loop {
+            let flow = match next {
+                Ok(flow) => flow,
+                // 错误先交给当前的处理器，没有处理器时向外传递
+                Err(err) => match self.handle(err) {
+                    Ok(flow) => flow,
+                    Err(err) => return Err(self.unwind(err)),
+                },
+            };
+            next = match flow {
+                Flow::Value(value) => {
+                    let Some(activation) = self.frames.pop() else {
+                        return Ok(value);
+                    };
+                    self.backtrace = activation.backtrace;
+                    let span = activation.span;
+                    self.resume(activation.resume, value)
+                        .map_err(|err| err.or_span(span))
+                }
+                Flow::Next => {
+                    let op = self.code.ops[self.pc];
+                    let span = self.code.spans[self.pc];
+                    self.pc += 1;
+                    self.execute(op).map_err(|err| err.or_span(span))
+                }
+            };
+        }
+    }
+
+    fn symbol(&self, index: usize) -> &Symbol {
+        match &self.code.constants[index] {
+            Value::Symbol(symbol) => symbol,
+            _ => unreachable!(),
+        }
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("value stack underflow")
+    }
+
+    fn top(&self) -> &Value {
+        self.stack.last().expect("value stack underflow")
+    }
+
+    fn site(&self, index: usize) -> Site {
+        Site {
+            code: Rc::clone(&self.code),
+            index,
+        }
+    }
+
+    fn execute(&mut self, op: Op) -> Result<Flow, RuntimeError> {
+        match op {
+            Op::Constant(index) => self.stack.push(self.code.constants[index].clone()),
+            Op::Void => self.stack.push(Value::Void),
+            op @ (Op::Local { .. }
+            | Op::Global(_)
+            | Op::SetLocal { .. }
+            | Op::DefineLocal(_)
+            | Op::SetGlobal(_)
+            | Op::DefineGlobal(_)
+            | Op::DefineSyntax(_)
+            | Op::DefineMacro(_)) => self.access(op)?,
+            Op::Pop => {
+                self.pop();
+            }
+            Op::Dup => self.stack.push(self.top().clone()),
+            Op::Swap => {
+                let len = self.stack.len();
+                self.stack.swap(len - 1, len - 2);
+            }
+            op @ (Op::Jump(_)
+            | Op::JumpIfFalse(_)
+            | Op::JumpIfTrue(_)
+            | Op::And(_)
+            | Op::Or(_)
+            | Op::Default { .. }) => self.branch(op),
+            Op::Memv(index) => {
+                let Value::List(data) = &self.code.constants[index] else {
+                    unreachable!()
+                };
+                let matched = data.contains(self.top());
+                self.stack.push(Value::Bool(matched));
+            }
+            Op::Lambda(index) => {
+                let procedure = Procedure {
+                    code: Rc::clone(&self.code.lambdas[index]),
+                    environment: Rc::clone(&self.env),
+                };
+                self.stack.push(Value::Procedure(procedure));
+            }
+            Op::Loop(index) => {
+                let loop_env = Environment::with_slots(&self.env, 1);
+                let procedure = Value::Procedure(Procedure {
+                    code: Rc::clone(&self.code.lambdas[index]),
+                    environment: Rc::clone(&loop_env),
+                });
+                loop_env.set_slot(0, 0, procedure.clone());
+                self.stack.push(procedure);
+            }
+            Op::PushEnv(size) => self.env = Environment::with_slots(&self.env, size),
+            Op::PopEnv => {
+                let parent = self.env.parent().expect("environment stack underflow");
+                self.env = Rc::clone(parent);
+            }
+            Op::Callable => {
+                if !matches!(
+                    self.top(),
+                    Value::Closure(_)
+                        | Value::CaseLambda(_)
+                        | Value::Procedure(_)
+                        | Value::InternalFunction(_)
+                        | Value::Control(_)
+                        | Value::Continuation(_)
+                ) {
+                    return Err(RuntimeErrorKind::NonCallableValue(self.pop()).into());
+                }
+            }
+            Op::Call { argc, site } => return self.call(argc, site, false),
+            Op::TailCall { argc, site } => return self.call(argc, site, true),
+            Op::Return => return Ok(Flow::Value(self.pop())),
+            Op::PushGuard { handler, site } => {
+                let site = self.site(site);
+                let span = site.span();
+                let resume = Resume::Guard {
+                    code: Rc::clone(&self.code),
+                    handler,
+                    env: Rc::clone(&self.env),
+                    base: self.base,
+                    height: self.stack.len(),
+                    winders: self.winders.clone(),
+                    site,
+                };
+                self.push(resume, span);
+            }
+            Op::PopGuard => {
+                let guard = self.frames.pop().expect("guard is not installed");
+                self.backtrace = guard.backtrace;
+            }
+            Op::Fail(index) => return Err(self.code.errors[index].clone()),
+        }
+        Ok(Flow::Next)
+    }
+
+    // 按条件跳转
+    fn branch(&mut self, op: Op) {
+        match op {
+            Op::Jump(target) => self.pc = target,
+            Op::JumpIfFalse(target) => {
+                if !Evaluator::is_true(&self.pop()) {
+                    self.pc = target;
+                }
+            }
+            Op::JumpIfTrue(target) => {
+                if Evaluator::is_true(&self.pop()) {
+                    self.pc = target;
+                }
+            }
+            Op::And(target) => {
+                if Evaluator::is_true(self.top()) {
+                    self.pop();
+                } else {
+                    self.pc = target;
+                }
+            }
+            Op::Or(target) => {
+                if Evaluator::is_true(self.top()) {
+                    self.pc = target;
+                } else {
+                    self.pop();
+                }
+            }
+            Op::Default { index, target } => {
+                if self.env.slot(0, index).is_some() {
+                    self.pc = target;
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // 读取、修改或定义变量
+    fn access(&mut self, op: Op) -> Result<(), RuntimeError> {
+        match op {
+            Op::Local { depth, index, name } => {
+                let value = self.env.slot(depth, index).ok_or_else(|| {
+                    let symbol = self.symbol(name);
+                    RuntimeError::new(
+                        RuntimeErrorKind::UndefinedVariable(symbol.to_string()),
+                        symbol.span(),
+                    )
+                })?;
+                self.stack.push(value);
+            }
+            Op::Global(index) => {
+                let symbol = self.symbol(index);
+                let value = self.env.lookup(symbol).ok_or_else(|| {
+                    RuntimeError::new(
+                        RuntimeErrorKind::UndefinedVariable(symbol.to_string()),
+                        symbol.span(),
+                    )
+                })?;
+                self.stack.push(value);
+            }
+            Op::SetLocal { depth, index } => {
+                let value = self.pop();
+                self.env.set_slot(depth, index, value);
+            }
+            Op::DefineLocal(index) => {
+                let value = self.pop();
+                self.env.set_slot(0, index, value);
+            }
+            Op::SetGlobal(index) => {
+                let value = self.pop();
+                self.env.assign(self.symbol(index), value)?;
+            }
+            Op::DefineGlobal(index) => {
+                let value = self.pop();
+                self.env.set(self.symbol(index), value);
+            }
+            Op::DefineSyntax(index) => {
+                let value = self.pop();
+                let name = self.symbol(index);
+                self.env
+                    .set(name, Evaluator::name_transformer(name, value)?);
+            }
+            Op::DefineMacro(index) => {
+                let value = self.pop();
+                let name = self.symbol(index);
+                let span = self.code.spans[self.pc - 1];
+                self.env
+                    .set(name, Evaluator::macro_transformer(name, value, span)?);
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    // 调用栈顶 `count` 个参数之下的过程，非尾调用先保存返回后继续执行的位置
+    fn call(&mut self, count: usize, site: usize, tail: bool) -> Result<Flow, RuntimeError> {
+        let args = self.stack.split_off(self.stack.len() - count);
+        let procedure = self.pop();
+        let site = self.site(site);
+        if tail {
+            self.stack.truncate(self.base);
+        } else if let Value::InternalFunction(internal_fn) = &procedure {
+            // 内部过程直接返回，不需要保存续延
+            let value = (internal_fn.function)(&args, &self.env).map_err(|err| {
+                err.with_frame(Frame::new(Some(internal_fn.name.to_string()), site.form()))
+            })?;
+            self.stack.push(value);
+            return Ok(Flow::Next);
+        } else {
+            let resume = Resume::Frame {
+                code: Rc::clone(&self.code),
+                pc: self.pc,
+                env: Rc::clone(&self.env),
+                base: self.base,
+                height: self.stack.len(),
+            };
+            self.push(resume, site.span());
+        }
+        self.apply(procedure, &args, site)
+    }
+
+    // 参数求值完毕后才进入被调用的过程，此后的错误会在调用栈中记录这一帧
+    fn apply(
+        &mut self,
+        procedure: Value,
+        args: &[Value],
+        site: Site,
+    ) -> Result<Flow, RuntimeError> {
+        match procedure {
+            Value::Procedure(procedure) => {
+                self.record(Invocation {
+                    procedure: procedure.code.name.clone(),
+                    site,
+                });
+                self.enter_procedure(&procedure, args)?;
+                Ok(Flow::Next)
+            }
+            // 由求值器创建的闭包在调用时编译
+            Value::Closure(closure) => {
+                let procedure = Procedure {
+                    code: Compiler::new(&closure.environment).compile_closure(&closure),
+                    environment: Rc::clone(&closure.environment),
+                };
+                self.apply(Value::Procedure(procedure), args, site)
+            }
+            Value::CaseLambda(clauses) => {
+                let Some(closure) = clauses
+                    .iter()
+                    .find(|closure| closure.params.arity().accepts(args.len()))
+                else {
+                    return Err(RuntimeError::from(RuntimeErrorKind::ArityMismatch {
+                        expected: clauses
+                            .iter()
+                            .map(|closure| closure.params.arity())
+                            .collect(),
+                        founded: args.len(),
+                    })
+                    .with_frame(Frame::new(None, site.form())));
+                };
+                self.apply(Value::Closure(closure.clone()), args, site)
+            }
+            Value::InternalFunction(internal_fn) => (internal_fn.function)(args, &self.env)
+                .map(Flow::Value)
+                .map_err(|err| {
+                    err.with_frame(Frame::new(Some(internal_fn.name.to_string()), site.form()))
+                }),
+            Value::Control(control) => {
+                self.record(Invocation {
+                    procedure: Some(Rc::from(control.to_string())),
+                    site: site.clone(),
+                });
+                self.apply_control(control, args, site)
+            }
+            Value::Continuation(continuation) => {
+                self.record(Invocation {
+                    procedure: None,
+                    site: site.clone(),
+                });
+                self.throw(&continuation, args, &site)
+            }
+            procedure => Err(RuntimeErrorKind::NonCallableValue(procedure).into()),
+        }
+    }
+
+    // 选择接受该数量参数的分支，绑定参数后从过程的第一条指令开始执行
+    fn enter_procedure(
+        &mut self,
+        procedure: &Procedure,
+        args: &[Value],
+    ) -> Result<(), RuntimeError> {
+        let mut code = &procedure.code;
+        // 没有分支的 `case-lambda` 不接受任何调用
+        if let Some(clauses) = &code.clauses {
+            code = clauses
+                .iter()
+                .find(|clause| clause.params.arity().accepts(args.len()))
+                .ok_or_else(|| RuntimeErrorKind::ArityMismatch {
+                    expected: clauses.iter().map(|clause| clause.params.arity()).collect(),
+                    founded: args.len(),
+                })?;
+        }
+
+        let params = &code.params;
+        let Some((positional_args, rest_args)) = params.split_args(args) else {
+            return Err(Evaluator::arity_error(params, code.span, args.len()));
+        };
+
+        // 没有提供的可选参数与关键字参数保持未绑定，由过程开头的指令求值默认值
+        let env = Environment::with_slots(&procedure.environment, code.size);
+        for (index, arg) in positional_args.iter().enumerate() {
+            env.set_slot(0, index, arg.clone());
+        }
+        let mut index = params.required.len() + params.optional.len();
+        if !params.keys.is_empty() {
+            let keyword_args = params.keyword_args(rest_args)?;
+            for (param, _) in &params.keys {
+                if let Some((_, arg)) = keyword_args.iter().find(|(name, _)| param == name) {
+                    env.set_slot(0, index, (*arg).clone());
+                }
+                index += 1;
+            }
+        }
+        if params.rest.is_some() {
+            let list = rest_args
+                .iter()
+                .rev()
+                .fold(Value::List(List::default()), |cdr, car| {
+                    Value::Pair(Pair::alloc(car.clone(), cdr, env.heap()))
+                });
+            env.set_slot(0, index, list);
+        }
+
+        self.code = Rc::clone(code);
+        self.pc = 0;
+        self.env = env;
+        self.base = self.stack.len();
+        Ok(())
+    }
+
+    fn apply_control(
+        &mut self,
+        control: Control,
+        args: &[Value],
+        site: Site,
+    ) -> Result<Flow, RuntimeError> {
+        match (control, args) {
+            // 接收者在尾部位置调用，续延就是 `call/cc` 返回之后要继续的计算
+            (Control::CallCc, [receiver]) => {
+                let continuation = Continuation::compiled(
+                    self.frames.clone(),
+                    self.stack.clone(),
+                    self.winders.clone(),
+                    self.env.heap(),
+                );
+                let args = [Value::Continuation(continuation)];
+                self.apply(receiver.clone(), &args, site)
+            }
+            // 逃逸续延不复制续延栈，只在栈中留下标记，跳转时丢弃标记之上的部分
+            (Control::CallEc, [receiver]) => {
+                let id = NEXT_ESCAPE.fetch_add(1, Ordering::Relaxed);
+                self.push(Resume::Escape { id }, site.span());
+                let continuation = Continuation::escape(id, self.winders.clone());
+                let args = [Value::Continuation(continuation)];
+                self.apply(receiver.clone(), &args, site)
+            }
+            (Control::DynamicWind, [before, thunk, after]) => {
+                let winder = Rc::new(Winder::new(
+                    before.clone(),
+                    after.clone(),
+                    self.winders.clone(),
+                ));
+                let span = site.span();
+                let resume = Resume::WindBody {
+                    winder,
+                    thunk: thunk.clone(),
+                    site: site.clone(),
+                };
+                self.push(resume, span);
+                self.apply(before.clone(), &[], site)
+            }
+            (Control::Raise, [value]) => Err(RuntimeError::raise(value.clone())),
+            (Control::RaiseContinuable, [value]) => match self.find_handler() {
+                Some(index) => self.raise(value.clone(), index, true),
+                None => Err(RuntimeError::raise(value.clone())),
+            },
+            (Control::WithExceptionHandler, [handler, thunk]) => {
+                // 处理器在引发异常时才被调用，因此安装时就检查参数的类型
+                handler.try_as_procedure()?;
+                thunk.try_as_procedure()?;
+                let span = site.span();
+                let resume = Resume::Handler {
+                    handler: handler.clone(),
+                    site: site.clone(),
+                };
+                self.push(resume, span);
+                self.apply(thunk.clone(), &[], site)
+            }
+            // 在 `dynamic-wind` 的动态范围中调用过程，离开时恢复原来的精度
+            (Control::WithPrecision, [bits, thunk]) => {
+                let (before, after) = math::precision_winders(bits)?;
+                let args = [before, thunk.clone(), after];
+                self.apply_control(Control::DynamicWind, &args, site)
+            }
+            (Control::SetPrecision(bits), []) => {
+                Numeric::set_precision(bits)?;
+                Ok(Flow::Value(Value::Void))
+            }
+            (Control::CallCc | Control::CallEc | Control::Raise | Control::RaiseContinuable, _) => {
+                Err(RuntimeErrorKind::InvalidArity {
+                    expected: 1,
+                    founded: args.len(),
+                }
+                .into())
+            }
+            (Control::WithExceptionHandler | Control::WithPrecision, _) => {
+                Err(RuntimeErrorKind::InvalidArity {
+                    expected: 2,
+                    founded: args.len(),
+                }
+                .into())
+            }
+            (Control::SetPrecision(_), _) => Err(RuntimeErrorKind::InvalidArity {
+                expected: 0,
+                founded: args.len(),
+            }
+            .into()),
+            (Control::DynamicWind, _) => Err(RuntimeErrorKind::InvalidArity {
+                expected: 3,
+                founded: args.len(),
+            }
+            .into()),
+        }
+    }
+
+    // 以得到的值继续之前保存的计算
+    fn resume(&mut self, resume: Resume, value: Value) -> Result<Flow, RuntimeError> {
+        match resume {
+            Resume::Frame {
+                code,
+                pc,
+                env,
+                base,
+                height,
+            } => {
+                self.code = code;
+                self.pc = pc;
+                self.env = env;
+                self.base = base;
+                self.stack.truncate(height);
+                self.stack.push(value);
+                Ok(Flow::Next)
+            }
+            Resume::Toplevel { forms, env } => self.toplevel_forms(&forms, &env),
+            Resume::WindBody {
+                winder,
+                thunk,
+                site,
+            } => {
+                self.winders = Some(Rc::clone(&winder));
+                let span = site.span();
+                let resume = Resume::Unwind {
+                    winder,
+                    site: site.clone(),
+                };
+                self.push(resume, span);
+                self.apply(thunk, &[], site)
+            }
+            Resume::Unwind { winder, site } => {
+                self.winders.clone_from(&winder.parent);
+                let resume = Resume::Return {
+                    value,
+                    winders: winder.parent.clone(),
+                };
+                self.push(resume, None);
+                self.apply(winder.after.clone(), &[], site)
+            }
+            Resume::Wind {
+                thunk,
+                winders,
+                site,
+            } => {
+                self.winders = winders;
+                self.apply(thunk, &[], site)
+            }
+            Resume::Return {
+                value: saved,
+                winders,
+            } => {
+                self.winders = winders;
+                Ok(Flow::Value(saved))
+            }
+            Resume::Escape { .. } | Resume::Handler { .. } | Resume::Guard { .. } => {
+                Ok(Flow::Value(value))
+            }
+            Resume::Raise {
+                value: raised,
+                marker,
+                continuable,
+            } => {
+                if continuable {
+                    return Ok(Flow::Value(value));
+                }
+                // `raise` 的处理器返回时，在处理器所在的动态环境中再次引发，交给更外层的处理器
+                let resume = Resume::Raise {
+                    value: raised.clone(),
+                    marker,
+                    continuable,
+                };
+                self.push(resume, None);
+                Err(RuntimeError::raise(raised))
+            }
+        }
+    }
+}
+
+impl ControlStack for Machine {
+    type Frame = Activation;
+    type Site<'a> = &'a Site;
+    type Step = Flow;
+
+    fn frames(&self) -> &[Activation] {
+        &self.frames
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.frames.truncate(len);
+    }
+
+    fn role(activation: &Activation) -> Role {
+        match activation.resume {
+            Resume::Escape { id } => Role::Escape(id),
+            Resume::Handler { .. } | Resume::Guard { .. } => Role::Handler,
+            Resume::Raise { marker, .. } => Role::Raise(marker),
+            _ => Role::Other,
+        }
+    }
+
+    fn winders(&self) -> Option<&Rc<Winder>> {
+        self.winders.as_ref()
+    }
+
+    fn restore(&mut self, continuation: &Continuation) {
+        let Some((frames, stack)) = continuation.frames() else {
+            return;
+        };
+        self.frames = frames;
+        self.stack = stack;
+    }
+
+    fn push_return(&mut self, value: Value, winders: Option<Rc<Winder>>) {
+        self.push(Resume::Return { value, winders }, None);
+    }
+
+    fn push_wind(&mut self, thunk: Value, winders: Option<Rc<Winder>>, site: &Site) {
+        let resume = Resume::Wind {
+            thunk,
+            winders,
+            site: site.clone(),
+        };
+        self.push(resume, site.span());
+    }
+
+    fn value(value: Value) -> Flow {
+        Flow::Value(value)
+    }
+
+    fn raise(
+        &mut self,
+        value: Value,
+        index: usize,
+        continuable: bool,
+    ) -> Result<Flow, RuntimeError> {
+        match self.frames[index].resume.clone() {
+            // 处理器在 `raise` 所在的动态环境中调用
+            Resume::Handler { handler, site } => {
+                let resume = Resume::Raise {
+                    value: value.clone(),
+                    marker: index,
+                    continuable,
+                };
+                self.push(resume, site.span());
+                self.apply(handler, &[value], site)
+            }
+            // `guard` 丢弃过程体的续延，离开其中的动态范围后从处理器的代码继续执行
+            Resume::Guard {
+                code,
+                handler,
+                env,
+                base,
+                height,
+                winders,
+                site,
+            } => {
+                self.frames.truncate(index);
+                let resume = Resume::Frame {
+                    code,
+                    pc: handler,
+                    env,
+                    base,
+                    height,
+                };
+                self.push(resume, site.span());
+                Ok(self.jump(value, winders, &site))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! 求值器与虚拟机共用的续延跳转与异常处理
+//!
+//! 两种后端都把之后要继续的计算保存在显式的续延栈中，
+//! 跳转时在栈上依次压入离开与进入动态范围时调用的过程，引发异常时沿栈向下查找处理器。
+
+use std::rc::Rc;
+
+use crate::model::{Condition, Continuation, RuntimeError, RuntimeErrorKind, Value, Winder};
+
+/// 续延栈中的一层在跳转与异常处理中的作用
+pub(crate) enum Role {
+    /// `call/ec` 的返回处
+    Escape(usize),
+    /// 由 `with-exception-handler` 或 `guard` 安装的处理器
+    Handler,
+    /// 正在以引发的值调用第 `marker` 层的处理器
+    Raise(usize),
+    Other,
+}
+
+/// 保存在显式续延栈中的计算状态
+pub(crate) trait ControlStack {
+    /// 续延栈中的一层
+    type Frame;
+    /// 跳转时调用动态范围中的过程的位置
+    type Site<'a>: Copy;
+    /// 交给求值循环的下一步
+    type Step;
+
+    fn frames(&self) -> &[Self::Frame];
+
+    fn truncate(&mut self, len: usize);
+
+    fn role(frame: &Self::Frame) -> Role;
+
+    /// 当前所在的动态范围
+    fn winders(&self) -> Option<&Rc<Winder>>;
+
+    /// 恢复完整续延中保存的续延栈
+    fn restore(&mut self, continuation: &Continuation);
+
+    /// 保存忽略收到的值、在动态范围 `winders` 中返回 `value` 的计算
+    fn push_return(&mut self, value: Value, winders: Option<Rc<Winder>>);
+
+    /// 保存在动态范围 `winders` 中调用 `thunk` 的计算
+    fn push_wind(&mut self, thunk: Value, winders: Option<Rc<Winder>>, site: Self::Site<'_>);
+
+    /// 将值交给续延栈顶的计算
+    fn value(value: Value) -> Self::Step;
+
+    /// 以引发的值调用续延栈第 `index` 层的处理器
+    fn raise(
+        &mut self,
+        value: Value,
+        index: usize,
+        continuable: bool,
+    ) -> Result<Self::Step, RuntimeError>;
+
+    /// 调用续延：先调用离开与进入的动态范围中的过程，再将值交给捕获处之后的计算
+    fn throw(
+        &mut self,
+        continuation: &Continuation,
+        args: &[Value],
+        site: Self::Site<'_>,
+    ) -> Result<Self::Step, RuntimeError> {
+        let value = match args {
+            [] => Value::Void,
+            [value] => value.clone(),
+            _ => {
+                return Err(RuntimeErrorKind::InvalidArity {
+                    expected: 1,
+                    founded: args.len(),
+                }
+                .into())
+            }
+        };
+
+        if let Some(id) = continuation.escape_id() {
+            // `call/ec` 已经返回时标记不在栈中
+            let index = self
+                .frames()
+                .iter()
+                .rposition(
+                    |frame| matches!(Self::role(frame), Role::Escape(marker) if marker == id),
+                )
+                .ok_or(RuntimeErrorKind::ExpiredContinuation)?;
+            self.truncate(index);
+        } else {
+            self.restore(continuation);
+        }
+
+        Ok(self.jump(value, continuation.winders().cloned(), site))
+    }
+
+    /// 在动态范围 `target` 中将 `value` 交给续延栈顶的计算，先依次调用离开与进入的动态范围中的过程
+    fn jump(
+        &mut self,
+        value: Value,
+        target: Option<Rc<Winder>>,
+        site: Self::Site<'_>,
+    ) -> Self::Step {
+        let thunks = winding_thunks(self.winders(), target.as_ref());
+        self.push_return(value, target);
+        for (thunk, winders) in thunks.into_iter().rev() {
+            self.push_wind(thunk, winders, site);
+        }
+        Self::value(Value::Void)
+    }
+
+    /// 将错误交给最内层的处理器，内置错误转换为错误对象
+    fn handle(&mut self, err: RuntimeError) -> Result<Self::Step, RuntimeError> {
+        let Some(index) = self.find_handler() else {
+            return Err(err);
+        };
+        let value = match err.into_kind() {
+            RuntimeErrorKind::Raise(value) => value,
+            kind => Value::Condition(Rc::new(Condition::new(kind))),
+        };
+        self.raise(value, index, false)
+    }
+
+    /// 从续延栈顶向下查找最内层的处理器，跳过正在运行的处理器及更内层的处理器
+    fn find_handler(&self) -> Option<usize> {
+        let frames = self.frames();
+        let mut index = frames.len();
+        while index > 0 {
+            index -= 1;
+            match Self::role(&frames[index]) {
+                Role::Handler => return Some(index),
+                Role::Raise(marker) => index = marker,
+                Role::Escape(_) | Role::Other => {}
+            }
+        }
+        None
+    }
+}
+
+// 从动态范围 `from` 跳转到 `to` 时依次调用的过程及调用时所在的动态范围：
+// 先由内向外离开只属于 `from` 的范围，再由外向内进入只属于 `to` 的范围
+fn winding_thunks(
+    mut from: Option<&Rc<Winder>>,
+    mut to: Option<&Rc<Winder>>,
+) -> Vec<(Value, Option<Rc<Winder>>)> {
+    let depth = |winder: Option<&Rc<Winder>>| winder.map_or(0, |winder| winder.depth() + 1);
+    let mut afters = Vec::new();
+    let mut befores = Vec::new();
+    loop {
+        match (from, to) {
+            (Some(leaving), _) if depth(from) > depth(to) => {
+                afters.push((leaving.after.clone(), leaving.parent.clone()));
+                from = leaving.parent.as_ref();
+            }
+            (_, Some(entering)) if depth(to) > depth(from) => {
+                befores.push((entering.before.clone(), entering.parent.clone()));
+                to = entering.parent.as_ref();
+            }
+            (Some(leaving), Some(entering)) if !Rc::ptr_eq(leaving, entering) => {
+                afters.push((leaving.after.clone(), leaving.parent.clone()));
+                befores.push((entering.before.clone(), entering.parent.clone()));
+                from = leaving.parent.as_ref();
+                to = entering.parent.as_ref();
+            }
+            _ => break,
+        }
+    }
+    afters.extend(befores.into_iter().rev());
+    afters
+}
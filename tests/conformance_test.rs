@@ -0,0 +1,87 @@
+// 以字节码虚拟机运行解释器、诊断与垃圾回收的测试，两种后端的行为应当一致
+
+extern crate lemon_lisp as library;
+extern crate self as lemon_lisp;
+
+pub use library::{diagnostic, evaluator, lexer, model, parser};
+
+pub mod interpreter {
+    use super::library::interpreter::{Backend, Interpreter as Library};
+
+    pub struct Interpreter;
+
+    impl Interpreter {
+        #[allow(clippy::new_ret_no_self)]
+        pub fn new() -> Library {
+            Library::new().with_backend(Backend::Bytecode)
+        }
+    }
+}
+
+#[path = "diagnostic_test.rs"]
+mod diagnostic_test;
+#[path = "gc_test.rs"]
+mod gc_test;
+#[path = "interpreter_test.rs"]
+mod interpreter_test;
+
+#[warn(clippy::all, clippy::pedantic)]
+#[cfg(test)]
+mod tests {
+    use lemon_lisp::{
+        interpreter::Interpreter,
+        model::{RuntimeError, RuntimeErrorKind, Value},
+    };
+    use rug::Integer;
+
+    #[test]
+    fn test_lexical_addressing() {
+        let interpreter = Interpreter::new();
+
+        interpreter
+            .eval("(define (make-counter) (define n 0) (lambda () (set! n (+ n 1)) n))")
+            .unwrap();
+        interpreter
+            .eval("(define a (make-counter)) (define b (make-counter))")
+            .unwrap();
+        assert_eq!(
+            interpreter.eval("(a) (a) (b) (list (a) (b))"),
+            interpreter.eval("'(3 2)")
+        );
+        assert_eq!(
+            interpreter.eval("(let* ((x 1) (x (+ x 1)) (f (lambda () x))) (let ((x 10)) (f)))"),
+            Ok(Value::from(Integer::from(2)))
+        );
+    }
+
+    #[test]
+    fn test_deferred_compile_error() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(
+            interpreter.eval("(define (f) (if)) 1"),
+            Ok(Value::from(Integer::from(1)))
+        );
+        assert!(matches!(
+            interpreter.eval("(f)").map_err(RuntimeError::into_kind),
+            Err(RuntimeErrorKind::InvalidSyntax(_))
+        ));
+    }
+
+    #[test]
+    fn test_toplevel_begin_continuation() {
+        let interpreter = Interpreter::new();
+
+        interpreter.eval("(define m 3) (define k3 #f)").unwrap();
+        assert_eq!(
+            interpreter.eval(
+                "(begin (call/cc (lambda (k) (set! k3 k))) (set! m (+ m 1)) (if (= m 6) m (k3 0)))"
+            ),
+            Ok(Value::from(Integer::from(6)))
+        );
+        assert_eq!(
+            interpreter.eval("(begin (define-syntax two (syntax-rules () ((_) 2))) (two))"),
+            Ok(Value::from(Integer::from(2)))
+        );
+    }
+}
@@ -0,0 +1,170 @@
+#[warn(clippy::all, clippy::pedantic)]
+#[cfg(test)]
+mod tests {
+    use lemon_lisp::{
+        diagnostic::{Diagnostic, Style},
+        interpreter::Interpreter,
+        lexer::TokenStream,
+        model::SourceId,
+        parser::Parser,
+    };
+
+    fn render_error(source: &str, style: Style) -> String {
+        let interpreter = Interpreter::new();
+        let error = interpreter.eval(source).unwrap_err();
+        Diagnostic::from(&error).render(source, style)
+    }
+
+    #[test]
+    fn test_type_error() {
+        assert_eq!(
+            render_error(r#"(+ 1 (* 2 "a"))"#, Style::Plain),
+            concat!(
+                "error[TypeError]: TypeError: expected numeric, found \"a\"\n",
+                " --> 1:6\n",
+                "  |\n",
+                "1 | (+ 1 (* 2 \"a\"))\n",
+                "  |      ^^^^^^^^^\n",
+                "backtrace:\n",
+                "   0: * at 1:6: (* 2 \"a\")\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_arity_note() {
+        let source = "(define (add a b)\n  (+ a b))\n\n\n\n\n\n\n\n(add 1)";
+        assert_eq!(
+            render_error(source, Style::Plain),
+            concat!(
+                "error[InvalidArity]: Invalid arity: expected 2 arguments, but found 1\n",
+                "  --> 10:1\n",
+                "   |\n",
+                "10 | (add 1)\n",
+                "   | ^^^^^^^\n",
+                "note: closure defined here\n",
+                "  --> 1:1\n",
+                "   |\n",
+                " 1 | (define (add a b)\n",
+                "   | -----------------\n",
+                "backtrace:\n",
+                "   0: add at 10:1: (add 1)\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_backtrace() {
+        let source = concat!(
+            "(define (inner x) (* x \"a\"))\n",
+            "(define (outer y) (+ 1 (inner y)))\n",
+            "(define thunk (lambda () (outer 2)))\n",
+            "(thunk)",
+        );
+        assert_eq!(
+            render_error(source, Style::Plain),
+            concat!(
+                "error[TypeError]: TypeError: expected numeric, found \"a\"\n",
+                " --> 1:19\n",
+                "  |\n",
+                "1 | (define (inner x) (* x \"a\"))\n",
+                "  |                   ^^^^^^^^^\n",
+                "backtrace:\n",
+                "   0: * at 1:19: (* x \"a\")\n",
+                "   1: inner at 2:24: (inner y)\n",
+                "   2: outer at 3:26: (outer 2)\n",
+                "   3: <anonymous> at 4:1: (thunk)\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_ansi() {
+        assert_eq!(
+            render_error("\t(f 1)", Style::Ansi),
+            concat!(
+                "\x1b[1;31merror[UndefinedVariable]\x1b[0m\x1b[1m: Undefined variable: f\x1b[0m\n",
+                " \x1b[1;34m-->\x1b[0m 1:3\n",
+                "\x1b[1;34m  |\x1b[0m\n",
+                "\x1b[1;34m1 |\x1b[0m \t(f 1)\n",
+                "\x1b[1;34m  |\x1b[0m \t \x1b[1;31m^\x1b[0m\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let source = "(print\n  (f 1)";
+        let error = Parser::new(TokenStream::new(source)).parse().unwrap_err();
+        assert_eq!(
+            Diagnostic::from(&error).render(source, Style::Plain),
+            concat!(
+                "error[MissingToken]: Missing token: )\n",
+                " --> 1:1\n",
+                "  |\n",
+                "1 | (print\n",
+                "  | ^^^^^^\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_multiple_sources() {
+        // 像交互式环境一样分多次输入，每个位置在它所属的输入中截取片段
+        let sources = [
+            "(define (add a b) (+ a b))",
+            "(define (inner x)\n  (* x \"a\"))",
+            "(add 1)",
+            "(inner 2)",
+        ];
+        let interpreter = Interpreter::new();
+        let mut errors = Vec::new();
+        for (i, source) in sources.iter().enumerate() {
+            if let Err(error) = interpreter.eval_source(source, SourceId(i)) {
+                errors.push(Diagnostic::from(&error));
+            }
+        }
+
+        assert_eq!(
+            errors[0].render_sources(&sources, Style::Plain),
+            concat!(
+                "error[InvalidArity]: Invalid arity: expected 2 arguments, but found 1\n",
+                " --> 1:1\n",
+                "  |\n",
+                "1 | (add 1)\n",
+                "  | ^^^^^^^\n",
+                "note: closure defined here\n",
+                " --> 1:1\n",
+                "  |\n",
+                "1 | (define (add a b) (+ a b))\n",
+                "  | --------------------------\n",
+                "backtrace:\n",
+                "   0: add at 1:1: (add 1)\n",
+            )
+        );
+        assert_eq!(
+            errors[1].render_sources(&sources, Style::Plain),
+            concat!(
+                "error[TypeError]: TypeError: expected numeric, found \"a\"\n",
+                " --> 2:3\n",
+                "  |\n",
+                "2 |   (* x \"a\"))\n",
+                "  |   ^^^^^^^^^\n",
+                "backtrace:\n",
+                "   0: * at 2:3: (* x \"a\")\n",
+                "   1: inner at 1:1: (inner 2)\n",
+            )
+        );
+        // 没有提供所属的源码时只输出位置
+        assert_eq!(
+            errors[1].render(sources[3], Style::Plain),
+            concat!(
+                "error[TypeError]: TypeError: expected numeric, found \"a\"\n",
+                " --> 2:3\n",
+                "backtrace:\n",
+                "   0: * at 2:3: (* x \"a\")\n",
+                "   1: inner at 1:1: (inner 2)\n",
+            )
+        );
+    }
+}
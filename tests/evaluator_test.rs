@@ -49,17 +49,20 @@ mod tests {
         assert!(result.is_ok());
 
         if let Some(Value::Closure(closure)) = environment.get("add-one") {
-            assert_eq!(Some("add-one".to_string()), closure.name);
-            assert_eq!(vec!["n".to_string()], closure.params);
+            assert_eq!(Some("add-one".into()), closure.name);
+            assert_eq!(vec!["n".to_string()], closure.params.required);
             assert_eq!(
-                vec![Value::List(vec![
-                    Value::Symbol("+".into()),
-                    Value::Symbol("n".into()),
-                    Value::from(Integer::from(1)),
-                ])],
-                closure.body
+                vec![Value::List(
+                    vec![
+                        Value::Symbol("+".into()),
+                        Value::Symbol("n".into()),
+                        Value::from(Integer::from(1)),
+                    ]
+                    .into()
+                )],
+                *closure.body
             );
-            assert!(closure.environment.upgrade().is_some());
+            assert!(Rc::ptr_eq(&closure.environment, &environment));
         } else {
             panic!("Expected to find a closure named 'add-one' in the environment");
         };
@@ -75,42 +78,27 @@ mod tests {
         assert!(result.is_ok());
         if let Ok(Value::Closure(closure)) = result {
             assert_eq!(None, closure.name);
-            assert_eq!(vec!["a".to_string(), "b".to_string()], closure.params);
             assert_eq!(
-                vec![Value::List(vec![
-                    Value::Symbol("+".into()),
-                    Value::Symbol("a".into()),
-                    Value::Symbol("b".into()),
-                ])],
-                closure.body
+                vec!["a".to_string(), "b".to_string()],
+                closure.params.required
             );
-            assert!(closure.environment.upgrade().is_some());
+            assert_eq!(
+                vec![Value::List(
+                    vec![
+                        Value::Symbol("+".into()),
+                        Value::Symbol("a".into()),
+                        Value::Symbol("b".into()),
+                    ]
+                    .into()
+                )],
+                *closure.body
+            );
+            assert!(Rc::ptr_eq(&closure.environment, &environment));
         } else {
             panic!("Expected a closure");
         }
     }
 
-    #[test]
-    fn test_optimize_tail_call() {
-        let environment = Environment::new();
-        let evaluator = Evaluator;
-
-        let result = eval_input("(define (loop) (loop))", &evaluator, &environment);
-
-        assert!(result.is_ok());
-        if let Some(Value::TailCall(tail_call)) = environment.get("loop") {
-            assert_eq!(Some("loop".to_string()), tail_call.closure.name);
-            assert!(tail_call.closure.params.is_empty());
-            assert!(tail_call.closure.body.is_empty());
-            assert!(tail_call.closure.environment.upgrade().is_some());
-            assert!(tail_call.updates.is_empty());
-            assert_eq!(Value::Bool(false), *tail_call.break_condition);
-            assert_eq!(Value::Void, *tail_call.return_expr);
-        } else {
-            panic!("Expected a tail call");
-        }
-    }
-
     #[test]
     fn test_internal_fn() {
         let environment = Environment::new();
@@ -127,7 +115,7 @@ mod tests {
         environment.set(
             "+",
             Value::InternalFunction(InternalFunction {
-                name: "+".to_string(),
+                name: "+",
                 function: add,
             }),
         );
@@ -136,4 +124,28 @@ mod tests {
 
         assert_eq!(Ok(Value::from(Integer::from(5))), result);
     }
+
+    #[test]
+    fn test_lexical_addressing() {
+        let environment = Environment::new();
+        let evaluator = Evaluator;
+
+        let input = "(define x 0)
+                     (define (swap a b) (let ((a b) (b a)) (lambda () (set! x a) b)))
+                     ((swap 1 2))";
+        let result = eval_input(input, &evaluator, &environment);
+
+        assert_eq!(Ok(Value::from(Integer::from(1))), result);
+        assert_eq!(Some(Value::from(Integer::from(2))), environment.get("x"));
+    }
+
+    #[test]
+    fn test_deferred_syntax_error() {
+        let environment = Environment::new();
+        let evaluator = Evaluator;
+
+        let result = eval_input("(define (broken) (if)) 1", &evaluator, &environment);
+        assert_eq!(Ok(Value::from(Integer::from(1))), result);
+        assert!(eval_input("(broken)", &evaluator, &environment).is_err());
+    }
 }
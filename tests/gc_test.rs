@@ -0,0 +1,147 @@
+#[warn(clippy::all, clippy::pedantic)]
+#[cfg(test)]
+mod tests {
+    use lemon_lisp::{
+        evaluator::Evaluator,
+        interpreter::Interpreter,
+        lexer::TokenStream,
+        model::{Environment, Value},
+        parser::Parser,
+    };
+    use rug::Integer;
+
+    // 过程的局部环境中定义了引用自身的闭包，调用结束后局部环境与闭包形成循环引用
+    const MAKE_CYCLE: &str = "(define (make-cycle) (define (self) self) self)";
+
+    #[test]
+    fn test_collect_cycle() {
+        let interpreter = Interpreter::new();
+        interpreter.eval(MAKE_CYCLE).unwrap();
+        interpreter.eval("(make-cycle) (make-cycle)").unwrap();
+
+        assert_eq!(interpreter.gc_stats().live_objects, 3);
+        assert_eq!(interpreter.collect_garbage(), 2);
+
+        let stats = interpreter.gc_stats();
+        assert_eq!(stats.live_objects, 1);
+        assert_eq!(stats.collections, 1);
+        assert_eq!(stats.freed_objects, 2);
+    }
+
+    #[test]
+    fn test_keep_reachable() {
+        let interpreter = Interpreter::new();
+        interpreter.eval(MAKE_CYCLE).unwrap();
+        interpreter
+            .eval("(define kept (make-cycle)) (make-cycle)")
+            .unwrap();
+
+        assert_eq!(interpreter.collect_garbage(), 1);
+        assert_eq!(interpreter.gc_stats().live_objects, 2);
+        assert_eq!(
+            interpreter.eval("((kept))").unwrap().to_string(),
+            "#<procedure:self>"
+        );
+    }
+
+    #[test]
+    fn test_external_root() {
+        let global = Environment::new();
+        let local = Environment::extend(&global);
+        let source = "(define (self) self) self";
+        let expressions = Parser::new(TokenStream::new(source)).parse().unwrap();
+        let mut closure = Value::Void;
+        for expr in &expressions {
+            closure = Evaluator.eval_value(expr, &local).unwrap();
+        }
+        drop(local);
+
+        // 闭包仍被 Rust 代码持有，它所在的环境不能被回收
+        assert_eq!(global.heap().collect(), 0);
+        let Value::Closure(closure) = &closure else {
+            panic!("expected closure");
+        };
+        assert!(closure.environment.get("self").is_some());
+        assert_eq!(global.heap().stats().live_objects, 2);
+    }
+
+    #[test]
+    fn test_automatic_collection() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (sum n acc)
+                   (define (self) self)
+                   (if (= n 0) acc (sum (- n 1) (+ acc n))))",
+            )
+            .unwrap();
+
+        assert_eq!(
+            interpreter.eval("(sum 10000 0)"),
+            Ok(Value::from(Integer::from(50_005_000)))
+        );
+
+        let stats = interpreter.gc_stats();
+        assert!(stats.collections > 0);
+        assert!(stats.live_objects < 10000);
+    }
+
+    #[test]
+    fn test_builtin() {
+        let interpreter = Interpreter::new();
+        interpreter.eval(MAKE_CYCLE).unwrap();
+        interpreter.eval("(make-cycle)").unwrap();
+
+        assert_eq!(interpreter.eval("(gc)"), Ok(Value::from(Integer::from(1))));
+        assert_eq!(interpreter.eval("(gc)"), Ok(Value::from(Integer::from(0))));
+
+        let stats = interpreter.eval("(gc-stats)").unwrap().to_string();
+        assert!(stats.starts_with("((live-objects 1) (bytes "));
+        assert!(stats.ends_with(") (collections 2) (freed-objects 1))"));
+    }
+
+    #[test]
+    fn test_collect_pair_cycle() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (make-ring)
+                   (define ring (list 1 2 3))
+                   (set-cdr! (cdr (cdr ring)) ring)
+                   (car ring))",
+            )
+            .unwrap();
+        let before = interpreter.gc_stats().live_objects;
+
+        assert_eq!(
+            interpreter.eval("(make-ring)"),
+            Ok(Value::from(Integer::from(1)))
+        );
+        assert_eq!(interpreter.gc_stats().live_objects, before + 3);
+        assert_eq!(interpreter.collect_garbage(), 3);
+        assert_eq!(interpreter.gc_stats().live_objects, before);
+    }
+
+    #[test]
+    fn test_collect_continuation_cycle() {
+        let interpreter = Interpreter::new();
+        // 续延保存的续延栈引用了局部环境，局部环境又保存着续延
+        interpreter
+            .eval(
+                "(define (make-continuation)
+                   (define saved #f)
+                   (+ 1 (call/cc (lambda (k) (set! saved k) 1)))
+                   saved)",
+            )
+            .unwrap();
+        let before = interpreter.gc_stats().live_objects;
+
+        assert_eq!(
+            interpreter.eval("(make-continuation)").unwrap().to_string(),
+            "#<continuation>"
+        );
+        assert_eq!(interpreter.gc_stats().live_objects, before + 2);
+        assert_eq!(interpreter.collect_garbage(), 2);
+        assert_eq!(interpreter.gc_stats().live_objects, before);
+    }
+}
@@ -1,8 +1,13 @@
 #[warn(clippy::all, clippy::pedantic)]
 #[cfg(test)]
 mod tests {
-    use lemon_lisp::{interpreter::Interpreter, model::Value};
-    use rug::{Float, Integer};
+    use lemon_lisp::{
+        interpreter::Interpreter,
+        model::{
+            Arity, Numeric, ParseErrorKind, RuntimeError, RuntimeErrorKind, Span, Token, Value,
+        },
+    };
+    use rug::{Complex, Float, Integer, Rational};
 
     #[test]
     fn test_simple_arithmetic() {
@@ -31,7 +36,7 @@ mod tests {
 
         assert_eq!(
             interpreter.eval("(/ 4)"),
-            Ok(Value::from(Float::with_val(53, 0.25)))
+            Ok(Value::from(Rational::from((1, 4))))
         );
         assert_eq!(
             interpreter.eval("(/ 45 5 3)"),
@@ -83,4 +88,2169 @@ mod tests {
             Ok(Value::from(Float::with_val(53, 12.5)))
         );
     }
+
+    #[test]
+    fn test_tail_call() {
+        let interpreter = Interpreter::new();
+
+        interpreter
+            .eval(
+                "(define (count-down n)
+                   (if (= n 0)
+                       'done
+                       (count-down (- n 1))))",
+            )
+            .unwrap();
+        assert_eq!(
+            interpreter.eval("(count-down 20000)"),
+            Ok(Value::Symbol("done".into()))
+        );
+
+        // 互相递归
+        interpreter
+            .eval(
+                "(define (even? n) (if (= n 0) #t (odd? (- n 1))))
+                 (define (odd? n) (if (= n 0) #f (even? (- n 1))))",
+            )
+            .unwrap();
+        assert_eq!(interpreter.eval("(even? 20001)"), Ok(Value::Bool(false)));
+
+        // 嵌套的 if 以及通过匿名过程进行的尾调用
+        interpreter
+            .eval(
+                "(define (walk n acc)
+                   (if (= n 0)
+                       acc
+                       (if (= n 1)
+                           (walk 0 (+ acc 1))
+                           ((lambda (m) (walk m (+ acc 1))) (- n 1)))))",
+            )
+            .unwrap();
+        assert_eq!(
+            interpreter.eval("(walk 20000 0)"),
+            Ok(Value::from(Integer::from(20000)))
+        );
+    }
+
+    #[test]
+    fn test_returned_closure() {
+        let interpreter = Interpreter::new();
+
+        interpreter
+            .eval(
+                "(define (make-adder n) (lambda (x) (+ x n)))
+                 (define add-two (make-adder 2))",
+            )
+            .unwrap();
+        assert_eq!(
+            interpreter.eval("(add-two 40)"),
+            Ok(Value::from(Integer::from(42)))
+        );
+        assert_eq!(
+            interpreter.eval("((make-adder 1) 2)"),
+            Ok(Value::from(Integer::from(3)))
+        );
+
+        // 多层嵌套的闭包在外层调用返回后仍然可用
+        interpreter
+            .eval(
+                "(define (compose f g) (lambda (x) (f (g x))))
+                 (define (make-point x y)
+                   (lambda (message)
+                     (if (= message 0) x y)))
+                 (define point (make-point 3 4))
+                 (define add-five (compose add-two (make-adder 3)))",
+            )
+            .unwrap();
+        assert_eq!(
+            interpreter.eval("(+ (point 0) (point 1))"),
+            Ok(Value::from(Integer::from(7)))
+        );
+        assert_eq!(
+            interpreter.eval("(add-five 1)"),
+            Ok(Value::from(Integer::from(6)))
+        );
+    }
+
+    #[test]
+    fn test_error_span() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(
+            interpreter.eval("(+ 1\n   (* y 2))"),
+            Err(RuntimeError::new(
+                RuntimeErrorKind::UndefinedVariable("y".into()),
+                Some(Span::new(11, 12, 2, 7))
+            ))
+        );
+
+        let error = interpreter.eval(r#"(+ 1 (* 2 "a"))"#).unwrap_err();
+        assert_eq!(
+            error.kind,
+            RuntimeErrorKind::TypeError {
+                expected: "numeric",
+                founded: Value::String("a".into())
+            }
+        );
+        assert_eq!(error.span, Some(Span::new(5, 14, 1, 6)));
+    }
+
+    #[test]
+    fn test_backtrace() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval("(define (f x) (g x)) (define (g x) (/ x 0))")
+            .unwrap();
+
+        let error = interpreter.eval("(+ 1 (f 2))").unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::DivideByZero);
+
+        let frames: Vec<_> = error
+            .backtrace
+            .iter()
+            .map(|frame| (frame.procedure.clone(), frame.call.to_string()))
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                (Some("/".to_string()), "(/ x 0)".to_string()),
+                (Some("g".to_string()), "(g x)".to_string()),
+                (Some("f".to_string()), "(f 2)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pair() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(
+            interpreter.eval("(car (cons 1 2))"),
+            Ok(Value::from(Integer::from(1)))
+        );
+        assert_eq!(
+            interpreter.eval("(cdr '(1 . 2))"),
+            Ok(Value::from(Integer::from(2)))
+        );
+        assert_eq!(
+            interpreter.eval("(cdr '(1 2 3))").unwrap().to_string(),
+            "(2 3)"
+        );
+        assert_eq!(
+            interpreter.eval("(cons 1 (cons 2 3))").unwrap().to_string(),
+            "(1 2 . 3)"
+        );
+        assert_eq!(
+            interpreter.eval("(cons 1 '(2 3))").unwrap().to_string(),
+            "(1 2 3)"
+        );
+        assert_eq!(interpreter.eval("(list)").unwrap().to_string(), "()");
+        assert_eq!(
+            interpreter
+                .eval("(list 1 (list 2) '(3 . 4))")
+                .unwrap()
+                .to_string(),
+            "(1 (2) (3 . 4))"
+        );
+
+        // 正规的序对链与列表相等
+        assert_eq!(interpreter.eval("(list 1 2)"), interpreter.eval("'(1 2)"));
+        assert_eq!(interpreter.eval("(pair? '())"), Ok(Value::Bool(false)));
+        assert_eq!(interpreter.eval("(pair? '(1))"), Ok(Value::Bool(true)));
+        assert_eq!(
+            interpreter.eval("(null? (cdr '(1)))"),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(interpreter.eval("(null? (list 1))"), Ok(Value::Bool(false)));
+
+        // 带点号的表达式不能求值，错误指向整个表达式
+        let error = interpreter.eval("(+ 1 2) (1 . 2)").unwrap_err();
+        assert_eq!(
+            error.kind,
+            RuntimeErrorKind::SyntaxError(ParseErrorKind::InvalidSyntax(Token::Dot))
+        );
+        assert_eq!(error.span, Some(Span::new(8, 15, 1, 9)));
+    }
+
+    #[test]
+    fn test_pair_mutation() {
+        let interpreter = Interpreter::new();
+
+        // 修改共享的尾部对所有引用者可见
+        interpreter
+            .eval(
+                "(define tail (list 2 3))
+                 (define a (cons 1 tail))
+                 (define b (cons 0 tail))
+                 (set-car! tail 20)
+                 (set-cdr! (cdr tail) 4)",
+            )
+            .unwrap();
+        assert_eq!(interpreter.eval("a").unwrap().to_string(), "(1 20 3 . 4)");
+        assert_eq!(interpreter.eval("b").unwrap().to_string(), "(0 20 3 . 4)");
+
+        // 列表字面量是序对，但不可修改
+        assert_eq!(interpreter.eval("(pair? '(1 2))"), Ok(Value::Bool(true)));
+        let error = interpreter.eval("(set-car! '(1 2) 3)").unwrap_err();
+        assert_eq!(
+            error.kind,
+            RuntimeErrorKind::TypeError {
+                expected: "mutable pair",
+                founded: interpreter.eval("'(1 2)").unwrap(),
+            }
+        );
+        assert_eq!(
+            interpreter.eval("(set-cdr! 1 2)").unwrap_err().kind,
+            RuntimeErrorKind::TypeError {
+                expected: "pair",
+                founded: Value::from(Integer::from(1)),
+            }
+        );
+        // 沿 `car` 深层嵌套的序对也不会在释放时耗尽栈空间
+        assert_eq!(
+            interpreter.eval(
+                "(define deep
+                   (let loop ((n 100000) (acc '()))
+                     (if (= n 0) acc (loop (- n 1) (cons acc (cons acc '()))))))
+                 (set! deep #f)
+                 deep"
+            ),
+            Ok(Value::Bool(false))
+        );
+        assert_eq!(
+            interpreter.eval("(car '())").unwrap_err().kind,
+            RuntimeErrorKind::TypeError {
+                expected: "pair",
+                founded: interpreter.eval("'()").unwrap(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_long_list() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (build n acc) (if (= n 0) acc (build (- n 1) (cons n acc))))
+                 (define xs (build 50000 '()))",
+            )
+            .unwrap();
+
+        assert_eq!(
+            interpreter.eval("(car (cdr xs))"),
+            Ok(Value::from(Integer::from(2)))
+        );
+        // 释放长列表不会耗尽栈空间
+        interpreter.eval("(define xs 0)").unwrap();
+    }
+
+    #[test]
+    fn test_quasiquote() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval("(define x 42) (define xs (list 1 2)) (define ys '(3 4))")
+            .unwrap();
+
+        assert_eq!(interpreter.eval("`(x ,x)"), interpreter.eval("'(x 42)"));
+        assert_eq!(
+            interpreter.eval("`(0 ,@xs ,@ys 5)").unwrap().to_string(),
+            "(0 1 2 3 4 5)"
+        );
+        assert_eq!(interpreter.eval("`(,@'() . ,x)").unwrap().to_string(), "42");
+        // 模板末尾拼接的值可以是不正规的列表
+        assert_eq!(
+            interpreter.eval("`(1 ,@'(2 . 3))").unwrap().to_string(),
+            "(1 2 . 3)"
+        );
+        assert_eq!(
+            interpreter
+                .eval("`(0 ,@xs ,@(cons 3 4))")
+                .unwrap()
+                .to_string(),
+            "(0 1 2 3 . 4)"
+        );
+        assert_eq!(
+            interpreter.eval("`(1 . ,(+ x 1))").unwrap().to_string(),
+            "(1 . 43)"
+        );
+        assert_eq!(
+            interpreter
+                .eval("`(a (b ,(car xs)) ,(cdr ys))")
+                .unwrap()
+                .to_string(),
+            "(a (b 1) (4))"
+        );
+        assert_eq!(
+            interpreter.eval("(quasiquote (x (unquote x)))"),
+            interpreter.eval("`(x ,x)")
+        );
+    }
+
+    #[test]
+    fn test_nested_quasiquote() {
+        let interpreter = Interpreter::new();
+        interpreter.eval("(define x 1) (define xs '(2 3))").unwrap();
+
+        // 内层准引用中的反引用保持原样，只有与最外层匹配的反引用被求值
+        assert_eq!(
+            interpreter.eval("`(a `(b ,(c ,x)))"),
+            interpreter.eval("'(a (quasiquote (b (unquote (c 1)))))")
+        );
+        assert_eq!(
+            interpreter.eval("`(a `(b ,,@xs))"),
+            interpreter.eval("'(a (quasiquote (b (unquote 2 3))))")
+        );
+        assert_eq!(
+            interpreter.eval("``,,x"),
+            interpreter.eval("'(quasiquote (unquote 1))")
+        );
+        assert_eq!(
+            interpreter.eval("`(a `(b ,(c ,x)))").unwrap().to_string(),
+            "(a `(b ,(c 1)))"
+        );
+    }
+
+    #[test]
+    fn test_quasiquote_error() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(
+            interpreter.eval("`,@'(1 2)").unwrap_err().kind,
+            RuntimeErrorKind::SyntaxError(ParseErrorKind::InvalidSyntax(Token::UnquoteSplicing))
+        );
+        assert_eq!(
+            interpreter.eval("`(1 ,@2)").unwrap_err().kind,
+            RuntimeErrorKind::TypeError {
+                expected: "list",
+                founded: Value::from(Integer::from(2)),
+            }
+        );
+    }
+
+    #[test]
+    fn test_quote() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(
+            interpreter.eval("(quote (1 2))"),
+            interpreter.eval("'(1 2)")
+        );
+        assert_eq!(interpreter.eval("(quote a)"), Ok(Value::Symbol("a".into())));
+        assert_eq!(interpreter.eval("(car ''a)").unwrap().to_string(), "quote");
+        assert_eq!(
+            interpreter.eval("(cdr '(quote a))").unwrap().to_string(),
+            "(a)"
+        );
+
+        // 引用形式以简写输出
+        assert_eq!(interpreter.eval("''a").unwrap().to_string(), "'a");
+        assert_eq!(
+            interpreter
+                .eval("'(define (f x) `(g ,x ,@(h '(y))))")
+                .unwrap()
+                .to_string(),
+            "(define (f x) `(g ,x ,@(h '(y))))"
+        );
+        assert_eq!(
+            interpreter.eval("(list 'quote 'a 'b)").unwrap().to_string(),
+            "(quote a b)"
+        );
+        // 构造得到的数据与读取得到的数据输出相同
+        assert_eq!(
+            interpreter.eval("(list 'quote 'a)").unwrap().to_string(),
+            "'a"
+        );
+        assert_eq!(
+            interpreter
+                .eval("(cons 'quasiquote (cons (cons 'unquote (cons 'x '())) '()))")
+                .unwrap()
+                .to_string(),
+            "`,x"
+        );
+        assert_eq!(
+            interpreter
+                .eval("(cons 'unquote-splicing (cons 'x '()))")
+                .unwrap()
+                .to_string(),
+            ",@x"
+        );
+        assert_eq!(
+            interpreter.eval("(cons 'quote 'a)").unwrap().to_string(),
+            "(quote . a)"
+        );
+
+        assert_eq!(
+            interpreter.eval("(quote 1 2)").unwrap_err().kind,
+            RuntimeErrorKind::InvalidArity {
+                expected: 1,
+                founded: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_define_macro() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define-macro (if-not condition then else) `(if ,condition ,else ,then))
+                 (define-macro (square x) (list '* x x))
+                 (define-macro inc (lambda (x) `(+ ,x 1)))",
+            )
+            .unwrap();
+
+        // 参数不会被求值，未选中的分支中的错误不会发生
+        assert_eq!(
+            interpreter.eval("(if-not #f 1 undefined)"),
+            Ok(Value::from(Integer::from(1)))
+        );
+        assert_eq!(
+            interpreter.eval("(square (inc 2))"),
+            Ok(Value::from(Integer::from(9)))
+        );
+        assert_eq!(
+            interpreter.eval("square").unwrap().to_string(),
+            "#<macro:square>"
+        );
+        assert_eq!(interpreter.eval("inc").unwrap().to_string(), "#<macro:inc>");
+
+        // 宏不具备卫生性，展开结果中的符号可以捕获使用处的绑定
+        interpreter
+            .eval("(define-macro (with-it body) `(lambda (it) ,body))")
+            .unwrap();
+        assert_eq!(
+            interpreter.eval("((with-it (+ it 1)) 41)"),
+            Ok(Value::from(Integer::from(42)))
+        );
+
+        // 尾部位置的宏调用不会增长调用栈
+        interpreter
+            .eval("(define (count-down n) (if-not (= n 0) (count-down (- n 1)) 'done))")
+            .unwrap();
+        assert_eq!(
+            interpreter.eval("(count-down 20000)"),
+            Ok(Value::Symbol("done".into()))
+        );
+    }
+
+    #[test]
+    fn test_macroexpand() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define-macro (inc x) `(+ ,x 1))
+                 (define-macro (inc-twice x) (list 'inc (list 'inc x)))",
+            )
+            .unwrap();
+
+        assert_eq!(
+            interpreter
+                .eval("(macroexpand-1 '(inc-twice y))")
+                .unwrap()
+                .to_string(),
+            "(inc (inc y))"
+        );
+        assert_eq!(
+            interpreter
+                .eval("(macroexpand '(inc-twice y))")
+                .unwrap()
+                .to_string(),
+            "(+ (inc y) 1)"
+        );
+        assert_eq!(
+            interpreter
+                .eval("(macroexpand '(f (inc y)))")
+                .unwrap()
+                .to_string(),
+            "(f (inc y))"
+        );
+        assert_eq!(
+            interpreter
+                .eval("(macroexpand-1 (list 'inc 2))")
+                .unwrap()
+                .to_string(),
+            "(+ 2 1)"
+        );
+
+        let error = interpreter.eval("(inc 1 2)").unwrap_err();
+        assert_eq!(
+            error.kind,
+            RuntimeErrorKind::InvalidArity {
+                expected: 1,
+                founded: 2
+            }
+        );
+        assert_eq!(error.backtrace[0].to_string(), "inc at 1:1: (inc 1 2)");
+    }
+
+    #[test]
+    fn test_syntax_rules() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define-syntax swap!
+                   (syntax-rules ()
+                     ((_ a b) ((lambda (tmp) (define a b) (define b tmp)) a))))
+                 (define-syntax my-list
+                   (syntax-rules ()
+                     ((_ (name value) ...) (list '(name ...) (list value ...)))))
+                 (define-syntax flatten
+                   (syntax-rules ()
+                     ((_ (a ...) ...) '(a ... ...))))
+                 (define-syntax last-two
+                   (syntax-rules ()
+                     ((_ x ... y z) '(y z))))
+                 (define-syntax dotted
+                   (syntax-rules ()
+                     ((_ a . rest) 'rest)))",
+            )
+            .unwrap();
+
+        assert_eq!(
+            interpreter
+                .eval("(my-list (a 1) (b (+ 1 1)))")
+                .unwrap()
+                .to_string(),
+            "((a b) (1 2))"
+        );
+        assert_eq!(
+            interpreter.eval("(my-list)").unwrap().to_string(),
+            "(() ())"
+        );
+        assert_eq!(
+            interpreter
+                .eval("(flatten (1 2) () (3))")
+                .unwrap()
+                .to_string(),
+            "(1 2 3)"
+        );
+        assert_eq!(
+            interpreter.eval("(last-two 1 2 3 4)").unwrap().to_string(),
+            "(3 4)"
+        );
+        assert_eq!(
+            interpreter.eval("(dotted 1 2 3)").unwrap().to_string(),
+            "(2 3)"
+        );
+        assert_eq!(
+            interpreter.eval("swap!").unwrap().to_string(),
+            "#<syntax:swap!>"
+        );
+
+        let error = interpreter.eval("(last-two 1)").unwrap_err();
+        assert_eq!(
+            error.kind,
+            RuntimeErrorKind::InvalidSyntax(interpreter.eval("'(last-two 1)").unwrap())
+        );
+        assert_eq!(
+            error.backtrace[0].to_string(),
+            "last-two at 1:1: (last-two 1)"
+        );
+
+        // 重复的模式变量与同一层中的多个省略号在定义时就报告错误
+        assert_eq!(
+            interpreter
+                .eval("(define-syntax dup (syntax-rules () ((_ (a a) ...) 'x)))")
+                .unwrap_err()
+                .kind,
+            RuntimeErrorKind::InvalidSyntax(interpreter.eval("'(_ (a a) ...)").unwrap())
+        );
+        assert_eq!(
+            interpreter
+                .eval("(define-syntax two (syntax-rules () ((_ a ... b ...) 'x)))")
+                .unwrap_err()
+                .kind,
+            RuntimeErrorKind::InvalidSyntax(interpreter.eval("'(_ a ... b ...)").unwrap())
+        );
+        assert_eq!(
+            interpreter.eval("(dup (1 2))").unwrap_err().kind,
+            RuntimeErrorKind::UndefinedVariable("dup".to_string())
+        );
+    }
+
+    #[test]
+    fn test_syntax_rules_literals() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define-syntax arrow
+                   (syntax-rules (=>)
+                     ((_ a => b) (list a b))
+                     ((_ a b c) 'no-arrow)))
+                 (define-syntax custom
+                   (syntax-rules ::: ()
+                     ((_ x :::) '(x ::: ...))))",
+            )
+            .unwrap();
+
+        assert_eq!(
+            interpreter.eval("(arrow 1 => 2)").unwrap().to_string(),
+            "(1 2)"
+        );
+        assert_eq!(
+            interpreter.eval("(arrow 1 2 3)"),
+            Ok(Value::Symbol("no-arrow".into()))
+        );
+        assert_eq!(
+            interpreter.eval("(custom 1 2)").unwrap().to_string(),
+            "(1 2 ...)"
+        );
+        // 被局部变量遮蔽的 `=>` 与字面量引用不同的绑定，不能匹配
+        assert_eq!(
+            interpreter.eval("(let ((=> #f)) (arrow 1 => 2))"),
+            Ok(Value::Symbol("no-arrow".into()))
+        );
+    }
+
+    #[test]
+    fn test_syntax_rules_r7rs_examples() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (even? n) (if (= n 0) #t (odd? (- n 1))))
+                 (define (odd? n) (if (= n 0) #f (even? (- n 1))))
+                 (define-syntax my-or
+                   (syntax-rules ()
+                     ((my-or) #f)
+                     ((my-or e) e)
+                     ((my-or e1 e2 ...)
+                      (let ((temp e1))
+                        (if temp temp (my-or e2 ...))))))",
+            )
+            .unwrap();
+
+        // 宏引入的 `let`、`if` 与 `temp` 不受使用处同名变量的影响
+        assert_eq!(
+            interpreter.eval(
+                "(let ((x #f) (y 7) (temp 8) (let odd?) (if even?))
+                   (my-or x (let temp) (if y) y))"
+            ),
+            Ok(Value::from(Integer::from(7)))
+        );
+        assert_eq!(
+            interpreter.eval("(let ((=> #f)) (cond (#t => 'ok)))"),
+            Ok(Value::Symbol("ok".into()))
+        );
+    }
+
+    #[test]
+    fn test_syntax_rules_hygiene() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define-syntax my-or
+                   (syntax-rules ()
+                     ((_) #f)
+                     ((_ e) e)
+                     ((_ e r ...) ((lambda (t) (if t t (my-or r ...))) e))))
+                 (define-syntax first
+                   (syntax-rules ()
+                     ((_ l) (car l))))
+                 (define-syntax define-getter
+                   (syntax-rules ()
+                     ((_ name value) (begin-define name value))))
+                 (define-syntax begin-define
+                   (syntax-rules ()
+                     ((_ name value) (define (name) helper-value))))
+                 (define helper-value 42)",
+            )
+            .unwrap();
+
+        // 宏引入的 `t` 不会捕获使用处的 `t`
+        assert_eq!(
+            interpreter.eval("((lambda (t) (my-or #f t)) 5)"),
+            Ok(Value::from(Integer::from(5)))
+        );
+        assert_eq!(interpreter.eval("(my-or)"), Ok(Value::Bool(false)));
+        // 宏中的 `car` 指向定义处的绑定
+        assert_eq!(
+            interpreter.eval("((lambda (car) (first '(1 2))) 0)"),
+            Ok(Value::from(Integer::from(1)))
+        );
+        // 作为参数传入的标识符在使用处定义
+        interpreter.eval("(define-getter get 1)").unwrap();
+        assert_eq!(
+            interpreter.eval("(get)"),
+            Ok(Value::from(Integer::from(42)))
+        );
+        // 引用模板中的符号得到原本的拼写
+        interpreter
+            .eval("(define-syntax sym (syntax-rules () ((_) 'tmp)))")
+            .unwrap();
+        assert_eq!(interpreter.eval("(sym)"), Ok(Value::Symbol("tmp".into())));
+    }
+
+    #[test]
+    fn test_let_syntax() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval("(define-syntax foo (syntax-rules () ((_) 'outer)))")
+            .unwrap();
+
+        assert_eq!(
+            interpreter.eval(
+                "(let-syntax ((foo (syntax-rules () ((_) 'inner)))
+                              (bar (syntax-rules () ((_) (foo)))))
+                   (list (foo) (bar)))"
+            ),
+            interpreter.eval("'(inner outer)")
+        );
+        assert_eq!(interpreter.eval("(foo)"), Ok(Value::Symbol("outer".into())));
+        assert_eq!(
+            interpreter.eval(
+                "(letrec-syntax ((my-and (syntax-rules ()
+                                           ((_) #t)
+                                           ((_ e) e)
+                                           ((_ e r ...) (if e (my-and r ...) #f)))))
+                   (my-and 1 2 3))"
+            ),
+            Ok(Value::from(Integer::from(3)))
+        );
+        assert_eq!(
+            interpreter.eval("(define-syntax bad 1)").unwrap_err().kind,
+            RuntimeErrorKind::TypeError {
+                expected: "syntax transformer",
+                founded: Value::from(Integer::from(1))
+            }
+        );
+    }
+
+    #[test]
+    fn test_syntax_defining_syntax() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define-syntax define-lister
+                   (syntax-rules ()
+                     ((_ name)
+                      (define-syntax name
+                        (syntax-rules ()
+                          ((_ x (... ...)) (list x (... ...))))))))
+                 (define-lister my-list)",
+            )
+            .unwrap();
+
+        assert_eq!(
+            interpreter.eval("(my-list 1 2 3)").unwrap().to_string(),
+            "(1 2 3)"
+        );
+        assert_eq!(
+            interpreter
+                .eval("(macroexpand-1 '(my-list 1 2))")
+                .unwrap()
+                .to_string(),
+            "(list 1 2)"
+        );
+    }
+
+    #[test]
+    fn test_set() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (make-counter)
+                   ((lambda (count)
+                      (lambda () (set! count (+ count 1)) count))
+                    0))
+                 (define c1 (make-counter))
+                 (define c2 (make-counter))",
+            )
+            .unwrap();
+
+        // 每个闭包修改各自捕获的变量
+        assert_eq!(interpreter.eval("(c1)"), Ok(Value::from(Integer::from(1))));
+        assert_eq!(interpreter.eval("(c1)"), Ok(Value::from(Integer::from(2))));
+        assert_eq!(interpreter.eval("(c2)"), Ok(Value::from(Integer::from(1))));
+
+        // 修改全局变量
+        interpreter
+            .eval("(define total 0) (define (add! n) (set! total (+ total n)))")
+            .unwrap();
+        assert_eq!(interpreter.eval("(add! 5)"), Ok(Value::Void));
+        assert_eq!(
+            interpreter.eval("(add! 2) total"),
+            Ok(Value::from(Integer::from(7)))
+        );
+
+        // 宏引入的 `tmp` 不会与使用处的 `tmp` 冲突
+        interpreter
+            .eval(
+                "(define-syntax swap!
+                   (syntax-rules ()
+                     ((_ a b) ((lambda (tmp) (set! a b) (set! b tmp)) a))))
+                 (define tmp 1)
+                 (define other 2)
+                 (swap! tmp other)",
+            )
+            .unwrap();
+        assert_eq!(
+            interpreter.eval("(list tmp other)").unwrap().to_string(),
+            "(2 1)"
+        );
+
+        assert_eq!(
+            interpreter.eval("(set! undefined 1)"),
+            Err(RuntimeError::new(
+                RuntimeErrorKind::UndefinedVariable("undefined".into()),
+                Some(Span::new(6, 15, 1, 7))
+            ))
+        );
+        assert_eq!(
+            interpreter.eval("(set! 1 2)").unwrap_err().kind,
+            RuntimeErrorKind::TypeError {
+                expected: "symbol",
+                founded: Value::from(Integer::from(1))
+            }
+        );
+    }
+
+    #[test]
+    fn test_redefine() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define x 1)
+                 (define (get-x) x)
+                 (define (shadow-x) (define x 10) x)",
+            )
+            .unwrap();
+
+        // 在过程体中定义的变量只遮蔽外层的绑定
+        assert_eq!(
+            interpreter.eval("(shadow-x)"),
+            Ok(Value::from(Integer::from(10)))
+        );
+        assert_eq!(interpreter.eval("x"), Ok(Value::from(Integer::from(1))));
+
+        // 在顶层重新定义会替换全局变量，引用它的闭包看到新的值
+        interpreter.eval("(define x 2)").unwrap();
+        assert_eq!(
+            interpreter.eval("(get-x)"),
+            Ok(Value::from(Integer::from(2)))
+        );
+        interpreter.eval("(define (get-x) (* x 3))").unwrap();
+        assert_eq!(
+            interpreter.eval("(get-x)"),
+            Ok(Value::from(Integer::from(6)))
+        );
+    }
+
+    #[test]
+    fn test_let() {
+        let interpreter = Interpreter::new();
+        interpreter.eval("(define x 1)").unwrap();
+
+        assert_eq!(
+            interpreter.eval("(let ((x 2) (y x)) (list x y))"),
+            interpreter.eval("'(2 1)")
+        );
+        assert_eq!(
+            interpreter.eval("(let* ((x 2) (y x)) (list x y))"),
+            interpreter.eval("'(2 2)")
+        );
+        assert_eq!(
+            interpreter.eval("(let () 5)"),
+            Ok(Value::from(Integer::from(5)))
+        );
+        assert_eq!(interpreter.eval("x"), Ok(Value::from(Integer::from(1))));
+
+        // `let*` 中的闭包捕获的是当时的绑定
+        assert_eq!(
+            interpreter.eval("(let* ((x 2) (f (lambda () x)) (x 3)) (list (f) x))"),
+            interpreter.eval("'(2 3)")
+        );
+
+        assert_eq!(
+            interpreter.eval("(let ((x)) x)").unwrap_err().kind,
+            RuntimeErrorKind::InvalidSyntax(interpreter.eval("'(let ((x)) x)").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_letrec() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(
+            interpreter.eval(
+                "(letrec ((even? (lambda (n) (if (= n 0) #t (odd? (- n 1)))))
+                          (odd? (lambda (n) (if (= n 0) #f (even? (- n 1))))))
+                   (list (even? 1000) (odd? 7)))"
+            ),
+            interpreter.eval("'(#t #t)")
+        );
+        assert_eq!(
+            interpreter.eval("(letrec* ((a 1) (b (+ a 1))) (list a b))"),
+            interpreter.eval("'(1 2)")
+        );
+        assert_eq!(
+            interpreter.eval("(letrec ((a 1) (b (+ a 1))) b)"),
+            Err(RuntimeError::new(
+                RuntimeErrorKind::UndefinedVariable("a".into()),
+                Some(Span::new(21, 22, 1, 22))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_named_let() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(
+            interpreter.eval(
+                "(let loop ((i 0) (acc '()))
+                   (if (= i 3) acc (loop (+ i 1) (cons i acc))))"
+            ),
+            interpreter.eval("'(2 1 0)")
+        );
+        // 循环在常数栈空间中执行
+        assert_eq!(
+            interpreter.eval("(let loop ((n 100000)) (if (= n 0) 'done (loop (- n 1))))"),
+            Ok(Value::Symbol("done".into()))
+        );
+        // 循环名称只在过程体中可见
+        interpreter.eval("(let loop () 1)").unwrap();
+        assert_eq!(
+            interpreter.eval("loop").unwrap_err().kind,
+            RuntimeErrorKind::UndefinedVariable("loop".into())
+        );
+    }
+
+    #[test]
+    fn test_if() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(
+            interpreter.eval("(if #t 1)"),
+            Ok(Value::from(Integer::from(1)))
+        );
+        assert_eq!(interpreter.eval("(if #f 1)"), Ok(Value::Void));
+        assert_eq!(
+            interpreter.eval("(if '() 1 2)"),
+            Ok(Value::from(Integer::from(1)))
+        );
+        assert_eq!(
+            interpreter.eval("(if 1)").unwrap_err().kind,
+            RuntimeErrorKind::InvalidSyntax(interpreter.eval("'(if 1)").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_cond() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (classify n)
+                   (cond ((= n 0) 'zero)
+                         ((= n 1))
+                         ((car (list n)) => (lambda (x) (list 'other x)))
+                         (else 'unreachable)))",
+            )
+            .unwrap();
+
+        assert_eq!(
+            interpreter.eval("(classify 0)"),
+            Ok(Value::Symbol("zero".into()))
+        );
+        assert_eq!(interpreter.eval("(classify 1)"), Ok(Value::Bool(true)));
+        assert_eq!(
+            interpreter.eval("(classify 5)").unwrap().to_string(),
+            "(other 5)"
+        );
+        assert_eq!(
+            interpreter.eval("(cond (#f 1) (else 2 3))"),
+            Ok(Value::from(Integer::from(3)))
+        );
+        assert_eq!(interpreter.eval("(cond (#f 1))"), Ok(Value::Void));
+        // 被局部变量遮蔽的 `else` 只是普通的测试
+        assert_eq!(
+            interpreter.eval("(let ((else #f)) (cond (else 1)))"),
+            Ok(Value::Void)
+        );
+    }
+
+    #[test]
+    fn test_keyword_shadowing() {
+        let interpreter = Interpreter::new();
+
+        // 关键字与其他标识符一样可以被局部变量遮蔽
+        assert_eq!(
+            interpreter
+                .eval("(let ((if list)) (if 1 2 3))")
+                .unwrap()
+                .to_string(),
+            "(1 2 3)"
+        );
+        assert_eq!(
+            interpreter.eval("((lambda (define) (define 1)) -)"),
+            Ok(Value::from(Integer::from(-1)))
+        );
+        // 引用的关键字是普通的符号
+        assert_eq!(
+            interpreter.eval("'lambda"),
+            Ok(Value::Symbol("lambda".into()))
+        );
+        assert_eq!(
+            interpreter.eval("(eq? (car ''a) 'quote)"),
+            Ok(Value::Bool(true))
+        );
+    }
+
+    #[test]
+    fn test_case() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (kind x)
+                   (case x
+                     ((1 2 3) 'small)
+                     ((a b) 'symbol)
+                     ((()) 'empty)
+                     (else => (lambda (x) (list 'unknown x)))))",
+            )
+            .unwrap();
+
+        assert_eq!(
+            interpreter.eval("(kind 2)"),
+            Ok(Value::Symbol("small".into()))
+        );
+        assert_eq!(
+            interpreter.eval("(kind 'b)"),
+            Ok(Value::Symbol("symbol".into()))
+        );
+        assert_eq!(
+            interpreter.eval("(kind '())"),
+            Ok(Value::Symbol("empty".into()))
+        );
+        assert_eq!(
+            interpreter.eval("(kind 9)").unwrap().to_string(),
+            "(unknown 9)"
+        );
+        assert_eq!(interpreter.eval("(case 1 ((2) 'two))"), Ok(Value::Void));
+    }
+
+    #[test]
+    fn test_and_or() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(interpreter.eval("(and)"), Ok(Value::Bool(true)));
+        assert_eq!(interpreter.eval("(or)"), Ok(Value::Bool(false)));
+        assert_eq!(
+            interpreter.eval("(and 1 2 3)"),
+            Ok(Value::from(Integer::from(3)))
+        );
+        assert_eq!(
+            interpreter.eval("(or #f 2 3)"),
+            Ok(Value::from(Integer::from(2)))
+        );
+        // 短路求值，之后的表达式不会被求值
+        assert_eq!(
+            interpreter.eval("(and 1 #f undefined)"),
+            Ok(Value::Bool(false))
+        );
+        assert_eq!(
+            interpreter.eval("(or #f 1 undefined)"),
+            Ok(Value::from(Integer::from(1)))
+        );
+    }
+
+    #[test]
+    fn test_when_unless_begin() {
+        let interpreter = Interpreter::new();
+        interpreter.eval("(define x 0)").unwrap();
+
+        assert_eq!(
+            interpreter.eval("(when (= x 0) (set! x 1) (+ x 1))"),
+            Ok(Value::from(Integer::from(2)))
+        );
+        assert_eq!(
+            interpreter.eval("(when (= x 0) undefined)"),
+            Ok(Value::Void)
+        );
+        assert_eq!(
+            interpreter.eval("(unless (= x 0) 'changed)"),
+            Ok(Value::Symbol("changed".into()))
+        );
+        assert_eq!(
+            interpreter.eval("(unless (= x 1) undefined)"),
+            Ok(Value::Void)
+        );
+        assert_eq!(
+            interpreter.eval("(begin (define y 2) (set! x (+ x y)) x)"),
+            Ok(Value::from(Integer::from(3)))
+        );
+        assert_eq!(interpreter.eval("y"), Ok(Value::from(Integer::from(2))));
+        assert_eq!(interpreter.eval("(begin)"), Ok(Value::Void));
+    }
+
+    #[test]
+    fn test_conditional_tail_position() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (count-cond n) (cond ((= n 0) 'done) (else (count-cond (- n 1)))))
+                 (define (count-case n) (case n ((0) 'done) (else (count-case (- n 1)))))
+                 (define (count-and n) (and #t (if (= n 0) 'done (count-and (- n 1)))))
+                 (define (count-or n) (or (= n 0) (count-or (- n 1))))
+                 (define (count-when n) (when #t (begin (if (= n 0) 'done (count-when (- n 1))))))
+                 (define (count-unless n) (unless (= n 0) (count-unless (- n 1))))",
+            )
+            .unwrap();
+
+        for name in ["count-cond", "count-case", "count-and", "count-when"] {
+            assert_eq!(
+                interpreter.eval(&format!("({name} 100000)")),
+                Ok(Value::Symbol("done".into()))
+            );
+        }
+        assert_eq!(interpreter.eval("(count-or 100000)"), Ok(Value::Bool(true)));
+        assert_eq!(interpreter.eval("(count-unless 100000)"), Ok(Value::Void));
+    }
+
+    #[test]
+    fn test_rest_params() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (f a b . rest) (list a b rest))
+                 (define g (lambda args args))
+                 (define-macro (my-begin . body) `((lambda () ,@body)))",
+            )
+            .unwrap();
+
+        assert_eq!(
+            interpreter.eval("(f 1 2 3 4)").unwrap().to_string(),
+            "(1 2 (3 4))"
+        );
+        assert_eq!(interpreter.eval("(f 1 2)").unwrap().to_string(), "(1 2 ())");
+        assert_eq!(interpreter.eval("(g)").unwrap().to_string(), "()");
+        assert_eq!(
+            interpreter.eval("(g 1 2 3)").unwrap().to_string(),
+            "(1 2 3)"
+        );
+        assert_eq!(
+            interpreter.eval("(my-begin (define x 1) (+ x 1))"),
+            Ok(Value::from(Integer::from(2)))
+        );
+        // 剩余参数是可以修改的序对
+        assert_eq!(
+            interpreter
+                .eval("((lambda xs (set-car! xs 0) xs) 1 2)")
+                .unwrap()
+                .to_string(),
+            "(0 2)"
+        );
+
+        let error = interpreter.eval("(f 1)").unwrap_err();
+        assert_eq!(
+            error.kind,
+            RuntimeErrorKind::ArityMismatch {
+                expected: vec![Arity { min: 2, max: None }],
+                founded: 1
+            }
+        );
+        assert_eq!(
+            error.kind.to_string(),
+            "Invalid arity: expected at least 2 arguments, but found 1"
+        );
+    }
+
+    #[test]
+    fn test_optional_params() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (f a #!optional (b (+ a 1)) c) (list a b c))
+                 (define (g a #!key (b 2) (c (* b 10))) (list a b c))
+                 (define (h #!key x #!rest rest) (list x rest))",
+            )
+            .unwrap();
+
+        assert_eq!(interpreter.eval("(f 1)").unwrap().to_string(), "(1 2 #f)");
+        assert_eq!(
+            interpreter.eval("(f 1 5 6)").unwrap().to_string(),
+            "(1 5 6)"
+        );
+        assert_eq!(interpreter.eval("(g 1)").unwrap().to_string(), "(1 2 20)");
+        assert_eq!(
+            interpreter.eval("(g 1 #:c 3 #:b 4)").unwrap().to_string(),
+            "(1 4 3)"
+        );
+        assert_eq!(
+            interpreter.eval("(g 1 #:b 4)").unwrap().to_string(),
+            "(1 4 40)"
+        );
+        assert_eq!(
+            interpreter.eval("(h #:x 1)").unwrap().to_string(),
+            "(1 (#:x 1))"
+        );
+
+        let error = interpreter.eval("(f 1 2 3 4)").unwrap_err();
+        assert_eq!(
+            error.kind.to_string(),
+            "Invalid arity: expected 1 to 3 arguments, but found 4"
+        );
+        assert_eq!(
+            interpreter.eval("(g 1 #:d 3)").unwrap_err().kind,
+            RuntimeErrorKind::TypeError {
+                expected: "keyword argument",
+                founded: Value::Symbol("#:d".into())
+            }
+        );
+        assert_eq!(
+            interpreter.eval("(g 1 #:b)").unwrap_err().kind,
+            RuntimeErrorKind::TypeError {
+                expected: "keyword argument",
+                founded: Value::Symbol("#:b".into())
+            }
+        );
+    }
+
+    #[test]
+    fn test_case_lambda() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define area
+                   (case-lambda
+                     ((r) (* 3 r r))
+                     ((w h) (* w h))
+                     ((a b c . rest) (list a b c rest))))",
+            )
+            .unwrap();
+
+        assert_eq!(
+            interpreter.eval("(area 2)"),
+            Ok(Value::from(Integer::from(12)))
+        );
+        assert_eq!(
+            interpreter.eval("(area 2 5)"),
+            Ok(Value::from(Integer::from(10)))
+        );
+        assert_eq!(
+            interpreter.eval("(area 1 2 3 4)").unwrap().to_string(),
+            "(1 2 3 (4))"
+        );
+        assert_eq!(
+            interpreter.eval("area").unwrap().to_string(),
+            "#<procedure>"
+        );
+
+        let error = interpreter.eval("(area)").unwrap_err();
+        assert_eq!(
+            error.kind,
+            RuntimeErrorKind::ArityMismatch {
+                expected: vec![
+                    Arity::exact(1),
+                    Arity::exact(2),
+                    Arity { min: 3, max: None }
+                ],
+                founded: 0
+            }
+        );
+        assert_eq!(
+            error.kind.to_string(),
+            "Invalid arity: expected 1 or 2 or at least 3 arguments, but found 0"
+        );
+        // 没有分支的 `case-lambda` 不接受任何调用
+        assert_eq!(
+            interpreter.eval("((case-lambda))").unwrap_err().kind,
+            RuntimeErrorKind::ArityMismatch {
+                expected: vec![],
+                founded: 0
+            }
+        );
+    }
+    #[test]
+    fn test_call_cc() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(
+            interpreter.eval("(+ 1 (call/cc (lambda (k) 2)))"),
+            Ok(Value::from(Integer::from(3)))
+        );
+        // 调用续延时放弃当前的计算，直接从 `call/cc` 返回
+        assert_eq!(
+            interpreter.eval("(+ 1 (call-with-current-continuation (lambda (k) (* 10 (k 2)))))"),
+            Ok(Value::from(Integer::from(3)))
+        );
+        assert_eq!(
+            interpreter
+                .eval(
+                    "(define (find-first pred items)
+                       (call/cc
+                         (lambda (return)
+                           (let loop ((items items))
+                             (if (null? items)
+                                 #f
+                                 (begin
+                                   (when (pred (car items)) (return (car items)))
+                                   (loop (cdr items))))))))
+                     (find-first (lambda (x) (= (* x x) 16)) '(1 2 3 4 5))",
+                )
+                .unwrap()
+                .to_string(),
+            "4"
+        );
+        assert_eq!(
+            interpreter
+                .eval("(call/cc (lambda (k) k))")
+                .unwrap()
+                .to_string(),
+            "#<continuation>"
+        );
+        assert_eq!(
+            interpreter
+                .eval("(call/cc (lambda (k) (k 1 2)))")
+                .unwrap_err()
+                .kind,
+            RuntimeErrorKind::InvalidArity {
+                expected: 1,
+                founded: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_call_cc_reentry() {
+        let interpreter = Interpreter::new();
+
+        // 保存的续延可以在 `call/cc` 返回之后多次重新进入
+        interpreter
+            .eval("(define saved #f) (define count 0)")
+            .unwrap();
+        assert_eq!(
+            interpreter.eval("(+ 100 (call/cc (lambda (k) (set! saved k) 1)))"),
+            Ok(Value::from(Integer::from(101)))
+        );
+        assert_eq!(
+            interpreter.eval("(saved 5)"),
+            Ok(Value::from(Integer::from(105)))
+        );
+        assert_eq!(
+            interpreter.eval("(saved 7)"),
+            Ok(Value::from(Integer::from(107)))
+        );
+        assert_eq!(
+            interpreter.eval(
+                "(let ((k (call/cc (lambda (k) k))))
+                   (set! count (+ count 1))
+                   (if (= count 3) count (k k)))"
+            ),
+            Ok(Value::from(Integer::from(3)))
+        );
+
+        // 顶层 `begin` 中捕获的续延包含之后的表达式
+        interpreter.eval("(define m 3) (define k3 #f)").unwrap();
+        assert_eq!(
+            interpreter.eval(
+                "(begin (call/cc (lambda (k) (set! k3 k))) (set! m (+ m 1)) (if (= m 6) m (k3 0)))"
+            ),
+            Ok(Value::from(Integer::from(6)))
+        );
+
+        // 生成器在每次产生元素时保存自己的续延，下一次调用时从那里继续
+        interpreter
+            .eval(
+                "(define (make-generator items)
+                   (define return #f)
+                   (define resume #f)
+                   (lambda ()
+                     (call/cc
+                       (lambda (r)
+                         (set! return r)
+                         (if resume
+                             (resume #f)
+                             (let loop ((items items))
+                               (if (null? items)
+                                   (return 'done)
+                                   (begin
+                                     (call/cc
+                                       (lambda (k)
+                                         (set! resume k)
+                                         (return (car items))))
+                                     (loop (cdr items))))))))))
+                 (define next (make-generator '(a b c)))",
+            )
+            .unwrap();
+        assert_eq!(
+            interpreter
+                .eval("(list (next) (next) (next) (next) (next))")
+                .unwrap()
+                .to_string(),
+            "(a b c done done)"
+        );
+    }
+
+    #[test]
+    fn test_dynamic_wind() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define trace '())
+                 (define (note x) (set! trace (cons x trace)))
+                 (define (wind name thunk)
+                   (dynamic-wind
+                     (lambda () (note (list 'in name)))
+                     thunk
+                     (lambda () (note (list 'out name)))))",
+            )
+            .unwrap();
+
+        assert_eq!(
+            interpreter
+                .eval("(wind 'a (lambda () (note 'body) 'result))")
+                .unwrap()
+                .to_string(),
+            "result"
+        );
+        assert_eq!(
+            interpreter.eval("trace").unwrap().to_string(),
+            "((out a) body (in a))"
+        );
+
+        // 跳出动态范围时由内向外调用 `after`
+        interpreter.eval("(set! trace '())").unwrap();
+        assert_eq!(
+            interpreter
+                .eval(
+                    "(call/cc
+                       (lambda (k)
+                         (wind 'a (lambda () (wind 'b (lambda () (k 'escaped) (note 'unreachable)))))))",
+                )
+                .unwrap()
+                .to_string(),
+            "escaped"
+        );
+        assert_eq!(
+            interpreter.eval("trace").unwrap().to_string(),
+            "((out a) (out b) (in b) (in a))"
+        );
+
+        // 重新进入动态范围时由外向内调用 `before`
+        interpreter
+            .eval("(define k #f) (define n 0) (set! trace '())")
+            .unwrap();
+        assert_eq!(
+            interpreter.eval(
+                "(wind 'a (lambda ()
+                   (wind 'b (lambda ()
+                     (call/cc (lambda (c) (set! k c)))
+                     (set! n (+ n 1))
+                     n))))"
+            ),
+            Ok(Value::from(Integer::from(1)))
+        );
+        assert_eq!(
+            interpreter.eval("(k #f)"),
+            Ok(Value::from(Integer::from(2)))
+        );
+        assert_eq!(
+            interpreter.eval("trace").unwrap().to_string(),
+            "((out a) (out b) (in b) (in a) (out a) (out b) (in b) (in a))"
+        );
+    }
+
+    #[test]
+    fn test_call_ec() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(
+            interpreter.eval("(+ 1 (call/ec (lambda (return) (return 41) 0)))"),
+            Ok(Value::from(Integer::from(42)))
+        );
+        assert_eq!(
+            interpreter.eval("(call-with-escape-continuation (lambda (return) 'normal))"),
+            Ok(Value::Symbol("normal".into()))
+        );
+        assert_eq!(
+            interpreter
+                .eval(
+                    "(define trace '())
+                     (call/ec
+                       (lambda (return)
+                         (dynamic-wind
+                           (lambda () (set! trace (cons 'in trace)))
+                           (lambda () (return 'escaped))
+                           (lambda () (set! trace (cons 'out trace))))))",
+                )
+                .unwrap()
+                .to_string(),
+            "escaped"
+        );
+        assert_eq!(interpreter.eval("trace").unwrap().to_string(), "(out in)");
+
+        // `call/ec` 返回之后逃逸续延不再有效
+        interpreter
+            .eval("(define saved (call/ec (lambda (k) k)))")
+            .unwrap();
+        assert_eq!(
+            interpreter.eval("(saved 1)").unwrap_err().kind,
+            RuntimeErrorKind::ExpiredContinuation
+        );
+    }
+
+    #[test]
+    fn test_deep_recursion() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (count n) (if (= n 0) 0 (+ 1 (count (- n 1)))))
+                 (define (product items)
+                   (call/ec
+                     (lambda (return)
+                       (let loop ((items items))
+                         (cond ((null? items) 1)
+                               ((= (car items) 0) (return 0))
+                               (else (* (car items) (loop (cdr items)))))))))",
+            )
+            .unwrap();
+
+        // 非尾调用的递归不占用 Rust 栈
+        assert_eq!(
+            interpreter.eval("(count 100000)"),
+            Ok(Value::from(Integer::from(100_000)))
+        );
+        assert_eq!(
+            interpreter.eval("(product '(1 2 3 0 4))"),
+            Ok(Value::from(Integer::from(0)))
+        );
+        assert_eq!(
+            interpreter.eval("(product '(1 2 3 4))"),
+            Ok(Value::from(Integer::from(24)))
+        );
+    }
+
+    #[test]
+    fn test_guard() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(
+            interpreter.eval("(guard (e (#t (list 'caught e))) (+ 1 (raise 'oops)))"),
+            interpreter.eval("'(caught oops)")
+        );
+        assert_eq!(
+            interpreter.eval(
+                "(guard (e ((pair? e) 'pair)
+                           ((null? e) 'null)
+                           (else 'other))
+                   (raise 42))"
+            ),
+            Ok(Value::Symbol("other".into()))
+        );
+        // 子句可以使用 `=>`，过程体正常返回时结果就是过程体的值
+        assert_eq!(
+            interpreter.eval("(guard (e ((car e) => (lambda (x) (* x 2)))) (raise (list 21)))"),
+            Ok(Value::from(Integer::from(42)))
+        );
+        assert_eq!(
+            interpreter.eval("(guard (e (#t 'caught)) 1 2 3)"),
+            Ok(Value::from(Integer::from(3)))
+        );
+
+        // 没有子句成立时交给外层的处理器
+        assert_eq!(
+            interpreter.eval(
+                "(guard (outer (#t (list 'outer outer)))
+                   (guard (inner ((= inner 1) 'inner))
+                     (raise 2)))"
+            ),
+            interpreter.eval("'(outer 2)")
+        );
+        assert_eq!(
+            interpreter
+                .eval("(guard (e ((= e 1) 'one)) (raise 2))")
+                .unwrap_err()
+                .kind,
+            RuntimeErrorKind::Raise(Value::from(Integer::from(2)))
+        );
+
+        // 离开过程体时调用其中动态范围的后置过程
+        assert_eq!(
+            interpreter
+                .eval(
+                    "(define trace '())
+                     (guard (e (#t (append trace (list e))))
+                       (dynamic-wind
+                         (lambda () (set! trace (append trace '(in))))
+                         (lambda () (raise 'error))
+                         (lambda () (set! trace (append trace '(out))))))"
+                )
+                .unwrap()
+                .to_string(),
+            "(in out error)"
+        );
+    }
+
+    #[test]
+    fn test_with_exception_handler() {
+        let interpreter = Interpreter::new();
+
+        // `raise-continuable` 的处理器的返回值作为 `raise-continuable` 的返回值
+        assert_eq!(
+            interpreter.eval(
+                "(with-exception-handler
+                   (lambda (e) (* e 10))
+                   (lambda () (+ 1 (raise-continuable 4))))"
+            ),
+            Ok(Value::from(Integer::from(41)))
+        );
+        // 处理器在外层处理器的范围内运行
+        assert_eq!(
+            interpreter.eval(
+                "(with-exception-handler
+                   (lambda (e) (list 'outer e))
+                   (lambda ()
+                     (with-exception-handler
+                       (lambda (e) (raise-continuable (list 'inner e)))
+                       (lambda () (raise-continuable 'x)))))"
+            ),
+            interpreter.eval("'(outer (inner x))")
+        );
+        // `raise` 的处理器返回后，引发的值交给外层的处理器
+        assert_eq!(
+            interpreter
+                .eval(
+                    "(define log '())
+                     (guard (e (#t (cons e log)))
+                       (with-exception-handler
+                         (lambda (e) (set! log (cons 'handled log)))
+                         (lambda () (raise 'boom) 'unreachable)))"
+                )
+                .unwrap()
+                .to_string(),
+            "(boom handled)"
+        );
+        // 处理器通过续延提前返回
+        assert_eq!(
+            interpreter.eval(
+                "(call/cc
+                   (lambda (k)
+                     (with-exception-handler
+                       (lambda (e) (k (list 'recovered e)))
+                       (lambda () (car 1)))))"
+            ),
+            interpreter.eval("(list 'recovered (guard (e (#t e)) (car 1)))")
+        );
+        assert_eq!(
+            interpreter
+                .eval("(with-exception-handler (lambda (e) 0) (lambda () (raise 'boom)))")
+                .unwrap_err()
+                .kind,
+            RuntimeErrorKind::Raise(Value::Symbol("boom".into()))
+        );
+        // 安装处理器时就检查处理器与过程的类型
+        assert_eq!(
+            interpreter
+                .eval("(with-exception-handler 1 (lambda () 'ok))")
+                .unwrap_err()
+                .kind,
+            RuntimeErrorKind::TypeError {
+                expected: "procedure",
+                founded: Value::from(Integer::from(1)),
+            }
+        );
+        assert_eq!(
+            interpreter
+                .eval("(with-exception-handler car 'thunk)")
+                .unwrap_err()
+                .kind,
+            RuntimeErrorKind::TypeError {
+                expected: "procedure",
+                founded: Value::Symbol("thunk".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_error_objects() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (describe thunk)
+                   (guard (e ((error-object? e)
+                              (list (error-object-message e) (error-object-irritants e)))
+                             (else (list 'raised e)))
+                     (thunk)))",
+            )
+            .unwrap();
+
+        assert_eq!(
+            interpreter
+                .eval("(describe (lambda () (error \"bad thing\" 1 'two)))")
+                .unwrap()
+                .to_string(),
+            "(\"bad thing\" (1 two))"
+        );
+        assert_eq!(
+            interpreter
+                .eval("(describe (lambda () (raise 'symbol)))")
+                .unwrap()
+                .to_string(),
+            "(raised symbol)"
+        );
+
+        // 内置错误被转换为错误对象
+        assert_eq!(
+            interpreter
+                .eval("(describe (lambda () (/ 1 0)))")
+                .unwrap()
+                .to_string(),
+            "(\"DivideByZero\" ())"
+        );
+        assert_eq!(
+            interpreter
+                .eval("(describe (lambda () (car 1)))")
+                .unwrap()
+                .to_string(),
+            "(\"TypeError: expected pair, found 1\" (1))"
+        );
+        assert_eq!(
+            interpreter
+                .eval("(describe (lambda () ((lambda (x) x))))")
+                .unwrap()
+                .to_string(),
+            "(\"Invalid arity: expected 1 arguments, but found 0\" ())"
+        );
+        assert_eq!(
+            interpreter.eval("(error-object? (guard (e (#t e)) undefined))"),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(
+            interpreter.eval("(error-object? 'symbol)"),
+            Ok(Value::Bool(false))
+        );
+
+        // 没有被处理的错误对象还原为原本的错误
+        assert_eq!(
+            interpreter
+                .eval("(error \"bad thing\" 1)")
+                .unwrap_err()
+                .kind,
+            RuntimeErrorKind::Error {
+                message: "bad thing".to_string(),
+                irritants: vec![Value::from(Integer::from(1))],
+            }
+        );
+        assert_eq!(
+            interpreter
+                .eval("(raise (guard (e (#t e)) (/ 1 0)))")
+                .unwrap_err()
+                .kind,
+            RuntimeErrorKind::DivideByZero
+        );
+        assert_eq!(
+            interpreter.eval("(error 'oops)").unwrap_err().kind,
+            RuntimeErrorKind::TypeError {
+                expected: "string",
+                founded: Value::Symbol("oops".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_eq_identity() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval(
+                "(define (counter) (let ((n 0)) (lambda () (set! n (+ n 1)) n)))
+                 (define c1 (counter))
+                 (define c2 (counter))
+                 (define cases (case-lambda ((x) x) ((x y) y)))
+                 (define (make-error) (guard (e (#t e)) (error \"boom\")))
+                 (define err (make-error))",
+            )
+            .unwrap();
+
+        // 分别创建的过程各自拥有状态，不是同一个对象
+        assert_eq!(interpreter.eval("(eq? c1 c2)"), Ok(Value::Bool(false)));
+        assert_eq!(interpreter.eval("(eq? c1 c1)"), Ok(Value::Bool(true)));
+        assert_eq!(interpreter.eval("(eq? car car)"), Ok(Value::Bool(true)));
+        assert_eq!(interpreter.eval("(eq? cases cases)"), Ok(Value::Bool(true)));
+        assert_eq!(
+            interpreter.eval("(eq? (case-lambda ((x) x)) (case-lambda ((x) x)))"),
+            Ok(Value::Bool(false))
+        );
+        assert_eq!(
+            interpreter.eval("(eq? (make-error) (make-error))"),
+            Ok(Value::Bool(false))
+        );
+        assert_eq!(interpreter.eval("(eq? err err)"), Ok(Value::Bool(true)));
+        assert_eq!(
+            interpreter.eval("(call/cc (lambda (k) (eq? k k)))"),
+            Ok(Value::Bool(true))
+        );
+
+        // 精确数与非精确数不是同一个对象
+        assert_eq!(interpreter.eval("(eq? 1 1)"), Ok(Value::Bool(true)));
+        assert_eq!(interpreter.eval("(eq? 1 1.0)"), Ok(Value::Bool(false)));
+        assert_eq!(interpreter.eval("(eq? 1/2 0.5)"), Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn test_symbol_interning() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(interpreter.eval("(eq? 'abc 'abc)"), Ok(Value::Bool(true)));
+        assert_eq!(interpreter.eval("(eq? 'abc 'abd)"), Ok(Value::Bool(false)));
+        assert_eq!(
+            interpreter.eval("(eq? (string->symbol \"abc\") 'abc)"),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(
+            interpreter.eval("(symbol->string (string->symbol \"hello world\"))"),
+            Ok(Value::String("hello world".into()))
+        );
+        assert_eq!(
+            interpreter.eval("(eq? (string->symbol (symbol->string 'xyz)) 'xyz)"),
+            Ok(Value::Bool(true))
+        );
+
+        // 序对按身份比较
+        assert_eq!(
+            interpreter.eval("(define p (cons 1 2)) (eq? p p)"),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(
+            interpreter.eval("(eq? (cons 1 2) (cons 1 2))"),
+            Ok(Value::Bool(false))
+        );
+
+        assert_eq!(
+            interpreter.eval("(string->symbol 'abc)").unwrap_err().kind,
+            RuntimeErrorKind::TypeError {
+                expected: "string",
+                founded: Value::Symbol("abc".into()),
+            }
+        );
+        assert_eq!(
+            interpreter
+                .eval("(symbol->string \"abc\")")
+                .unwrap_err()
+                .kind,
+            RuntimeErrorKind::TypeError {
+                expected: "symbol",
+                founded: Value::String("abc".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_shared_lists() {
+        let interpreter = Interpreter::new();
+        interpreter.eval("(define l '(1 2 3))").unwrap();
+
+        // 列表的尾部与原列表共享元素
+        assert_eq!(interpreter.eval("(cdr (cdr l))"), interpreter.eval("'(3)"));
+        assert_eq!(
+            interpreter.eval("(eq? (cdr l) (cdr l))"),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(interpreter.eval("(eq? l l)"), Ok(Value::Bool(true)));
+        assert_eq!(interpreter.eval("(eq? '(1) '(1))"), Ok(Value::Bool(false)));
+        assert_eq!(
+            interpreter.eval("(eq? '() (cdr '(1)))"),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(interpreter.eval("l").unwrap().to_string(), "(1 2 3)");
+    }
+
+    #[test]
+    fn test_fixnum_overflow() {
+        let interpreter = Interpreter::new();
+        let big = || Integer::from(i64::MAX) + 1;
+
+        // 溢出时提升为任意精度的整数
+        let sum = interpreter.eval("(+ 9223372036854775807 1)").unwrap();
+        assert_eq!(sum, Value::from(big()));
+        assert!(matches!(sum, Value::Numeric(Numeric::Integer(_))));
+        assert_eq!(
+            interpreter.eval("(* -9223372036854775808 -1)"),
+            Ok(Value::from(big()))
+        );
+        assert_eq!(
+            interpreter.eval("(/ -9223372036854775808 -1)"),
+            Ok(Value::from(big()))
+        );
+        assert_eq!(
+            interpreter.eval("(- -9223372036854775808 1)"),
+            Ok(Value::from(Integer::from(i64::MIN) - 1))
+        );
+
+        // 结果重新落入机器字长的范围时降回
+        let difference = interpreter
+            .eval("(- (+ 9223372036854775807 10) 20)")
+            .unwrap();
+        assert_eq!(difference, Value::from(Integer::from(i64::MAX - 10)));
+        assert!(matches!(difference, Value::Numeric(Numeric::Fixnum(_))));
+        assert!(matches!(
+            interpreter.eval("100000000000000000000").unwrap(),
+            Value::Numeric(Numeric::Integer(_))
+        ));
+        assert_eq!(
+            interpreter.eval("(= (+ 9223372036854775807 1) 9223372036854775808)"),
+            Ok(Value::Bool(true))
+        );
+    }
+
+    #[test]
+    fn test_rationals() {
+        let interpreter = Interpreter::new();
+
+        // 整数相除的结果是精确的
+        let half = interpreter.eval("(/ 7 2)").unwrap();
+        assert_eq!(half, Value::from(Rational::from((7, 2))));
+        assert!(matches!(half, Value::Numeric(Numeric::Rational(_))));
+        assert_eq!(half.to_string(), "7/2");
+        assert!(matches!(
+            interpreter.eval("(/ 6 3)").unwrap(),
+            Value::Numeric(Numeric::Fixnum(2))
+        ));
+        assert_eq!(
+            interpreter.eval("(+ 1/2 1/3)"),
+            Ok(Value::from(Rational::from((5, 6))))
+        );
+        // 分母为 1 时化为整数
+        assert!(matches!(
+            interpreter.eval("(* 3/4 4/3)").unwrap(),
+            Value::Numeric(Numeric::Fixnum(1))
+        ));
+        assert!(matches!(
+            interpreter.eval("(+ 1/2 1/2)").unwrap(),
+            Value::Numeric(Numeric::Fixnum(1))
+        ));
+        assert_eq!(
+            interpreter.eval("(- 1 6/4)"),
+            Ok(Value::from(Rational::from((-1, 2))))
+        );
+
+        // 与浮点数运算的结果不精确
+        assert_eq!(
+            interpreter.eval("(+ 1/2 0.25)"),
+            Ok(Value::from(Float::with_val(53, 0.75)))
+        );
+        assert!(matches!(
+            interpreter.eval("(* 1/2 2.0)").unwrap(),
+            Value::Numeric(Numeric::Float(_))
+        ));
+        assert_eq!(interpreter.eval("(= 1/2 0.5)"), Ok(Value::Bool(true)));
+        assert_eq!(interpreter.eval("(= 1/3 2/6)"), Ok(Value::Bool(true)));
+
+        assert_eq!(
+            interpreter.eval("(numerator 6/4)"),
+            Ok(Value::from(Integer::from(3)))
+        );
+        assert_eq!(
+            interpreter.eval("(denominator 6/4)"),
+            Ok(Value::from(Integer::from(2)))
+        );
+        assert_eq!(
+            interpreter.eval("(denominator 5)"),
+            Ok(Value::from(Integer::from(1)))
+        );
+        let denominator = interpreter.eval("(denominator 0.75)").unwrap();
+        assert_eq!(denominator, Value::from(Float::with_val(53, 4.0)));
+        assert!(matches!(denominator, Value::Numeric(Numeric::Float(_))));
+
+        assert_eq!(
+            interpreter.eval("(exact 0.25)"),
+            Ok(Value::from(Rational::from((1, 4))))
+        );
+        assert!(matches!(
+            interpreter.eval("(exact 2.0)").unwrap(),
+            Value::Numeric(Numeric::Fixnum(2))
+        ));
+        assert_eq!(
+            interpreter.eval("(inexact 3/4)"),
+            Ok(Value::from(Float::with_val(53, 0.75)))
+        );
+        assert!(matches!(
+            interpreter.eval("(inexact 3)").unwrap(),
+            Value::Numeric(Numeric::Float(_))
+        ));
+        assert_eq!(
+            interpreter.eval("(numerator 'x)").unwrap_err().kind,
+            RuntimeErrorKind::TypeError {
+                expected: "numeric",
+                founded: Value::Symbol("x".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_complex() {
+        let interpreter = Interpreter::new();
+        let complex = |re: f64, im: f64| Value::from(Complex::with_val(53, (re, im)));
+
+        assert_eq!(interpreter.eval("(+ 1+2i 3-i)"), Ok(complex(4.0, 1.0)));
+        assert_eq!(interpreter.eval("(* 1+2i 2)"), Ok(complex(2.0, 4.0)));
+        assert_eq!(interpreter.eval("(/ 1+2i 1/2)"), Ok(complex(2.0, 4.0)));
+        assert_eq!(interpreter.eval("(- +i)"), Ok(complex(0.0, -1.0)));
+        assert_eq!(
+            interpreter.eval("(+ 1+2i 0.5)").unwrap().to_string(),
+            "1.5000000000000000+2.0000000000000000i"
+        );
+        // 虚部为零的结果化为浮点数
+        let square = interpreter.eval("(* +i +i)").unwrap();
+        assert_eq!(square, Value::from(Float::with_val(53, -1)));
+        assert!(matches!(square, Value::Numeric(Numeric::Float(_))));
+        assert_eq!(interpreter.eval("(= 1+2i 1+2i)"), Ok(Value::Bool(true)));
+        assert_eq!(interpreter.eval("(= 1+2i 1)"), Ok(Value::Bool(false)));
+
+        assert_eq!(
+            interpreter.eval("(make-rectangular 3 4)"),
+            Ok(complex(3.0, 4.0))
+        );
+        assert!(matches!(
+            interpreter.eval("(make-rectangular 3 0)").unwrap(),
+            Value::Numeric(Numeric::Fixnum(3))
+        ));
+        assert_eq!(
+            interpreter.eval("(make-polar 2 0)"),
+            Ok(Value::from(Integer::from(2)))
+        );
+        assert_eq!(
+            interpreter.eval("(magnitude (make-polar 2 1))"),
+            Ok(Value::from(Float::with_val(53, 2)))
+        );
+        assert_eq!(
+            interpreter.eval("(real-part 3-4i)"),
+            Ok(Value::from(Float::with_val(53, 3)))
+        );
+        assert_eq!(
+            interpreter.eval("(imag-part 3-4i)"),
+            Ok(Value::from(Float::with_val(53, -4)))
+        );
+        assert_eq!(
+            interpreter.eval("(real-part 5)"),
+            Ok(Value::from(Integer::from(5)))
+        );
+        assert_eq!(
+            interpreter.eval("(imag-part 5)"),
+            Ok(Value::from(Integer::from(0)))
+        );
+        assert_eq!(
+            interpreter.eval("(magnitude 3-4i)"),
+            Ok(Value::from(Float::with_val(53, 5)))
+        );
+        assert_eq!(
+            interpreter.eval("(magnitude -7)"),
+            Ok(Value::from(Integer::from(7)))
+        );
+        assert_eq!(
+            interpreter.eval("(angle +i)"),
+            Ok(Value::from(
+                Float::with_val(53, rug::float::Constant::Pi) / 2
+            ))
+        );
+        assert_eq!(
+            interpreter.eval("(angle -1)"),
+            Ok(Value::from(Float::with_val(53, rug::float::Constant::Pi)))
+        );
+        assert_eq!(
+            interpreter.eval("(angle 1)"),
+            Ok(Value::from(Integer::from(0)))
+        );
+
+        assert_eq!(
+            interpreter
+                .eval("(make-rectangular +i 1)")
+                .unwrap_err()
+                .kind,
+            RuntimeErrorKind::TypeError {
+                expected: "real number",
+                founded: complex(0.0, 1.0),
+            }
+        );
+        assert_eq!(
+            interpreter.eval("(exact 1+i)").unwrap_err().kind,
+            RuntimeErrorKind::TypeError {
+                expected: "finite real number",
+                founded: complex(1.0, 1.0),
+            }
+        );
+        assert_eq!(
+            interpreter.eval("(/ 1+i 0)").unwrap_err().kind,
+            RuntimeErrorKind::DivideByZero
+        );
+    }
+
+    #[test]
+    fn test_precision() {
+        let precision = |value: Value| match value {
+            Value::Numeric(Numeric::Float(f)) => f.prec(),
+            Value::Numeric(Numeric::Complex(c)) => c.prec().0,
+            value => panic!("expected an inexact number, found {value}"),
+        };
+
+        let interpreter = Interpreter::new();
+
+        assert_eq!(precision(interpreter.eval("(/ 1.0 3)").unwrap()), 53);
+        assert_eq!(
+            precision(
+                interpreter
+                    .eval("(with-precision 200 (lambda () (/ 1.0 3)))")
+                    .unwrap()
+            ),
+            200
+        );
+        // 离开动态范围后恢复原来的精度
+        assert_eq!(
+            precision(
+                interpreter
+                    .eval("(with-precision 200 (lambda () 1)) (inexact 1/3)")
+                    .unwrap()
+            ),
+            53
+        );
+        assert_eq!(
+            precision(
+                interpreter
+                    .eval("(with-precision 100 (lambda () (make-polar 1 1)))")
+                    .unwrap()
+            ),
+            100
+        );
+        assert!(matches!(
+            interpreter
+                .eval("(with-precision 0 (lambda () 1))")
+                .unwrap_err()
+                .kind,
+            RuntimeErrorKind::TypeError { .. }
+        ));
+        assert!(matches!(
+            interpreter.eval("(with-precision 64)").unwrap_err().kind,
+            RuntimeErrorKind::InvalidArity { .. }
+        ));
+
+        // 字面量按解释器的精度读取，精度更高的操作数决定结果的精度
+        let interpreter = Interpreter::new().with_precision(128).unwrap();
+        assert_eq!(precision(interpreter.eval("0.1").unwrap()), 128);
+        assert_eq!(interpreter.eval("(= 0.1 (/ 1 10))"), Ok(Value::Bool(false)));
+        assert_eq!(precision(Interpreter::new().eval("0.1").unwrap()), 53);
+        assert_eq!(
+            precision(
+                interpreter
+                    .eval("(define x 0.1) (with-precision 64 (lambda () (+ x 1)))")
+                    .unwrap()
+            ),
+            128
+        );
+
+        // 精度在执行结束后恢复，即使错误从 `with-precision` 中逃出
+        assert!(interpreter
+            .eval("(with-precision 200 (lambda () (car 1)))")
+            .is_err());
+        assert_eq!(Numeric::precision(), Numeric::DEFAULT_PRECISION);
+        assert_eq!(
+            precision(
+                interpreter
+                    .eval("(guard (e (#t (inexact 1/3))) (with-precision 200 (lambda () (car 1))))")
+                    .unwrap()
+            ),
+            128
+        );
+        assert!(Interpreter::new().with_precision(0).is_err());
+        assert!(Numeric::set_precision(0).is_err());
+        assert_eq!(Numeric::precision(), Numeric::DEFAULT_PRECISION);
+    }
 }
@@ -3,7 +3,7 @@
 mod tests {
     use lemon_lisp::{
         lexer::TokenStream,
-        model::{Token::*, TokenizeError},
+        model::{Span, Spanned, Token::*, TokenizeError, TokenizeErrorKind},
     };
     use rug::Float;
 
@@ -16,7 +16,11 @@ mod tests {
                 ];
                 for (input, expected) in test_data {
                     let token_stream = TokenStream::new(input);
-                    assert_eq!(token_stream.tokenize(), expected);
+                    let tokens = token_stream
+                        .tokenize()
+                        .map(|tokens| tokens.into_iter().map(|token| token.node).collect::<Vec<_>>())
+                        .map_err(|err| err.kind);
+                    assert_eq!(tokens, expected);
                 }
             }
         }
@@ -29,6 +33,26 @@ mod tests {
         "+5" => Ok(vec![Integer(5.into())]),
     }
 
+    test_lexer! {
+        test_rational,
+        "3/4" => Ok(vec![Rational(rug::Rational::from((3, 4)))]),
+        "-6/4" => Ok(vec![Rational(rug::Rational::from((-3, 2)))]),
+        "1/0" => Ok(vec![Symbol("1/0".into())]),
+        "/" => Ok(vec![Symbol("/".into())]),
+    }
+
+    test_lexer! {
+        test_complex,
+        "1+2i" => Ok(vec![Complex(rug::Complex::with_val(53, (1, 2)))]),
+        "1.5-2.5i" => Ok(vec![Complex(rug::Complex::with_val(53, (1.5, -2.5)))]),
+        "+i" => Ok(vec![Complex(rug::Complex::with_val(53, (0, 1)))]),
+        "-3i" => Ok(vec![Complex(rug::Complex::with_val(53, (0, -3)))]),
+        "1e+2-1e-2i" => Ok(vec![Complex(rug::Complex::with_val(53, (100, -0.01)))]),
+        "2i" => Ok(vec![Symbol("2i".into())]),
+        "pi" => Ok(vec![Symbol("pi".into())]),
+        "a-i" => Ok(vec![Symbol("a-i".into())]),
+    }
+
     test_lexer! {
         test_float,
         "1.2" => Ok(vec![Float(Float::with_val(53, 1.2))]),
@@ -53,6 +77,25 @@ mod tests {
         "(quote x)" => Ok(vec![LParen, Symbol("quote".into()), Symbol("x".into()), RParen]),
     );
 
+    test_lexer!(
+        test_quasiquote,
+        "`(a ,b ,@c)" => Ok(vec![
+            Quasiquote, LParen, Symbol("a".into()), Unquote, Symbol("b".into()),
+            UnquoteSplicing, Symbol("c".into()), RParen,
+        ]),
+        "`(,@ a , @b)" => Ok(vec![
+            Quasiquote, LParen, UnquoteSplicing, Symbol("a".into()),
+            Unquote, Symbol("@b".into()), RParen,
+        ]),
+    );
+
+    test_lexer!(
+        test_dot,
+        "(a . b)" => Ok(vec![LParen, Symbol("a".into()), Dot, Symbol("b".into()), RParen]),
+        "(a .b)" => Ok(vec![LParen, Symbol("a".into()), Symbol(".b".into()), RParen]),
+        "(1 . .5)" => Ok(vec![LParen, Integer(1.into()), Dot, Float(Float::with_val(53, 0.5)), RParen]),
+    );
+
     test_lexer!(
         test_area_of_a_circle,
         "(define r 10) (define pi 3.14) (* pi (* r r))" =>  Ok(vec![
@@ -79,19 +122,56 @@ mod tests {
 
     test_lexer!(
         test_unexpected_char,
-        "(let ([x 1] {y 2.3}) (+ x y))" => Err(TokenizeError::UnexpectedChar('{')),
-        "(define a,b 2)" => Err(TokenizeError::UnexpectedChar(',')),
-        "(define `a 2)" => Err(TokenizeError::UnexpectedChar('`')),
-        "(define a|b 3)" => Err(TokenizeError::UnexpectedChar('|')),
+        "(let ([x 1] {y 2.3}) (+ x y))" => Err(TokenizeErrorKind::UnexpectedChar('{')),
+        "(define a,b 2)" => Err(TokenizeErrorKind::UnexpectedChar(',')),
+        "(define a`b 2)" => Err(TokenizeErrorKind::UnexpectedChar('`')),
+        "(define a|b 3)" => Err(TokenizeErrorKind::UnexpectedChar('|')),
     );
 
     test_lexer!(
         test_unclosed_string,
-        r#" "Hello "# => Err(TokenizeError::UnclosedString),
-        r#" "Hello" "NAVI "# => Err(TokenizeError::UnclosedString),
-        r#" "Hello, NAVI\" "# => Err(TokenizeError::UnclosedString),
+        r#" "Hello "# => Err(TokenizeErrorKind::UnclosedString),
+        r#" "Hello" "NAVI "# => Err(TokenizeErrorKind::UnclosedString),
+        r#" "Hello, NAVI\" "# => Err(TokenizeErrorKind::UnclosedString),
         r#" "Hello
              NAVI
-        "# => Err(TokenizeError::UnclosedString),
+        "# => Err(TokenizeErrorKind::UnclosedString),
     );
+
+    #[test]
+    fn test_token_span() {
+        let token_stream = TokenStream::new("(define x\n  \"é\")");
+
+        assert_eq!(
+            token_stream.tokenize(),
+            Ok(vec![
+                Spanned::new(LParen, Span::new(0, 1, 1, 1)),
+                Spanned::new(Symbol("define".into()), Span::new(1, 7, 1, 2)),
+                Spanned::new(Symbol("x".into()), Span::new(8, 9, 1, 9)),
+                Spanned::new(String("é".into()), Span::new(12, 16, 2, 3)),
+                Spanned::new(RParen, Span::new(16, 17, 2, 6)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_error_span() {
+        let token_stream = TokenStream::new("(a\n b|c)");
+        assert_eq!(
+            token_stream.tokenize(),
+            Err(TokenizeError::new(
+                TokenizeErrorKind::UnexpectedChar('|'),
+                Span::new(5, 6, 2, 3)
+            ))
+        );
+
+        let token_stream = TokenStream::new(r#"(print "Hello)"#);
+        assert_eq!(
+            token_stream.tokenize(),
+            Err(TokenizeError::new(
+                TokenizeErrorKind::UnclosedString,
+                Span::new(7, 14, 1, 8)
+            ))
+        );
+    }
 }
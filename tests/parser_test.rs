@@ -4,12 +4,13 @@ mod test {
     use lemon_lisp::{
         lexer::TokenStream,
         model::{
-            Keyword, ParseError, Token, TokenizeError,
+            Pair, ParseError, ParseErrorKind, Span, Token, TokenizeErrorKind,
             Value::{self, *},
         },
         parser::Parser,
     };
     use rug::{Float, Integer};
+    use std::rc::Rc;
 
     macro_rules! test_parser {
         ($name:ident, $($input:expr => $expected:expr),* $(,)?) => {
@@ -21,7 +22,7 @@ mod test {
                 for (input, expected) in test_data {
                     let token_stream = TokenStream::new(input);
                     let mut parser = Parser::new(token_stream);
-                    assert_eq!(parser.parse(), expected);
+                    assert_eq!(parser.parse().map_err(ParseError::into_kind), expected);
                 }
             }
         }
@@ -31,15 +32,15 @@ mod test {
         test_area_of_a_circle,
         "(define r 10) (define pi 3.14) (* pi (* r r))" => Ok(vec![
             List(vec![
-                Keyword(Keyword::Define),
+                Symbol("define".into()),
                 Symbol("r".into()),
                 Value::from(Integer::from(10)),
-            ]),
+            ].into()),
             List(vec![
-                Keyword(Keyword::Define),
+                Symbol("define".into()),
                 Symbol("pi".into()),
                 Value::from(Float::with_val(53, 3.140)),
-            ]),
+            ].into()),
             List(vec![
                 Symbol("*".into()),
                 Symbol("pi".into()),
@@ -47,8 +48,8 @@ mod test {
                     Symbol("*".into()),
                     Symbol("r".into()),
                     Symbol("r".into()),
-                ]),
-            ]),
+                ].into()),
+            ].into()),
         ])
     );
 
@@ -56,32 +57,33 @@ mod test {
         test_quote,
         "(define a '(1 2 3))" => Ok(vec![
             List(vec![
-                Keyword(Keyword::Define),
+                Symbol("define".into()),
                 Symbol("a".into()),
-                Quoted(Box::new(
+                List(vec![
+                    Symbol("quote".into()),
                     List(vec![
                         Value::from(Integer::from(1)),
                         Value::from(Integer::from(2)),
                         Value::from(Integer::from(3)),
-                    ])
-                )),
-            ])
+                    ].into()),
+                ].into()),
+            ].into())
         ]),
-        // 调用 quote 函数 和使用 ' 符号不同
-        // ' 符号在语法解析阶段就会处理为对应的 Object
-        // 而 (quote something) 通过调用函数的方式返回对应的 Object
+        // ' 符号只是 (quote something) 的简写，两者解析为相同的列表
         r"(quote (1 2 '3))" => Ok(vec![
             List(vec![
                 Symbol("quote".into()),
                 List(vec![
                         Value::from(Integer::from(1)),
                         Value::from(Integer::from(2)),
-                        Quoted(Box::new(
+                        List(vec![
+                            Symbol("quote".into()),
                             Value::from(Integer::from(3)),
-                        )),
-                    ]),
-            ])
-        ])
+                        ].into()),
+                    ].into()),
+            ].into())
+        ]),
+        "'" => Err(ParseErrorKind::UnexpectedEOF),
     );
 
     test_parser!(
@@ -90,45 +92,140 @@ mod test {
              (print (string-append "Hello, " name))
              name)"# => Ok(vec![
             List(vec![
-                Keyword(Keyword::Define),
+                Symbol("define".into()),
                 List(vec![
                     Symbol("greet".into()),
                     Symbol("name".into())
-                ]),
+                ].into()),
                 List(vec![
                     Symbol("print".into()),
                     List(vec![
                         Symbol("string-append".into()),
                         String("Hello, ".into()),
                         Symbol("name".into())
-                    ])
-                ]),
+                    ].into())
+                ].into()),
                 Symbol("name".into())
-            ])
+            ].into())
         ])
     );
 
+    test_parser!(
+        test_quasiquote,
+        "`(a ,b ,@c)" => Ok(vec![
+            List(vec![
+                Symbol("quasiquote".into()),
+                List(vec![
+                    Symbol("a".into()),
+                    List(vec![Symbol("unquote".into()), Symbol("b".into())].into()),
+                    List(vec![Symbol("unquote-splicing".into()), Symbol("c".into())].into()),
+                ].into()),
+            ].into())
+        ]),
+        "(quasiquote ,x)" => Ok(vec![
+            List(vec![
+                Symbol("quasiquote".into()),
+                List(vec![Symbol("unquote".into()), Symbol("x".into())].into()),
+            ].into())
+        ]),
+        "`" => Err(ParseErrorKind::UnexpectedEOF),
+    );
+
+    test_parser!(
+        test_dotted_pair,
+        "(1 . 2)" => Ok(vec![
+            Value::Pair(Rc::new(Pair::new(
+                Value::from(Integer::from(1)),
+                Value::from(Integer::from(2)),
+            )))
+        ]),
+        "(a b . c)" => Ok(vec![
+            Value::Pair(Rc::new(Pair::new(
+                Symbol("a".into()),
+                Value::Pair(Rc::new(Pair::new(Symbol("b".into()), Symbol("c".into())))),
+            )))
+        ]),
+        // 点号之后是列表时与正规列表相等
+        "(a . (b c))" => Ok(vec![
+            List(vec![Symbol("a".into()), Symbol("b".into()), Symbol("c".into())].into())
+        ]),
+    );
+
+    test_parser!(
+        test_invalid_dot,
+        "(. a)" => Err(ParseErrorKind::InvalidSyntax(Token::Dot)),
+        "(a .)" => Err(ParseErrorKind::InvalidSyntax(Token::Dot)),
+        "(a . b c)" => Err(ParseErrorKind::UnexpectedToken {
+            expected: Token::RParen,
+            found: Token::Symbol("c".into()),
+        }),
+        "a . b" => Err(ParseErrorKind::InvalidSyntax(Token::Dot)),
+    );
+
     test_parser!(
         test_missing_token,
-        r#"(print "Hello NAVI""# => Err(ParseError::MissingToken(Token::RParen))
+        r#"(print "Hello NAVI""# => Err(ParseErrorKind::MissingToken(Token::RParen))
     );
 
     test_parser!(
         test_invalid_syntax,
-        r#"(print "Hello NAVI"))"# => Err(ParseError::InvalidSyntax(Token::RParen)),
+        r#"(print "Hello NAVI"))"# => Err(ParseErrorKind::InvalidSyntax(Token::RParen)),
     );
 
     test_parser!(
         test_invalid_digit,
-        "#b02" => Err(ParseError::InvalidDigit("02".into())),
-        "#d10a" => Err(ParseError::InvalidDigit("10a".into())),
-        "#o459" => Err(ParseError::InvalidDigit("459".into())),
-        "#xf1g" => Err(ParseError::InvalidDigit("f1g".into())),
+        "#b02" => Err(ParseErrorKind::InvalidDigit("02".into())),
+        "#d10a" => Err(ParseErrorKind::InvalidDigit("10a".into())),
+        "#o459" => Err(ParseErrorKind::InvalidDigit("459".into())),
+        "#xf1g" => Err(ParseErrorKind::InvalidDigit("f1g".into())),
     );
 
     test_parser!(
         test_lexical_error,
-        "a|b" => Err(ParseError::LexicalError(TokenizeError::UnexpectedChar('|'))),
-        r#" "Hello HAVI "# => Err(ParseError::LexicalError(TokenizeError::UnclosedString)),
+        "a|b" => Err(ParseErrorKind::LexicalError(TokenizeErrorKind::UnexpectedChar('|'))),
+        r#" "Hello HAVI "# => Err(ParseErrorKind::LexicalError(TokenizeErrorKind::UnclosedString)),
     );
+
+    #[test]
+    fn test_value_span() {
+        let token_stream = TokenStream::new("(define (f x)\n  (g x))");
+        let mut parser = Parser::new(token_stream);
+        let values = parser.parse().unwrap();
+
+        let List(define) = &values[0] else {
+            panic!("Expected a list");
+        };
+        assert_eq!(Some(Span::new(0, 22, 1, 1)), define.span());
+        assert_eq!(Some(Span::new(8, 13, 1, 9)), define[1].span());
+        assert_eq!(Some(Span::new(16, 21, 2, 3)), define[2].span());
+
+        let List(body) = &define[2] else {
+            panic!("Expected a list");
+        };
+        assert_eq!(Some(Span::new(17, 18, 2, 4)), body[0].span());
+        assert_eq!(Some(Span::new(19, 20, 2, 6)), body[1].span());
+    }
+
+    #[test]
+    fn test_error_span() {
+        let token_stream = TokenStream::new("(print\n  (f 1)");
+        let mut parser = Parser::new(token_stream);
+        assert_eq!(
+            parser.parse(),
+            Err(ParseError::new(
+                ParseErrorKind::MissingToken(Token::RParen),
+                Span::new(0, 14, 1, 1)
+            ))
+        );
+
+        let token_stream = TokenStream::new("(f) #b12");
+        let mut parser = Parser::new(token_stream);
+        assert_eq!(
+            parser.parse(),
+            Err(ParseError::new(
+                ParseErrorKind::InvalidDigit("12".into()),
+                Span::new(4, 8, 1, 5)
+            ))
+        );
+    }
 }